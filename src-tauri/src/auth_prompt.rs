@@ -0,0 +1,124 @@
+// 键盘交互认证（keyboard-interactive）的提示处理：密码提示直接用保存的密码回答，
+// 其它提示（例如动态验证码）通过 ssh-auth-prompt 事件发给前端，等待用户输入后继续认证
+use crate::error::EasyLogError;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use serde::Serialize;
+use ssh2::{KeyboardInteractivePrompt, Prompt};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// 等待用户输入的最长时间，服务器通常在两分钟后断开未完成认证的连接
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+lazy_static! {
+    // 等待前端回答的提示，键为请求id
+    static ref PENDING_PROMPTS: Mutex<HashMap<String, mpsc::Sender<Option<Vec<String>>>>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_PROMPT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Serialize, Clone)]
+struct PromptItem {
+    text: String,
+    echo: bool, // 是否可以显示输入的内容，密码和验证码通常为false
+}
+
+// ssh-auth-prompt 事件的数据
+#[derive(Debug, Serialize, Clone)]
+struct AuthPromptRequest {
+    request_id: String,
+    host: String,
+    username: String,
+    instructions: String,
+    prompts: Vec<PromptItem>,
+}
+
+pub struct InteractivePrompter {
+    host: String,
+    password: Option<String>,
+}
+
+impl InteractivePrompter {
+    pub fn new(host: &str, password: Option<String>) -> Self {
+        InteractivePrompter {
+            host: host.to_string(),
+            password: password.filter(|p| !p.is_empty()),
+        }
+    }
+
+    // 发送事件并等待前端回答，用户取消或超时时返回None
+    fn ask_user(&self, username: &str, instructions: &str, prompts: &[Prompt]) -> Option<Vec<String>> {
+        let request_id = NEXT_PROMPT_ID.fetch_add(1, Ordering::Relaxed).to_string();
+        let (sender, receiver) = mpsc::channel();
+        PENDING_PROMPTS.lock().unwrap().insert(request_id.clone(), sender);
+
+        let request = AuthPromptRequest {
+            request_id: request_id.clone(),
+            host: self.host.clone(),
+            username: username.to_string(),
+            instructions: instructions.to_string(),
+            prompts: prompts
+                .iter()
+                .map(|prompt| PromptItem {
+                    text: prompt.text.to_string(),
+                    echo: prompt.echo,
+                })
+                .collect(),
+        };
+        let answer = if crate::emit_app_event("ssh-auth-prompt", request) {
+            receiver.recv_timeout(PROMPT_TIMEOUT).ok().flatten()
+        } else {
+            None
+        };
+
+        PENDING_PROMPTS.lock().unwrap().remove(&request_id);
+        if answer.is_none() {
+            warn!("Keyboard-interactive prompt for {} was cancelled or timed out", self.host);
+        }
+        answer
+    }
+}
+
+impl KeyboardInteractivePrompt for InteractivePrompter {
+    fn prompt<'a>(&mut self, username: &str, instructions: &str, prompts: &[Prompt<'a>]) -> Vec<String> {
+        if prompts.is_empty() {
+            return Vec::new();
+        }
+
+        // 只有一个不回显的密码提示时使用保存的密码，只使用一次，密码错误时再询问用户
+        if let [prompt] = prompts {
+            if !prompt.echo && prompt.text.to_lowercase().contains("password") {
+                if let Some(password) = self.password.take() {
+                    return vec![password];
+                }
+            }
+        }
+
+        let mut responses = self.ask_user(username, instructions, prompts).unwrap_or_default();
+        responses.resize(prompts.len(), String::new());
+        responses
+    }
+}
+
+// 前端回答键盘交互认证的提示，responses为空表示取消；
+// 异步命令不在主线程上执行，主线程上等待认证的命令不会挡住回答
+#[tauri::command]
+pub async fn answer_ssh_auth_prompt(request_id: String, responses: Option<Vec<String>>) -> Result<(), EasyLogError> {
+    let sender = PENDING_PROMPTS
+        .lock()
+        .unwrap()
+        .remove(&request_id)
+        .ok_or_else(|| expired_prompt(&request_id))?;
+    info!("Received answer for keyboard-interactive prompt {}", request_id);
+    sender
+        .send(responses)
+        .map_err(|_| expired_prompt(&request_id))
+}
+
+fn expired_prompt(request_id: &str) -> EasyLogError {
+    EasyLogError::invalid("request_id", format!("认证提示已失效: {}", request_id))
+}
@@ -0,0 +1,294 @@
+// 压缩日志的透明解压：根据文件头的魔数识别 gzip / zstd / bzip2 / xz，
+// 本地文件解压到缓存目录后按普通文件读取（支持索引和分页），远程数据流则边读边解压
+use lazy_static::lazy_static;
+use log::{info, warn};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+// 识别压缩格式需要的最大文件头长度
+const MAGIC_LEN: usize = 6;
+// 解压缓存目录的总大小上限，启动清理时超出的部分从最早解压的文件开始删除
+const CACHE_MAX_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+// 启动时删除超过这个时间的解压文件
+const CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+// 正在写入的临时文件的前缀；超过这个时间还没有完成的视为上次中断留下的
+const PARTIAL_PREFIX: &str = ".partial-";
+const PARTIAL_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+// 根据文件头识别压缩格式
+pub fn detect_compression(header: &[u8]) -> Compression {
+    if header.starts_with(&[0x1F, 0x8B]) {
+        Compression::Gzip
+    } else if header.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+        Compression::Zstd
+    } else if header.starts_with(b"BZh") {
+        Compression::Bzip2
+    } else if header.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+        Compression::Xz
+    } else {
+        Compression::None
+    }
+}
+
+// 根据扩展名判断是否为压缩文件，用于读取前选择远程命令
+pub fn is_compressed_path(path: &str) -> bool {
+    [".gz", ".zst", ".bz2", ".xz"].iter().any(|ext| path.ends_with(ext))
+}
+
+// 按压缩格式包装reader，多段压缩（例如多次追加的gzip）会被完整解压
+pub fn decompress_reader<'a, R: Read + 'a>(reader: R, compression: Compression) -> std::io::Result<Box<dyn Read + 'a>> {
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+        Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+    })
+}
+
+// 先读取数据流开头的几个字节识别压缩格式，再把这些字节拼回去交给解压器
+pub fn decompress_stream<'a, R: Read + 'a>(mut reader: R) -> std::io::Result<(Box<dyn Read + 'a>, Compression)> {
+    let mut header = Vec::with_capacity(MAGIC_LEN);
+    (&mut reader).take(MAGIC_LEN as u64).read_to_end(&mut header)?;
+    let compression = detect_compression(&header);
+    let reader = decompress_reader(Cursor::new(header).chain(reader), compression)?;
+    Ok((reader, compression))
+}
+
+// 已解压的本地文件缓存，记录源文件的长度和修改时间用于判断是否失效
+#[derive(Debug, Clone)]
+struct CachedDecompression {
+    len: u64,
+    modified: Option<SystemTime>,
+    path: PathBuf,
+}
+
+lazy_static! {
+    static ref DECOMPRESSED_CACHE: Mutex<HashMap<PathBuf, CachedDecompression>> = Mutex::new(HashMap::new());
+}
+
+fn cache_dir() -> PathBuf {
+    std::env::temp_dir().join("easylog-decompressed")
+}
+
+// 返回可以直接按普通文件读取的路径：未压缩的文件原样返回，压缩文件解压到缓存目录
+pub fn materialize(path: &Path) -> std::io::Result<PathBuf> {
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let decompressed = materialize_stream(path, std::io::BufReader::new(file), metadata.len(), metadata.modified().ok())?;
+    Ok(decompressed.unwrap_or_else(|| path.to_path_buf()))
+}
+
+// 把压缩的数据流解压到缓存目录，数据没有压缩时返回None。
+// key用于缓存（本地文件为路径，远程文件为 sftp:// 地址），len和modified用于判断缓存是否失效
+pub fn materialize_stream<R: Read>(
+    key: &Path,
+    mut reader: R,
+    len: u64,
+    modified: Option<SystemTime>,
+) -> std::io::Result<Option<PathBuf>> {
+    if let Some(cached) = DECOMPRESSED_CACHE.lock().unwrap().get(key) {
+        if cached.len == len && cached.modified == modified && cached.path.exists() {
+            return Ok(Some(cached.path.clone()));
+        }
+    }
+
+    let mut header = Vec::with_capacity(MAGIC_LEN);
+    (&mut reader).take(MAGIC_LEN as u64).read_to_end(&mut header)?;
+    let compression = detect_compression(&header);
+    if compression == Compression::None {
+        return Ok(None);
+    }
+
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    let file_name = key.file_name().and_then(|n| n.to_str()).unwrap_or("log");
+    std::fs::create_dir_all(cache_dir())?;
+    let target = cache_dir().join(format!("{:016x}-{}.decompressed", hasher.finish(), file_name));
+
+    info!("Decompressing {:?} ({:?}) to {:?}", key, compression, target);
+    // 先写入单独的临时文件再重命名，避免中途失败留下不完整的缓存；
+    // 同一个文件被同时打开（例如查看和搜索）时各自写入自己的临时文件，不会互相覆盖
+    let mut reader = decompress_reader(Cursor::new(header).chain(reader), compression)?;
+    let mut output = tempfile::Builder::new().prefix(PARTIAL_PREFIX).tempfile_in(cache_dir())?;
+    std::io::copy(&mut reader, &mut output)?;
+    output.persist(&target).map_err(|e| e.error)?;
+
+    DECOMPRESSED_CACHE.lock().unwrap().insert(
+        key.to_path_buf(),
+        CachedDecompression {
+            len,
+            modified,
+            path: target.clone(),
+        },
+    );
+
+    Ok(Some(target))
+}
+
+// 缓存目录中的文件：(路径, 大小, 修改时间)
+fn cache_entries() -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(entries) = std::fs::read_dir(cache_dir()) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+        })
+        .collect()
+}
+
+fn is_partial(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with(PARTIAL_PREFIX))
+}
+
+fn remove_cached(path: &Path) {
+    match std::fs::remove_file(path) {
+        Ok(()) => info!("Removed decompressed cache file {:?}", path),
+        // 文件可能正在被读取（Windows上无法删除），下次清理时再删除
+        Err(e) => warn!("Failed to remove decompressed cache file {:?}: {}", path, e),
+    }
+}
+
+// 当前进程已经解压并可能正在读取的缓存文件
+fn in_use(path: &Path) -> bool {
+    DECOMPRESSED_CACHE
+        .lock()
+        .unwrap()
+        .values()
+        .any(|cached| cached.path == path)
+}
+
+// 启动时清理解压缓存：删除过期的解压文件和上次中断留下的临时文件，
+// 再把缓存目录控制在大小上限以内。运行期间不删除缓存，避免删掉正在被索引或监控读取的文件；
+// 清理与启动后的第一次读取同时进行，所以已被本进程使用的文件同样跳过
+pub fn clean_cache() {
+    let now = SystemTime::now();
+    let mut remaining = Vec::new();
+    for (path, len, modified) in cache_entries() {
+        let age = now.duration_since(modified).unwrap_or_default();
+        let max_age = if is_partial(&path) { PARTIAL_MAX_AGE } else { CACHE_MAX_AGE };
+        if age >= max_age && !in_use(&path) {
+            remove_cached(&path);
+        } else {
+            remaining.push((path, len, modified));
+        }
+    }
+    enforce_cache_limit(remaining);
+}
+
+// 缓存目录超过大小上限时从最早解压的文件开始删除，正在写入的临时文件和本进程使用中的文件除外
+fn enforce_cache_limit(mut entries: Vec<(PathBuf, u64, SystemTime)>) {
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    entries.sort_by_key(|(_, _, modified)| *modified);
+    for (path, len, _) in entries {
+        if total <= CACHE_MAX_BYTES {
+            break;
+        }
+        if is_partial(&path) || in_use(&path) {
+            continue;
+        }
+        remove_cached(&path);
+        total -= len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn detects_compression_by_magic() {
+        assert_eq!(detect_compression(&gzip(b"x")), Compression::Gzip);
+        assert_eq!(detect_compression(&[0x28, 0xB5, 0x2F, 0xFD, 0x00]), Compression::Zstd);
+        assert_eq!(detect_compression(b"BZh91AY"), Compression::Bzip2);
+        assert_eq!(detect_compression(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]), Compression::Xz);
+        assert_eq!(detect_compression(b"2024-01-01 INFO"), Compression::None);
+        assert_eq!(detect_compression(&[0x1F]), Compression::None);
+        assert_eq!(detect_compression(b""), Compression::None);
+    }
+
+    #[test]
+    fn decompresses_stream_and_passes_plain_data_through() {
+        let (mut reader, compression) = decompress_stream(Cursor::new(gzip(b"line\n"))).unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(compression, Compression::Gzip);
+        assert_eq!(text, "line\n");
+
+        let (mut reader, compression) = decompress_stream(Cursor::new(b"plain\n".to_vec())).unwrap();
+        let mut text = String::new();
+        reader.read_to_string(&mut text).unwrap();
+        assert_eq!(compression, Compression::None);
+        assert_eq!(text, "plain\n");
+    }
+
+    #[test]
+    fn materialize_stream_reuses_and_invalidates_cache() {
+        let key = Path::new("test://materialize_stream_reuses_and_invalidates_cache/app.log.gz");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let first = gzip(b"first\n");
+
+        let path = materialize_stream(key, Cursor::new(&first), first.len() as u64, Some(modified))
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"first\n");
+
+        // 长度和修改时间不变时直接使用缓存，不再读取数据流
+        let mut untouched = Cursor::new(b"not read".to_vec());
+        let cached = materialize_stream(key, &mut untouched, first.len() as u64, Some(modified))
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached, path);
+        assert_eq!(untouched.position(), 0);
+
+        // 修改时间变化后重新解压
+        let second = gzip(b"second\n");
+        let changed = modified + Duration::from_secs(1);
+        let path = materialize_stream(key, Cursor::new(&second), first.len() as u64, Some(changed))
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second\n");
+
+        // 长度变化后同样重新解压
+        let third = gzip(b"third, longer\n");
+        let path = materialize_stream(key, Cursor::new(&third), third.len() as u64, Some(changed))
+            .unwrap()
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"third, longer\n");
+        assert!(in_use(&path));
+    }
+
+    #[test]
+    fn materialize_stream_ignores_plain_data() {
+        let key = Path::new("test://materialize_stream_ignores_plain_data/app.log");
+        let result = materialize_stream(key, Cursor::new(b"plain\n".to_vec()), 6, None).unwrap();
+        assert!(result.is_none());
+    }
+}
@@ -0,0 +1,257 @@
+// 日志编码识别与解码：按 BOM -> UTF-16 -> UTF-8 -> GBK/GB18030 -> Latin-1 的顺序识别，
+// 解码时遇到非法字节使用替换字符，保证任何编码问题都不会导致读取失败
+use crate::error::EasyLogError;
+use encoding_rs::{Decoder, Encoding, GB18030, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+// 编码识别时采样的最大字节数
+pub const DETECT_SAMPLE_SIZE: usize = 64 * 1024;
+
+// 识别出的编码以及文件开头BOM的长度
+#[derive(Debug, Clone, Copy)]
+pub struct DetectedEncoding {
+    pub encoding: &'static Encoding,
+    pub bom_len: usize,
+}
+
+impl DetectedEncoding {
+    pub fn name(&self) -> &'static str {
+        self.encoding.name()
+    }
+}
+
+// 调用方没有指定编码（为空或"auto"），需要根据内容识别
+pub fn is_auto(label: Option<&str>) -> bool {
+    matches!(label.map(str::trim), None | Some("") | Some("auto"))
+}
+
+// 根据调用方指定的编码（为空或"auto"时自动识别）确定解码使用的编码
+pub fn resolve_encoding(label: Option<&str>, sample: &[u8]) -> Result<DetectedEncoding, EasyLogError> {
+    let detected = detect_encoding(sample);
+    let label = match label.map(str::trim) {
+        Some(label) if !is_auto(Some(label)) => label,
+        _ => return Ok(detected),
+    };
+
+    let encoding = Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| EasyLogError::Encoding {
+            encoding: label.to_string(),
+        })?;
+    // 指定编码与BOM一致时仍需要跳过BOM
+    let bom_len = if detected.encoding == encoding { detected.bom_len } else { 0 };
+    Ok(DetectedEncoding { encoding, bom_len })
+}
+
+// 根据采样内容识别编码
+pub fn detect_encoding(sample: &[u8]) -> DetectedEncoding {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(sample) {
+        return DetectedEncoding { encoding, bom_len };
+    }
+
+    let encoding = if let Some(utf16) = detect_utf16(sample) {
+        utf16
+    } else if is_utf8(sample) {
+        UTF_8
+    } else if is_gb18030(sample) {
+        GB18030
+    } else {
+        // encoding_rs 中 Latin-1 (ISO-8859-1) 按 WHATWG 规范映射为 windows-1252
+        WINDOWS_1252
+    };
+
+    DetectedEncoding { encoding, bom_len: 0 }
+}
+
+// 没有BOM的UTF-16：ASCII字符占多数的日志中，每两个字节就有一个0
+fn detect_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+    let units = sample.len() / 2;
+    if units < 2 {
+        return None;
+    }
+
+    let even_zeros = sample.iter().step_by(2).take(units).filter(|&&b| b == 0).count();
+    let odd_zeros = sample.iter().skip(1).step_by(2).take(units).filter(|&&b| b == 0).count();
+
+    if odd_zeros * 10 >= units * 3 && even_zeros * 10 < units {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 >= units * 3 && odd_zeros * 10 < units {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+// 采样末尾可能截断在多字节字符中间，这种情况仍视为合法的UTF-8
+fn is_utf8(sample: &[u8]) -> bool {
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn is_gb18030(sample: &[u8]) -> bool {
+    // 去掉末尾可能被截断的字符（GB18030最长4字节）再检查
+    let trimmed = &sample[..sample.len().saturating_sub(3)];
+    [sample, trimmed].iter().any(|s| {
+        !s.is_empty()
+            && GB18030
+                .decode_without_bom_handling_and_without_replacement(s)
+                .is_some()
+    })
+}
+
+// 一次性解码，非法字节替换为U+FFFD
+pub fn decode_lossy(encoding: &'static Encoding, bytes: &[u8]) -> String {
+    encoding.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+// 流式解码器：分块读取时，跨块边界被截断的多字节字符会保留到下一块再解码
+pub struct StreamDecoder {
+    label: Option<String>,
+    decoder: Option<Decoder>,
+    encoding: Option<&'static Encoding>,
+}
+
+impl StreamDecoder {
+    // label为空或"auto"时，使用收到的第一块数据识别编码
+    pub fn new(label: Option<&str>) -> Result<Self, EasyLogError> {
+        // 提前校验指定的编码是否受支持
+        resolve_encoding(label, &[])?;
+        Ok(StreamDecoder {
+            label: label.map(str::to_string),
+            decoder: None,
+            encoding: None,
+        })
+    }
+
+    pub fn with_encoding(encoding: &'static Encoding) -> Self {
+        StreamDecoder {
+            label: None,
+            decoder: Some(encoding.new_decoder_with_bom_removal()),
+            encoding: Some(encoding),
+        }
+    }
+
+    // 输入一块数据，返回能够完整解码的文本；last为true表示数据已结束
+    pub fn feed(&mut self, bytes: &[u8], last: bool) -> String {
+        if self.decoder.is_none() {
+            if bytes.is_empty() && !last {
+                return String::new();
+            }
+
+            let encoding = resolve_encoding(self.label.as_deref(), bytes)
+                .map(|d| d.encoding)
+                .unwrap_or(UTF_8);
+            self.encoding = Some(encoding);
+            self.decoder = Some(encoding.new_decoder_with_bom_removal());
+        }
+
+        self.decode(bytes, last)
+    }
+
+    fn decode(&mut self, bytes: &[u8], last: bool) -> String {
+        let decoder = self.decoder.as_mut().expect("decoder initialized");
+        let mut output = String::with_capacity(
+            decoder
+                .max_utf8_buffer_length(bytes.len())
+                .unwrap_or(bytes.len() * 3),
+        );
+        let (_, _, _) = decoder.decode_to_string(bytes, &mut output, last);
+        output
+    }
+
+    // 文件被截断或轮转后重新开始解码（保留已识别的编码）
+    pub fn reset(&mut self) {
+        if let Some(encoding) = self.encoding {
+            self.decoder = Some(encoding.new_decoder_with_bom_removal());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect()
+    }
+
+    fn utf16be(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|u| u.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn detects_bom() {
+        let detected = detect_encoding(b"\xEF\xBB\xBFhello");
+        assert_eq!(detected.encoding, UTF_8);
+        assert_eq!(detected.bom_len, 3);
+
+        let detected = detect_encoding(b"\xFF\xFEh\0i\0");
+        assert_eq!(detected.encoding, UTF_16LE);
+        assert_eq!(detected.bom_len, 2);
+
+        let detected = detect_encoding(b"\xFE\xFF\0h\0i");
+        assert_eq!(detected.encoding, UTF_16BE);
+        assert_eq!(detected.bom_len, 2);
+    }
+
+    #[test]
+    fn detects_utf16_without_bom() {
+        let text = "2024-01-01 INFO started\n";
+        assert_eq!(detect_encoding(&utf16le(text)).encoding, UTF_16LE);
+        assert_eq!(detect_encoding(&utf16be(text)).encoding, UTF_16BE);
+        assert_eq!(detect_encoding(&utf16le(text)).bom_len, 0);
+    }
+
+    #[test]
+    fn detects_utf8_and_ascii() {
+        assert_eq!(detect_encoding(b"plain ascii\n").encoding, UTF_8);
+        assert_eq!(detect_encoding("中文日志\n".as_bytes()).encoding, UTF_8);
+        // 采样截断在多字节字符中间
+        let bytes = "日志".as_bytes();
+        assert_eq!(detect_encoding(&bytes[..bytes.len() - 1]).encoding, UTF_8);
+    }
+
+    #[test]
+    fn detects_gb18030() {
+        let (bytes, _, _) = GB18030.encode("错误：连接超时\n");
+        assert_eq!(detect_encoding(&bytes).encoding, GB18030);
+        // 末尾被截断的双字节字符
+        assert_eq!(detect_encoding(&bytes[..bytes.len() - 2]).encoding, GB18030);
+    }
+
+    #[test]
+    fn falls_back_to_windows_1252() {
+        // 0x80-0xFF之间单独出现的字节既不是UTF-8也不是GB18030
+        assert_eq!(detect_encoding(b"caf\xe9 \xff\n").encoding, WINDOWS_1252);
+    }
+
+    #[test]
+    fn explicit_label_overrides_detection() {
+        let detected = resolve_encoding(Some("gbk"), b"ascii").unwrap();
+        assert_eq!(detected.encoding, encoding_rs::GBK);
+        assert_eq!(resolve_encoding(Some("auto"), b"ascii").unwrap().encoding, UTF_8);
+        assert!(resolve_encoding(Some("no-such-encoding"), b"").is_err());
+
+        // 指定的编码与BOM一致时仍然跳过BOM
+        let detected = resolve_encoding(Some("utf-8"), b"\xEF\xBB\xBFx").unwrap();
+        assert_eq!(detected.bom_len, 3);
+        let detected = resolve_encoding(Some("windows-1252"), b"\xEF\xBB\xBFx").unwrap();
+        assert_eq!(detected.bom_len, 0);
+    }
+
+    #[test]
+    fn stream_decoder_keeps_split_characters() {
+        let mut decoder = StreamDecoder::new(None).unwrap();
+        let bytes = "日志行\n".as_bytes();
+        let mut text = decoder.feed(&bytes[..4], false);
+        text.push_str(&decoder.feed(&bytes[4..], false));
+        text.push_str(&decoder.feed(&[], true));
+        assert_eq!(text, "日志行\n");
+    }
+
+    #[test]
+    fn stream_decoder_replaces_invalid_bytes() {
+        let mut decoder = StreamDecoder::with_encoding(UTF_8);
+        assert_eq!(decoder.feed(b"a\xffb", true), "a\u{FFFD}b");
+    }
+}
@@ -0,0 +1,321 @@
+// 所有命令统一返回的错误类型，序列化为 { code, message, details }：
+// code是稳定的错误码，前端按它判断错误类型并显示本地化的提示；details是填入提示的结构化数据；
+// message是后端生成的中文说明，前端没有对应的翻译时直接显示
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::{json, Value};
+use std::fmt;
+use std::path::Path;
+
+// libssh2的错误码
+const LIBSSH2_ERROR_TIMEOUT: i32 = -9;
+const LIBSSH2_FX_NO_SUCH_FILE: i32 = 2;
+const LIBSSH2_FX_PERMISSION_DENIED: i32 = 3;
+const LIBSSH2_FX_NO_SUCH_PATH: i32 = 10;
+
+#[derive(Debug, Clone)]
+pub enum EasyLogError {
+    NotFound { path: String },               // 文件或目录不存在
+    PermissionDenied { path: String },       // 没有访问文件或目录的权限
+    Io { path: String, reason: String },     // 其它读写错误
+    ConnectionFailed { host: String, port: u16, reason: String },
+    AuthFailed { host: String, username: String, reason: String },
+    // 服务器的主机密钥未知，用户在 ssh-host-key-unknown 事件中确认指纹后重新连接
+    HostKeyUnknown { host: String, port: u16, key_type: String, fingerprint: String },
+    // 服务器的主机密钥与 known_hosts 中保存的不一致，可能存在中间人攻击
+    HostKeyMismatch { host: String, port: u16, key_type: String, fingerprint: String, known_hosts: String },
+    Timeout { operation: String },
+    Encoding { encoding: String },           // 不支持的编码名称
+    InvalidInput { field: String, reason: String }, // 参数无效，field为参数名
+    RemoteCommand { host: String, reason: String }, // 远程命令执行失败，reason为标准错误输出
+    StreamNotFound { stream_id: String },    // 没有正在运行的日志流（本地文件的流id为文件路径）
+    ProfileNotFound { id: String },
+    ProfilesLocked,                          // SSH配置已锁定，需要先输入主密码
+    WrongPassword,                           // 主密码不正确
+    Internal { reason: String },             // 其它错误
+}
+
+impl EasyLogError {
+    pub fn invalid(field: &str, reason: impl fmt::Display) -> Self {
+        EasyLogError::InvalidInput {
+            field: field.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn internal(reason: impl fmt::Display) -> Self {
+        EasyLogError::Internal {
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn not_found(path: impl AsRef<Path>) -> Self {
+        EasyLogError::NotFound {
+            path: path.as_ref().display().to_string(),
+        }
+    }
+
+    // 本地文件的读写错误
+    pub fn io(path: impl AsRef<Path>, e: std::io::Error) -> Self {
+        let path = path.as_ref().display().to_string();
+        match e.kind() {
+            std::io::ErrorKind::NotFound => EasyLogError::NotFound { path },
+            std::io::ErrorKind::PermissionDenied => EasyLogError::PermissionDenied { path },
+            std::io::ErrorKind::TimedOut => EasyLogError::Timeout { operation: path },
+            _ => EasyLogError::Io {
+                path,
+                reason: e.to_string(),
+            },
+        }
+    }
+
+    // SFTP操作的错误，按SFTP状态码区分文件不存在和没有权限
+    pub fn sftp(path: impl AsRef<Path>, e: ssh2::Error) -> Self {
+        let path = path.as_ref().display().to_string();
+        match e.code() {
+            ssh2::ErrorCode::SFTP(LIBSSH2_FX_NO_SUCH_FILE | LIBSSH2_FX_NO_SUCH_PATH) => EasyLogError::NotFound { path },
+            ssh2::ErrorCode::SFTP(LIBSSH2_FX_PERMISSION_DENIED) => EasyLogError::PermissionDenied { path },
+            ssh2::ErrorCode::Session(LIBSSH2_ERROR_TIMEOUT) => EasyLogError::Timeout { operation: path },
+            _ => EasyLogError::Io {
+                path,
+                reason: e.to_string(),
+            },
+        }
+    }
+
+    // 远程命令失败：tail、wc等命令找不到文件或没有权限时，错误输出形如
+    // "tail: cannot open '/var/log/app.log' for reading: No such file or directory"
+    pub fn remote_command(host: &str, stderr: &str) -> Self {
+        let stderr = stderr.trim();
+        let path = || quoted_path(stderr).unwrap_or(stderr).to_string();
+        if stderr.contains("No such file or directory") {
+            EasyLogError::NotFound { path: path() }
+        } else if stderr.contains("Permission denied") {
+            EasyLogError::PermissionDenied { path: path() }
+        } else {
+            EasyLogError::RemoteCommand {
+                host: host.to_string(),
+                reason: stderr.to_string(),
+            }
+        }
+    }
+
+    // 稳定的错误码，前端按它选择本地化的提示
+    pub fn code(&self) -> &'static str {
+        match self {
+            EasyLogError::NotFound { .. } => "NOT_FOUND",
+            EasyLogError::PermissionDenied { .. } => "PERMISSION_DENIED",
+            EasyLogError::Io { .. } => "IO",
+            EasyLogError::ConnectionFailed { .. } => "CONNECTION_FAILED",
+            EasyLogError::AuthFailed { .. } => "AUTH_FAILED",
+            EasyLogError::HostKeyUnknown { .. } => "HOST_KEY_UNKNOWN",
+            EasyLogError::HostKeyMismatch { .. } => "HOST_KEY_MISMATCH",
+            EasyLogError::Timeout { .. } => "TIMEOUT",
+            EasyLogError::Encoding { .. } => "ENCODING",
+            EasyLogError::InvalidInput { .. } => "INVALID_INPUT",
+            EasyLogError::RemoteCommand { .. } => "REMOTE_COMMAND",
+            EasyLogError::StreamNotFound { .. } => "STREAM_NOT_FOUND",
+            EasyLogError::ProfileNotFound { .. } => "PROFILE_NOT_FOUND",
+            EasyLogError::ProfilesLocked => "PROFILES_LOCKED",
+            EasyLogError::WrongPassword => "WRONG_PASSWORD",
+            EasyLogError::Internal { .. } => "INTERNAL",
+        }
+    }
+
+    fn details(&self) -> Value {
+        match self {
+            EasyLogError::NotFound { path } | EasyLogError::PermissionDenied { path } => json!({ "path": path }),
+            EasyLogError::Io { path, reason } => json!({ "path": path, "reason": reason }),
+            EasyLogError::ConnectionFailed { host, port, reason } => json!({ "host": host, "port": port, "reason": reason }),
+            EasyLogError::AuthFailed { host, username, reason } => {
+                json!({ "host": host, "username": username, "reason": reason })
+            }
+            EasyLogError::HostKeyUnknown { host, port, key_type, fingerprint } => {
+                json!({ "host": host, "port": port, "key_type": key_type, "fingerprint": fingerprint })
+            }
+            EasyLogError::HostKeyMismatch { host, port, key_type, fingerprint, known_hosts } => json!({
+                "host": host,
+                "port": port,
+                "key_type": key_type,
+                "fingerprint": fingerprint,
+                "known_hosts": known_hosts,
+            }),
+            EasyLogError::Timeout { operation } => json!({ "operation": operation }),
+            EasyLogError::Encoding { encoding } => json!({ "encoding": encoding }),
+            EasyLogError::InvalidInput { field, reason } => json!({ "field": field, "reason": reason }),
+            EasyLogError::RemoteCommand { host, reason } => json!({ "host": host, "reason": reason }),
+            EasyLogError::StreamNotFound { stream_id } => json!({ "stream_id": stream_id }),
+            EasyLogError::ProfileNotFound { id } => json!({ "id": id }),
+            EasyLogError::ProfilesLocked | EasyLogError::WrongPassword => json!({}),
+            EasyLogError::Internal { reason } => json!({ "reason": reason }),
+        }
+    }
+}
+
+// 错误输出中第一个用引号括起来的路径，GNU工具使用 '...'，部分语言环境使用 ‘...’
+fn quoted_path(stderr: &str) -> Option<&str> {
+    let (open, close) = [('\'', '\''), ('‘', '’'), ('"', '"')]
+        .into_iter()
+        .find(|(open, _)| stderr.contains(*open))?;
+    let start = stderr.find(open)? + open.len_utf8();
+    let end = stderr[start..].find(close)?;
+    Some(&stderr[start..start + end])
+}
+
+impl fmt::Display for EasyLogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EasyLogError::NotFound { path } => write!(f, "文件不存在: {}", path),
+            EasyLogError::PermissionDenied { path } => write!(f, "没有访问权限: {}", path),
+            EasyLogError::Io { path, reason } => write!(f, "读取 {} 失败: {}", path, reason),
+            EasyLogError::ConnectionFailed { host, port, reason } => write!(f, "无法连接到服务器 {}:{}: {}", host, port, reason),
+            EasyLogError::AuthFailed { host, username, reason } => write!(f, "SSH认证失败 ({}@{}): {}", username, host, reason),
+            EasyLogError::HostKeyUnknown { host, port, key_type, fingerprint } => write!(
+                f,
+                "服务器 {}:{} 的主机密钥未知（{} {}），请确认指纹后重新连接",
+                host, port, key_type, fingerprint
+            ),
+            EasyLogError::HostKeyMismatch { host, port, key_type, fingerprint, known_hosts } => write!(
+                f,
+                "服务器 {}:{} 的主机密钥与 {} 中保存的不一致，可能存在中间人攻击！当前密钥指纹: {} {}。如果服务器确实更换了密钥，请先删除已保存的旧密钥",
+                host, port, known_hosts, key_type, fingerprint
+            ),
+            EasyLogError::Timeout { operation } => write!(f, "操作超时: {}", operation),
+            EasyLogError::Encoding { encoding } => write!(f, "不支持的编码: {}", encoding),
+            EasyLogError::InvalidInput { field, reason } => write!(f, "参数 {} 无效: {}", field, reason),
+            EasyLogError::RemoteCommand { host, reason } => write!(f, "在 {} 上执行命令失败: {}", host, reason),
+            EasyLogError::StreamNotFound { stream_id } => write!(f, "未找到日志流: {}", stream_id),
+            EasyLogError::ProfileNotFound { id } => write!(f, "SSH配置不存在: {}", id),
+            EasyLogError::ProfilesLocked => write!(f, "SSH配置已锁定，请先输入主密码解锁"),
+            EasyLogError::WrongPassword => write!(f, "主密码不正确"),
+            EasyLogError::Internal { reason } => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for EasyLogError {}
+
+impl Serialize for EasyLogError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+        map.serialize_entry("details", &self.details())?;
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serialized(error: &EasyLogError) -> Value {
+        serde_json::to_value(error).unwrap()
+    }
+
+    // 错误码是前端本地化提示的键，修改任何一个都需要同步修改前端
+    #[test]
+    fn codes_are_stable() {
+        let text = || "x".to_string();
+        let cases = [
+            (EasyLogError::NotFound { path: text() }, "NOT_FOUND"),
+            (EasyLogError::PermissionDenied { path: text() }, "PERMISSION_DENIED"),
+            (EasyLogError::Io { path: text(), reason: text() }, "IO"),
+            (EasyLogError::ConnectionFailed { host: text(), port: 22, reason: text() }, "CONNECTION_FAILED"),
+            (EasyLogError::AuthFailed { host: text(), username: text(), reason: text() }, "AUTH_FAILED"),
+            (
+                EasyLogError::HostKeyUnknown { host: text(), port: 22, key_type: text(), fingerprint: text() },
+                "HOST_KEY_UNKNOWN",
+            ),
+            (
+                EasyLogError::HostKeyMismatch {
+                    host: text(),
+                    port: 22,
+                    key_type: text(),
+                    fingerprint: text(),
+                    known_hosts: text(),
+                },
+                "HOST_KEY_MISMATCH",
+            ),
+            (EasyLogError::Timeout { operation: text() }, "TIMEOUT"),
+            (EasyLogError::Encoding { encoding: text() }, "ENCODING"),
+            (EasyLogError::InvalidInput { field: text(), reason: text() }, "INVALID_INPUT"),
+            (EasyLogError::RemoteCommand { host: text(), reason: text() }, "REMOTE_COMMAND"),
+            (EasyLogError::StreamNotFound { stream_id: text() }, "STREAM_NOT_FOUND"),
+            (EasyLogError::ProfileNotFound { id: text() }, "PROFILE_NOT_FOUND"),
+            (EasyLogError::ProfilesLocked, "PROFILES_LOCKED"),
+            (EasyLogError::WrongPassword, "WRONG_PASSWORD"),
+            (EasyLogError::Internal { reason: text() }, "INTERNAL"),
+        ];
+        for (error, code) in cases {
+            let value = serialized(&error);
+            assert_eq!(value["code"], code);
+            assert_eq!(value["message"], error.to_string());
+            assert!(value["details"].is_object());
+        }
+    }
+
+    #[test]
+    fn serializes_code_message_and_details() {
+        let error = EasyLogError::ConnectionFailed {
+            host: "web1".to_string(),
+            port: 2222,
+            reason: "Connection refused".to_string(),
+        };
+        assert_eq!(
+            serialized(&error),
+            json!({
+                "code": "CONNECTION_FAILED",
+                "message": "无法连接到服务器 web1:2222: Connection refused",
+                "details": { "host": "web1", "port": 2222, "reason": "Connection refused" },
+            })
+        );
+        assert_eq!(
+            serialized(&EasyLogError::ProfilesLocked),
+            json!({ "code": "PROFILES_LOCKED", "message": "SSH配置已锁定，请先输入主密码解锁", "details": {} })
+        );
+    }
+
+    #[test]
+    fn remote_command_stderr() {
+        let error = EasyLogError::remote_command(
+            "web1",
+            "tail: cannot open '/var/log/app.log' for reading: No such file or directory\n",
+        );
+        assert_eq!(serialized(&error)["code"], "NOT_FOUND");
+        assert_eq!(serialized(&error)["details"]["path"], "/var/log/app.log");
+
+        let error = EasyLogError::remote_command("web1", "wc: ‘/var/log/secure’: Permission denied");
+        assert_eq!(serialized(&error)["code"], "PERMISSION_DENIED");
+        assert_eq!(serialized(&error)["details"]["path"], "/var/log/secure");
+
+        // 没有引号时用整个错误输出作为路径
+        let error = EasyLogError::remote_command("web1", "No such file or directory");
+        assert_eq!(serialized(&error)["details"]["path"], "No such file or directory");
+
+        let error = EasyLogError::remote_command("web1", "  grep: invalid regular expression  ");
+        assert_eq!(
+            serialized(&error),
+            json!({
+                "code": "REMOTE_COMMAND",
+                "message": "在 web1 上执行命令失败: grep: invalid regular expression",
+                "details": { "host": "web1", "reason": "grep: invalid regular expression" },
+            })
+        );
+    }
+
+    #[test]
+    fn io_and_sftp_errors() {
+        let io = |kind: std::io::ErrorKind| EasyLogError::io("/tmp/app.log", kind.into()).code();
+        assert_eq!(io(std::io::ErrorKind::NotFound), "NOT_FOUND");
+        assert_eq!(io(std::io::ErrorKind::PermissionDenied), "PERMISSION_DENIED");
+        assert_eq!(io(std::io::ErrorKind::TimedOut), "TIMEOUT");
+        assert_eq!(io(std::io::ErrorKind::InvalidData), "IO");
+
+        let sftp = |code| EasyLogError::sftp("/var/log", ssh2::Error::new(code, "sftp error")).code();
+        assert_eq!(sftp(ssh2::ErrorCode::SFTP(LIBSSH2_FX_NO_SUCH_FILE)), "NOT_FOUND");
+        assert_eq!(sftp(ssh2::ErrorCode::SFTP(LIBSSH2_FX_NO_SUCH_PATH)), "NOT_FOUND");
+        assert_eq!(sftp(ssh2::ErrorCode::SFTP(LIBSSH2_FX_PERMISSION_DENIED)), "PERMISSION_DENIED");
+        assert_eq!(sftp(ssh2::ErrorCode::Session(LIBSSH2_ERROR_TIMEOUT)), "TIMEOUT");
+        assert_eq!(sftp(ssh2::ErrorCode::SFTP(4)), "IO");
+    }
+}
@@ -0,0 +1,481 @@
+// 多主机合并监控：在每台服务器上按路径模式找到日志文件并分别监控，
+// 收到的行按解析出的时间戳在一个较短的重排窗口内排序，合并为一个日志流发送到前端
+use crate::error::EasyLogError;
+use crate::parser::{leading_timestamp, parse_timestamp};
+use crate::profiles::{self, SshTarget};
+use crate::stream_batch::{BatchOptions, BatchSender};
+use crate::{
+    multiline, remote_files, run_remote_tail, LogStreamData, RemoteStream, RemoteStreamInfo, RemoteTail,
+    SshCredentials, ACTIVE_CONNECTIONS, NEXT_STREAM_ID,
+};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BinaryHeap, HashSet};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio_util::sync::CancellationToken;
+
+// 默认重排窗口：每一行最多等待这么久，期间到达的更早的行可以排到它前面
+const DEFAULT_REORDER_WINDOW: Duration = Duration::from_millis(1500);
+const MAX_REORDER_WINDOW: Duration = Duration::from_secs(10);
+// 等待中的行超过这个数量时不再等待窗口，直接发送最早的行
+const MAX_PENDING_LINES: usize = 20_000;
+// 检查是否有可以发送的行的间隔
+const MERGE_TICK: Duration = Duration::from_millis(100);
+// 成员发送到合并线程的通道容量，合并线程处理不过来时成员暂停读取
+const MERGE_CHANNEL_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Deserialize)]
+pub struct MergedStreamOptions {
+    #[serde(default)]
+    targets: Vec<SshTarget>, // 要监控的服务器
+    group: Option<String>,   // 可选参数，主机分组，分组中所有的SSH配置都会加入监控
+    path_pattern: String,    // 日志路径，可以包含通配符，例如 /var/log/app/*.log
+    encoding: Option<String>,
+    parse: Option<String>,
+    record_start: Option<multiline::RecordStart>,
+    reorder_window_ms: Option<u64>, // 可选参数，重排窗口（毫秒），默认1500
+    stream_id: Option<String>,      // 可选参数，指定合并流的id，已存在相同id的流时替换它
+    #[serde(flatten)]
+    batch: BatchOptions,
+}
+
+// 没能加入合并流的服务器
+#[derive(Debug, Serialize, Clone)]
+pub struct HostError {
+    host: String,
+    error: EasyLogError,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MergedStreamInfo {
+    stream_id: String,
+    members: Vec<RemoteStreamInfo>, // 每台服务器上的每个文件是一个成员
+    errors: Vec<HostError>,
+}
+
+// 合并流的所有成员都结束后发送的 ssh-log-disconnected 事件
+#[derive(Debug, Serialize, Clone)]
+struct MergedStreamEvent {
+    stream_id: String,
+    path_pattern: String,
+}
+
+// 从成员发送到合并线程的一行
+struct MergeLine {
+    timestamp: Option<i64>,
+    data: LogStreamData,
+}
+
+struct Pending {
+    timestamp: i64,
+    seq: u64, // 到达顺序，时间戳相同时保持先后
+    arrived: Instant,
+    data: LogStreamData,
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// BinaryHeap是最大堆，反过来比较使最早的行最先弹出
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.timestamp.cmp(&self.timestamp).then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+struct Merger {
+    pending: BinaryHeap<Pending>,
+    window: Duration,
+    next_seq: u64,
+    latest: i64, // 已收到的最晚的时间戳，没有时间戳的行排在它之后
+}
+
+impl Merger {
+    fn new(window: Duration) -> Self {
+        Merger {
+            pending: BinaryHeap::new(),
+            window,
+            next_seq: 0,
+            latest: i64::MIN,
+        }
+    }
+
+    fn push(&mut self, line: MergeLine, now: Instant) {
+        let timestamp = line.timestamp.unwrap_or(self.latest);
+        self.latest = self.latest.max(timestamp);
+        self.pending.push(Pending {
+            timestamp,
+            seq: self.next_seq,
+            arrived: now,
+            data: line.data,
+        });
+        self.next_seq += 1;
+    }
+
+    // 取出等待超过重排窗口的行，以及所有时间戳不晚于它们的行
+    fn release(&mut self, now: Instant) -> Vec<LogStreamData> {
+        let threshold = self
+            .pending
+            .iter()
+            .filter(|p| now.duration_since(p.arrived) >= self.window)
+            .map(|p| p.timestamp)
+            .max();
+        let mut released = Vec::new();
+        while let Some(top) = self.pending.peek() {
+            if threshold.is_some_and(|t| top.timestamp <= t) || self.pending.len() > MAX_PENDING_LINES {
+                released.push(self.pending.pop().unwrap().data);
+            } else {
+                break;
+            }
+        }
+        released
+    }
+
+    // 所有成员结束后按顺序取出剩下的行
+    fn drain(&mut self) -> Vec<LogStreamData> {
+        let mut released = Vec::with_capacity(self.pending.len());
+        while let Some(pending) = self.pending.pop() {
+            released.push(pending.data);
+        }
+        released
+    }
+}
+
+fn send_lines(batch: &BatchSender, lines: Vec<LogStreamData>) {
+    for line in lines {
+        batch.push(line);
+    }
+}
+
+// 合并线程：排好序的行交给批量发送，所有成员结束（发送端全部释放）后发送剩下的行并结束
+fn run_merger(
+    window: tauri::Window,
+    receiver: mpsc::Receiver<MergeLine>,
+    mut merger: Merger,
+    batch: BatchSender,
+    event: MergedStreamEvent,
+) {
+    let mut last_release = Instant::now();
+    loop {
+        match receiver.recv_timeout(MERGE_TICK) {
+            Ok(line) => merger.push(line, Instant::now()),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        let now = Instant::now();
+        if now.duration_since(last_release) >= MERGE_TICK {
+            send_lines(&batch, merger.release(now));
+            last_release = now;
+        }
+    }
+    send_lines(&batch, merger.drain());
+    drop(batch);
+
+    info!("Merged log stream {} ended", event.stream_id);
+    if let Err(e) = window.emit("ssh-log-disconnected", event) {
+        error!("Failed to emit ssh-log-disconnected: {}", e);
+    }
+}
+
+// 成员的每一行交给合并线程，没有时间戳的行（例如异常堆栈）沿用同一个文件中上一行的时间戳
+fn member_sink(
+    sender: mpsc::SyncSender<MergeLine>,
+    stream_id: String,
+    host: String,
+) -> Box<dyn FnMut(LogStreamData) + Send> {
+    let mut last_timestamp = None;
+    Box::new(move |mut data: LogStreamData| {
+        let timestamp = data
+            .record
+            .as_ref()
+            .and_then(|record| record.timestamp_ms)
+            .or_else(|| leading_timestamp(&data.content).and_then(parse_timestamp))
+            .or(last_timestamp);
+        last_timestamp = timestamp;
+        data.stream_id = Some(stream_id.clone());
+        data.host = Some(host.clone());
+        // 合并线程已经结束时丢弃
+        let _ = sender.send(MergeLine { timestamp, data });
+    })
+}
+
+// 解析所有目标服务器，按 用户@主机:端口 去重
+fn resolve_targets(options: &MergedStreamOptions) -> Result<Vec<SshCredentials>, EasyLogError> {
+    let mut targets = options.targets.clone();
+    if let Some(group) = options.group.as_deref().filter(|g| !g.is_empty()) {
+        targets.extend(profiles::group_targets(group)?);
+    }
+    if targets.is_empty() {
+        return Err(EasyLogError::invalid("targets", "没有指定要监控的服务器"));
+    }
+
+    let mut seen = HashSet::new();
+    let mut resolved = Vec::new();
+    for target in &targets {
+        let credentials = target.resolve()?;
+        let key = format!("{}@{}:{}", credentials.username, credentials.host, credentials.port.unwrap_or(22));
+        if seen.insert(key) {
+            resolved.push(credentials);
+        }
+    }
+    Ok(resolved)
+}
+
+// 在每台服务器上同时展开路径模式，返回每台服务器匹配到的文件或错误
+fn expand_all(hosts: &[SshCredentials], pattern: &str) -> Vec<Result<Vec<String>, EasyLogError>> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = hosts
+            .iter()
+            .map(|credentials| scope.spawn(move || remote_files::expand_path_pattern(credentials, pattern)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|_| Err(EasyLogError::internal("查找日志文件失败"))))
+            .collect()
+    })
+}
+
+// 同时监控多台服务器上的日志，合并为一个按时间排序的日志流，返回合并流的id和所有成员。
+// 合并后的行通过 ssh-log-batch 事件批量发送，stream_id 为合并流的id，host 为来源服务器；
+// 成员各自的连接状态事件（ssh-log-connected、ssh-log-reconnecting 等）附带 fanout_id。
+// 每一行最多等待重排窗口的时间，超过窗口才到达的行不再参与排序
+#[tauri::command]
+pub async fn monitor_remote_logs_merged(
+    window: tauri::Window,
+    options: MergedStreamOptions,
+) -> Result<MergedStreamInfo, EasyLogError> {
+    let hosts = resolve_targets(&options)?;
+    info!("Starting merged log monitoring for {} on {} hosts", options.path_pattern, hosts.len());
+
+    let pattern = options.path_pattern.clone();
+    let expand_hosts = hosts.clone();
+    let expanded = tokio::task::spawn_blocking(move || expand_all(&expand_hosts, &pattern))
+        .await
+        .map_err(|e| EasyLogError::internal(format!("查找日志文件失败: {}", e)))?;
+
+    let fanout_id = options
+        .stream_id
+        .clone()
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| format!("merged-{}", NEXT_STREAM_ID.fetch_add(1, Ordering::Relaxed)));
+    let started_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (sender, receiver) = mpsc::sync_channel(MERGE_CHANNEL_CAPACITY);
+
+    // 先创建所有成员的读取状态，编码或解析格式参数有误时直接返回错误
+    let mut members = Vec::new();
+    let mut errors = Vec::new();
+    for (credentials, paths) in hosts.into_iter().zip(expanded) {
+        let paths = match paths {
+            Ok(paths) => paths,
+            Err(error) => {
+                warn!("合并监控跳过服务器 {}: {}", credentials.host, error);
+                errors.push(HostError {
+                    host: credentials.host.clone(),
+                    error,
+                });
+                continue;
+            }
+        };
+        for path in paths {
+            let port = credentials.port.unwrap_or(22);
+            let stream = RemoteStreamInfo {
+                stream_id: format!("{}/{}:{}{}", fanout_id, credentials.host, port, path),
+                host: credentials.host.clone(),
+                port,
+                username: credentials.username.clone(),
+                log_path: path,
+                started_at,
+                fanout_id: Some(fanout_id.clone()),
+            };
+            let tail = RemoteTail::with_sink(
+                member_sink(sender.clone(), fanout_id.clone(), credentials.host.clone()),
+                options.encoding.as_deref(),
+                options.parse.as_deref(),
+                options.record_start.as_ref(),
+            )?;
+            members.push((credentials.clone(), stream, tail));
+        }
+    }
+    // 合并线程在所有成员的发送端释放后结束
+    drop(sender);
+
+    // 所有服务器都失败时返回第一台服务器的错误，其它服务器的错误已经记录在日志中
+    if members.is_empty() {
+        return Err(errors
+            .into_iter()
+            .next()
+            .map(|e| e.error)
+            .unwrap_or_else(|| EasyLogError::not_found(&options.path_pattern)));
+    }
+
+    // 添加到活跃连接列表，相同id的旧合并流停止
+    let mut started = Vec::new();
+    {
+        let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
+        for existing in connections.stop_fanout(&fanout_id) {
+            info!("替换合并日志流成员 {}", existing.info.stream_id);
+        }
+        for (credentials, stream, tail) in members {
+            let cancel = CancellationToken::new();
+            let (finished, finished_rx) = tokio::sync::oneshot::channel();
+            connections.streams.insert(
+                stream.stream_id.clone(),
+                RemoteStream {
+                    info: stream.clone(),
+                    cancel: cancel.clone(),
+                    finished: finished_rx,
+                },
+            );
+            started.push(stream.clone());
+
+            let window = window.clone();
+            std::thread::spawn(move || run_remote_tail(&window, &credentials, tail, &stream, &cancel, finished));
+        }
+    }
+
+    let reorder_window = options
+        .reorder_window_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_REORDER_WINDOW)
+        .min(MAX_REORDER_WINDOW);
+    let event = MergedStreamEvent {
+        stream_id: fanout_id.clone(),
+        path_pattern: options.path_pattern,
+    };
+    let batch = BatchSender::spawn(window.clone(), "ssh-log-batch", Some(fanout_id.clone()), &options.batch);
+    std::thread::spawn(move || run_merger(window, receiver, Merger::new(reorder_window), batch, event));
+
+    info!("Merged log stream {} started with {} files", fanout_id, started.len());
+    Ok(MergedStreamInfo {
+        stream_id: fanout_id,
+        members: started,
+        errors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(1500);
+
+    fn line(timestamp: Option<i64>, content: &str) -> MergeLine {
+        MergeLine {
+            timestamp,
+            data: LogStreamData::new_line(content.to_string(), "remote", None),
+        }
+    }
+
+    fn contents(lines: Vec<LogStreamData>) -> Vec<String> {
+        lines.into_iter().map(|l| l.content).collect()
+    }
+
+    #[test]
+    fn waits_for_the_reorder_window() {
+        let start = Instant::now();
+        let mut merger = Merger::new(WINDOW);
+        merger.push(line(Some(2_000), "b"), start);
+        merger.push(line(Some(1_000), "a"), start + Duration::from_millis(500));
+        assert!(merger.release(start + Duration::from_millis(1000)).is_empty());
+
+        // b 等待超过窗口后，时间戳更早的 a 排在它前面一起发送
+        assert_eq!(contents(merger.release(start + WINDOW)), ["a", "b"]);
+        assert!(merger.drain().is_empty());
+    }
+
+    #[test]
+    fn holds_lines_later_than_the_released_ones() {
+        let start = Instant::now();
+        let mut merger = Merger::new(WINDOW);
+        merger.push(line(Some(1_000), "a"), start);
+        merger.push(line(Some(3_000), "c"), start + Duration::from_millis(1000));
+        merger.push(line(Some(2_000), "b"), start + Duration::from_millis(1000));
+
+        assert_eq!(contents(merger.release(start + WINDOW)), ["a"]);
+        assert_eq!(contents(merger.release(start + Duration::from_millis(2500))), ["b", "c"]);
+    }
+
+    #[test]
+    fn equal_timestamps_keep_arrival_order() {
+        let start = Instant::now();
+        let mut merger = Merger::new(WINDOW);
+        for content in ["first", "second", "third"] {
+            merger.push(line(Some(1_000), content), start);
+        }
+        merger.push(line(Some(500), "earlier"), start);
+        assert_eq!(contents(merger.release(start + WINDOW)), ["earlier", "first", "second", "third"]);
+    }
+
+    #[test]
+    fn lines_without_timestamp_follow_the_latest() {
+        let start = Instant::now();
+        let mut merger = Merger::new(WINDOW);
+        // 还没有收到时间戳时排在最前面
+        merger.push(line(None, "banner"), start);
+        merger.push(line(Some(3_000), "c"), start);
+        merger.push(line(None, "c-continued"), start);
+        merger.push(line(Some(2_000), "b"), start);
+        merger.push(line(None, "b-continued"), start);
+        assert_eq!(merger.latest, 3_000);
+        assert_eq!(contents(merger.drain()), ["banner", "b", "c", "c-continued", "b-continued"]);
+    }
+
+    #[test]
+    fn releases_early_when_too_many_lines_are_pending() {
+        let start = Instant::now();
+        let mut merger = Merger::new(MAX_REORDER_WINDOW);
+        for i in (0..MAX_PENDING_LINES as i64 + 3).rev() {
+            merger.push(line(Some(i), &i.to_string()), start);
+        }
+        // 窗口还没到，只发送超出上限的最早几行
+        assert_eq!(contents(merger.release(start)), ["0", "1", "2"]);
+        assert_eq!(merger.pending.len(), MAX_PENDING_LINES);
+        assert!(merger.release(start).is_empty());
+    }
+
+    #[test]
+    fn drain_returns_everything_in_order() {
+        let start = Instant::now();
+        let mut merger = Merger::new(WINDOW);
+        merger.push(line(Some(3_000), "c"), start);
+        merger.push(line(Some(1_000), "a"), start);
+        merger.push(line(Some(2_000), "b"), start);
+        merger.push(line(Some(1_000), "a2"), start);
+        assert_eq!(contents(merger.drain()), ["a", "a2", "b", "c"]);
+        assert!(merger.pending.is_empty());
+    }
+
+    #[test]
+    fn member_sink_inherits_the_previous_timestamp() {
+        let (sender, receiver) = mpsc::sync_channel(10);
+        let mut sink = member_sink(sender, "merged-1".to_string(), "web1".to_string());
+        for content in ["2024-01-15 10:30:00 ERROR failed", "    at Foo.bar(Foo.java:10)"] {
+            sink(LogStreamData::new_line(content.to_string(), "remote", None));
+        }
+        drop(sink);
+
+        let lines: Vec<MergeLine> = receiver.iter().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].timestamp.is_some());
+        assert_eq!(lines[1].timestamp, lines[0].timestamp);
+        assert_eq!(lines[1].data.stream_id.as_deref(), Some("merged-1"));
+        assert_eq!(lines[1].data.host.as_deref(), Some("web1"));
+    }
+}
@@ -0,0 +1,650 @@
+// 大文件行索引：每隔固定行数记录一次行首字节偏移，
+// 读取任意行区间时先跳到最近的检查点再向后扫描，避免把整个文件读入内存
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use lazy_static::lazy_static;
+use log::info;
+use serde::Deserialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime};
+
+// 每隔多少行记录一个检查点，数值越小定位越快但占用内存越多
+const INDEX_STRIDE: usize = 1024;
+// 建立索引时每次读取的块大小
+const SCAN_CHUNK_SIZE: usize = 64 * 1024;
+// 最多缓存多少个文件的索引，超出时丢弃最久没有使用的
+const MAX_CACHED_INDEXES: usize = 64;
+// 校验已索引部分是否改变时读取的开头和结尾的字节数
+const FINGERPRINT_BYTES: u64 = 4096;
+
+// 换行符的字节形式：UTF-16编码下换行符占两个字节，且只能出现在编码单元边界上
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineTerminator {
+    #[default]
+    Byte,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl LineTerminator {
+    pub fn for_encoding(encoding: &'static Encoding) -> Self {
+        if encoding == UTF_16LE {
+            LineTerminator::Utf16Le
+        } else if encoding == UTF_16BE {
+            LineTerminator::Utf16Be
+        } else {
+            LineTerminator::Byte
+        }
+    }
+
+    // 编码单元的字节数
+    pub fn unit_len(self) -> usize {
+        match self {
+            LineTerminator::Byte => 1,
+            LineTerminator::Utf16Le | LineTerminator::Utf16Be => 2,
+        }
+    }
+
+    fn encode(self, c: u8) -> &'static [u8] {
+        match (self, c) {
+            (LineTerminator::Byte, b'\n') => b"\n",
+            (LineTerminator::Byte, _) => b"\r",
+            (LineTerminator::Utf16Le, b'\n') => b"\n\0",
+            (LineTerminator::Utf16Le, _) => b"\r\0",
+            (LineTerminator::Utf16Be, b'\n') => b"\0\n",
+            (LineTerminator::Utf16Be, _) => b"\0\r",
+        }
+    }
+
+    // 判断一个编码单元是否为换行符
+    pub fn is_newline(self, unit: &[u8]) -> bool {
+        unit == self.encode(b'\n')
+    }
+
+    // 去掉行尾的 \n 或 \r\n
+    pub fn trim(self, line: &[u8]) -> &[u8] {
+        let line = line.strip_suffix(self.encode(b'\n')).unwrap_or(line);
+        line.strip_suffix(self.encode(b'\r')).unwrap_or(line)
+    }
+
+    // 读取一行（包含换行符），返回读取的字节数
+    pub fn read_line<R: BufRead>(self, reader: &mut R, line: &mut Vec<u8>) -> std::io::Result<usize> {
+        if self == LineTerminator::Byte {
+            return reader.read_until(b'\n', line);
+        }
+
+        // UTF-16下0x0A也可能是其它字符的一个字节，需要检查它是否位于编码单元边界
+        let start = line.len();
+        loop {
+            if reader.read_until(0x0A, line)? == 0 || line.last() != Some(&0x0A) {
+                break;
+            }
+
+            let offset = line.len() - 1 - start;
+            match (self, offset % 2) {
+                (LineTerminator::Utf16Le, 0) => {
+                    let mut next = [0u8; 1];
+                    if reader.read(&mut next)? == 0 {
+                        break;
+                    }
+                    line.push(next[0]);
+                    if next[0] == 0 {
+                        break;
+                    }
+                }
+                (LineTerminator::Utf16Be, 1) if line[line.len() - 2] == 0 => break,
+                _ => {}
+            }
+        }
+        Ok(line.len() - start)
+    }
+}
+
+// 尽量填满缓冲区，只有到达文件末尾时才会返回不足的长度
+fn fill_buffer<R: Read>(reader: &mut R, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let n = reader.read(&mut buffer[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    Ok(filled)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+    // 换行符形式，由文件编码决定
+    terminator: LineTerminator,
+    // checkpoints[i] 为第 i * INDEX_STRIDE 行的起始字节偏移
+    checkpoints: Vec<u64>,
+    // 已扫描到的换行符数量
+    newline_count: usize,
+    // 已建立索引的字节数
+    indexed_bytes: u64,
+    // 已扫描部分的最后一个字节是否为换行符
+    ends_with_newline: bool,
+}
+
+// 一次窗口读取的结果
+#[derive(Debug, Clone)]
+pub struct LineWindow {
+    pub lines: Vec<Vec<u8>>,
+    pub start_byte: u64,
+    pub end_byte: u64,
+}
+
+// 读取范围，本地文件和远程文件使用相同的参数；都不指定时读取整个文件。
+// 优先级依次为 tail_lines、tail_bytes、start_byte、start_line
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ReadRange {
+    pub start_line: Option<usize>, // 从第几行开始读取（从0计数）
+    pub line_count: Option<usize>, // 读取的行数
+    pub tail_lines: Option<usize>, // 读取最后N行
+    pub tail_bytes: Option<u64>,   // 读取最后N个字节，从其中第一个完整的行开始
+    pub start_byte: Option<u64>,   // 按字节分块读取，从这个偏移处（不在行首时为下一行）开始
+    pub max_bytes: Option<u64>,    // 与start_byte一起使用，读到超过这么多字节的那一行为止
+}
+
+impl ReadRange {
+    // 按行号读取时需要完整的行索引，其它方式直接从文件末尾或指定偏移读取
+    pub fn needs_index(&self) -> bool {
+        self.tail_lines.is_none() && self.tail_bytes.is_none() && self.start_byte.is_none()
+    }
+}
+
+// 按ReadRange读取的结果，没有行索引时行号未知
+#[derive(Debug, Clone)]
+pub struct RangeWindow {
+    pub window: LineWindow,
+    pub start_line: Option<usize>,
+    pub total_lines: Option<usize>,
+}
+
+impl LineIndex {
+    // 从头开始为reader建立索引
+    pub fn build<R: Read + Seek>(reader: &mut R, terminator: LineTerminator) -> std::io::Result<Self> {
+        let mut index = LineIndex {
+            terminator,
+            checkpoints: vec![0],
+            ..Default::default()
+        };
+        index.extend(reader)?;
+        Ok(index)
+    }
+
+    // 从上次扫描结束的位置继续建立索引（用于文件追加写入的情况）
+    pub fn extend<R: Read + Seek>(&mut self, reader: &mut R) -> std::io::Result<()> {
+        // 上次扫描可能停在半个编码单元上，退回到单元边界重新扫描
+        let unit = self.terminator.unit_len();
+        self.indexed_bytes -= self.indexed_bytes % unit as u64;
+        reader.seek(SeekFrom::Start(self.indexed_bytes))?;
+        let mut buffer = vec![0u8; SCAN_CHUNK_SIZE];
+
+        loop {
+            let n = fill_buffer(reader, &mut buffer)?;
+            if n == 0 {
+                break;
+            }
+
+            for (k, bytes) in buffer[..n].chunks_exact(unit).enumerate() {
+                if self.terminator.is_newline(bytes) {
+                    self.newline_count += 1;
+                    if self.newline_count == self.checkpoints.len() * INDEX_STRIDE {
+                        self.checkpoints.push(self.indexed_bytes + ((k + 1) * unit) as u64);
+                    }
+                }
+            }
+
+            if n >= unit {
+                let last_unit = (n / unit - 1) * unit;
+                self.ends_with_newline = self.terminator.is_newline(&buffer[last_unit..last_unit + unit]);
+            }
+            self.indexed_bytes += n as u64;
+        }
+
+        Ok(())
+    }
+
+    pub fn terminator(&self) -> LineTerminator {
+        self.terminator
+    }
+
+    pub fn indexed_bytes(&self) -> u64 {
+        self.indexed_bytes
+    }
+
+    // 与 str::lines() 的计数方式保持一致：最后一行没有换行符时也算一行
+    pub fn total_lines(&self) -> usize {
+        if self.indexed_bytes > 0 && !self.ends_with_newline {
+            self.newline_count + 1
+        } else {
+            self.newline_count
+        }
+    }
+
+    // 行首偏移offset所在的行号（从0计数）：跳到之前最近的检查点，再统计中间的换行符
+    pub fn line_at<R: Read + Seek>(&self, reader: &mut R, offset: u64) -> std::io::Result<usize> {
+        let checkpoint = self.checkpoints.partition_point(|&c| c <= offset).saturating_sub(1);
+        let unit = self.terminator.unit_len();
+        let mut position = self.checkpoints[checkpoint];
+        let mut line = checkpoint * INDEX_STRIDE;
+        reader.seek(SeekFrom::Start(position))?;
+        let mut buffer = vec![0u8; SCAN_CHUNK_SIZE];
+
+        while position < offset {
+            let chunk = (SCAN_CHUNK_SIZE as u64).min(offset - position) as usize;
+            reader.read_exact(&mut buffer[..chunk])?;
+            line += buffer[..chunk]
+                .chunks_exact(unit)
+                .filter(|bytes| self.terminator.is_newline(bytes))
+                .count();
+            position += chunk as u64;
+        }
+        Ok(line)
+    }
+
+    // 读取从start_line开始（从0计数）的最多count行，返回的行不包含换行符
+    pub fn read_window<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        start_line: usize,
+        count: usize,
+    ) -> std::io::Result<LineWindow> {
+        let total = self.total_lines();
+        if start_line >= total {
+            return Ok(LineWindow {
+                lines: Vec::new(),
+                start_byte: self.indexed_bytes,
+                end_byte: self.indexed_bytes,
+            });
+        }
+
+        // 跳到最近的检查点
+        let checkpoint = start_line / INDEX_STRIDE;
+        let mut position = self.checkpoints[checkpoint];
+        reader.seek(SeekFrom::Start(position))?;
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+
+        // 跳过检查点与起始行之间的行
+        for _ in 0..(start_line - checkpoint * INDEX_STRIDE) {
+            line.clear();
+            position += self.terminator.read_line(&mut reader, &mut line)? as u64;
+        }
+
+        let start_byte = position;
+        let wanted = count.min(total - start_line);
+        let mut lines = Vec::with_capacity(wanted);
+
+        while lines.len() < wanted {
+            line.clear();
+            let n = self.terminator.read_line(&mut reader, &mut line)?;
+            if n == 0 {
+                break;
+            }
+            position += n as u64;
+            lines.push(self.terminator.trim(&line).to_vec());
+        }
+
+        Ok(LineWindow {
+            lines,
+            start_byte,
+            end_byte: position,
+        })
+    }
+}
+
+// 读取[start, end)范围内开始的行，最多max_lines行，最后一行可以超出end
+fn read_lines<R: Read + Seek>(
+    reader: &mut R,
+    terminator: LineTerminator,
+    start: u64,
+    end: u64,
+    max_lines: usize,
+) -> std::io::Result<LineWindow> {
+    reader.seek(SeekFrom::Start(start))?;
+    let mut reader = BufReader::new(reader);
+    let mut position = start;
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+
+    while position < end && lines.len() < max_lines {
+        line.clear();
+        let n = terminator.read_line(&mut reader, &mut line)?;
+        if n == 0 {
+            break;
+        }
+        position += n as u64;
+        lines.push(terminator.trim(&line).to_vec());
+    }
+
+    Ok(LineWindow {
+        lines,
+        start_byte: start,
+        end_byte: position,
+    })
+}
+
+// offset不在行首时移动到下一行的行首
+fn align_to_line<R: Read + Seek>(reader: &mut R, terminator: LineTerminator, offset: u64) -> std::io::Result<u64> {
+    let unit = terminator.unit_len() as u64;
+    let offset = offset - offset % unit;
+    if offset == 0 {
+        return Ok(0);
+    }
+    // 从前一个编码单元开始读取一行，前一个单元是换行符时读到的长度正好是一个单元
+    reader.seek(SeekFrom::Start(offset - unit))?;
+    let mut line = Vec::new();
+    let n = terminator.read_line(&mut BufReader::new(reader), &mut line)?;
+    Ok(offset - unit + n as u64)
+}
+
+// 从文件末尾向前查找最后count行的起始位置，不需要扫描整个文件
+fn tail_start<R: Read + Seek>(reader: &mut R, terminator: LineTerminator, len: u64, count: usize) -> std::io::Result<u64> {
+    let unit = terminator.unit_len();
+    let end = len - len % unit as u64;
+    if count == 0 {
+        return Ok(end);
+    }
+
+    let mut position = end;
+    let mut newlines = 0;
+    let mut buffer = vec![0u8; SCAN_CHUNK_SIZE];
+    while position > 0 {
+        let chunk = (SCAN_CHUNK_SIZE as u64).min(position) as usize;
+        position -= chunk as u64;
+        reader.seek(SeekFrom::Start(position))?;
+        reader.read_exact(&mut buffer[..chunk])?;
+
+        for (k, bytes) in buffer[..chunk].chunks_exact(unit).enumerate().rev() {
+            let line_start = position + ((k + 1) * unit) as u64;
+            // 文件末尾的换行符属于最后一行
+            if line_start < end && terminator.is_newline(bytes) {
+                newlines += 1;
+                if newlines == count {
+                    return Ok(line_start);
+                }
+            }
+        }
+    }
+    Ok(0)
+}
+
+// 按ReadRange读取；index为None时只能使用不需要行号的读取方式，返回的行号也未知
+pub fn read_range<R: Read + Seek>(
+    reader: &mut R,
+    len: u64,
+    terminator: LineTerminator,
+    index: Option<&LineIndex>,
+    range: &ReadRange,
+) -> std::io::Result<RangeWindow> {
+    let total_lines = index.map(LineIndex::total_lines);
+
+    let (start, end, max_lines) = match (range.tail_lines, range.tail_bytes, range.start_byte, index) {
+        (Some(count), _, _, Some(index)) => {
+            let start_line = index.total_lines().saturating_sub(count);
+            return Ok(RangeWindow {
+                window: index.read_window(reader, start_line, count)?,
+                start_line: Some(start_line),
+                total_lines,
+            });
+        }
+        (Some(count), _, _, None) => (tail_start(reader, terminator, len, count)?, len, count),
+        (None, Some(bytes), _, _) => (align_to_line(reader, terminator, len.saturating_sub(bytes))?, len, usize::MAX),
+        (None, None, Some(offset), _) => {
+            let start = align_to_line(reader, terminator, offset.min(len))?;
+            let end = range.max_bytes.map_or(len, |max| start.saturating_add(max.max(1)).min(len));
+            (start, end, usize::MAX)
+        }
+        (None, None, None, Some(index)) => {
+            let start_line = range.start_line.unwrap_or(0);
+            let count = range.line_count.unwrap_or(usize::MAX);
+            return Ok(RangeWindow {
+                window: index.read_window(reader, start_line, count)?,
+                start_line: Some(start_line),
+                total_lines,
+            });
+        }
+        (None, None, None, None) => {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "按行号读取需要行索引"));
+        }
+    };
+
+    let window = read_lines(reader, terminator, start, end, max_lines)?;
+    let start_line = match index {
+        Some(index) if window.start_byte <= index.indexed_bytes() => Some(index.line_at(reader, window.start_byte)?),
+        _ => None,
+    };
+    Ok(RangeWindow {
+        window,
+        start_line,
+        total_lines,
+    })
+}
+
+// 缓存的索引，记录建立索引时文件的长度和修改时间，用于判断是否失效；
+// fingerprint是已索引部分开头和结尾的哈希，文件变长时用它确认之前的内容没有被替换
+#[derive(Debug, Clone)]
+struct CachedIndex {
+    index: LineIndex,
+    modified: Option<SystemTime>,
+    fingerprint: u64,
+    last_used: Instant,
+}
+
+// [0, end)范围开头和结尾各FINGERPRINT_BYTES字节的哈希。copytruncate轮转后文件被清空再写入，
+// 即使新文件已经超过原来的长度，原来已索引的部分内容也不同
+fn fingerprint<R: Read + Seek>(reader: &mut R, end: u64) -> std::io::Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    let mut buffer = vec![0u8; FINGERPRINT_BYTES as usize];
+    let tail = end.saturating_sub(FINGERPRINT_BYTES).max(FINGERPRINT_BYTES.min(end));
+    for (start, stop) in [(0, FINGERPRINT_BYTES.min(end)), (tail, end)] {
+        let chunk = &mut buffer[..(stop - start) as usize];
+        reader.seek(SeekFrom::Start(start))?;
+        reader.read_exact(chunk)?;
+        hasher.write(chunk);
+    }
+    Ok(hasher.finish())
+}
+
+lazy_static! {
+    static ref LINE_INDEX_CACHE: Mutex<HashMap<PathBuf, CachedIndex>> = Mutex::new(HashMap::new());
+}
+
+// 按key缓存的行索引，远程文件使用 sftp:// 地址作为key。
+// build为false时只返回已有的索引（追加写入时增量扫描），没有索引时不为了这次读取扫描整个文件
+pub fn cached_index<R: Read + Seek>(
+    key: &Path,
+    reader: &mut R,
+    len: u64,
+    modified: Option<SystemTime>,
+    terminator: LineTerminator,
+    build: bool,
+) -> std::io::Result<Option<LineIndex>> {
+    // 换行符形式不同（例如调用方指定了其它编码）时索引不能复用
+    let cached = LINE_INDEX_CACHE
+        .lock()
+        .unwrap()
+        .get_mut(key)
+        .filter(|cached| cached.index.terminator() == terminator)
+        .map(|cached| {
+            cached.last_used = Instant::now();
+            cached.clone()
+        });
+
+    let index = match cached {
+        Some(cached) if cached.index.indexed_bytes() == len && cached.modified == modified => {
+            return Ok(Some(cached.index));
+        }
+        Some(mut cached)
+            if cached.index.indexed_bytes() < len
+                && fingerprint(reader, cached.index.indexed_bytes())? == cached.fingerprint =>
+        {
+            info!("Extending line index for {:?} from byte {}", key, cached.index.indexed_bytes());
+            cached.index.extend(reader)?;
+            cached.index
+        }
+        _ if !build => {
+            // 文件已被截断或替换，旧索引不能再用于增量扫描
+            LINE_INDEX_CACHE.lock().unwrap().remove(key);
+            return Ok(None);
+        }
+        _ => {
+            info!("Building line index for {:?} ({} bytes)", key, len);
+            LineIndex::build(reader, terminator)?
+        }
+    };
+
+    let fingerprint = fingerprint(reader, index.indexed_bytes())?;
+    let mut cache = LINE_INDEX_CACHE.lock().unwrap();
+    cache.insert(
+        key.to_path_buf(),
+        CachedIndex {
+            index: index.clone(),
+            modified,
+            fingerprint,
+            last_used: Instant::now(),
+        },
+    );
+    if cache.len() > MAX_CACHED_INDEXES {
+        if let Some(oldest) = cache.iter().min_by_key(|(_, cached)| cached.last_used).map(|(key, _)| key.clone()) {
+            cache.remove(&oldest);
+        }
+    }
+
+    Ok(Some(index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn numbered_lines(count: usize) -> Vec<u8> {
+        (0..count).map(|i| format!("line {}\n", i)).collect::<String>().into_bytes()
+    }
+
+    fn utf16(text: &str, terminator: LineTerminator) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| match terminator {
+                LineTerminator::Utf16Be => unit.to_be_bytes(),
+                _ => unit.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn checkpoints_every_stride_lines() {
+        let data = numbered_lines(INDEX_STRIDE * 2 + 10);
+        let index = LineIndex::build(&mut Cursor::new(&data), LineTerminator::Byte).unwrap();
+
+        assert_eq!(index.total_lines(), INDEX_STRIDE * 2 + 10);
+        assert_eq!(index.checkpoints.len(), 3);
+        for (i, &offset) in index.checkpoints.iter().enumerate() {
+            let expected = format!("line {}\n", i * INDEX_STRIDE);
+            assert!(data[offset as usize..].starts_with(expected.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn read_window_across_checkpoint() {
+        let data = numbered_lines(INDEX_STRIDE * 2);
+        let mut reader = Cursor::new(&data);
+        let index = LineIndex::build(&mut reader, LineTerminator::Byte).unwrap();
+
+        let window = index.read_window(&mut reader, INDEX_STRIDE - 1, 3).unwrap();
+        let lines: Vec<_> = window.lines.iter().map(|line| String::from_utf8_lossy(line)).collect();
+        assert_eq!(lines, [format!("line {}", INDEX_STRIDE - 1), format!("line {}", INDEX_STRIDE), format!("line {}", INDEX_STRIDE + 1)]);
+        assert_eq!(index.line_at(&mut reader, window.start_byte).unwrap(), INDEX_STRIDE - 1);
+    }
+
+    #[test]
+    fn extend_matches_full_build() {
+        let data = numbered_lines(INDEX_STRIDE + 100);
+        let half = data.len() / 2 + 3;
+        let mut index = LineIndex::build(&mut Cursor::new(&data[..half]), LineTerminator::Byte).unwrap();
+        index.extend(&mut Cursor::new(&data)).unwrap();
+        let full = LineIndex::build(&mut Cursor::new(&data), LineTerminator::Byte).unwrap();
+
+        assert_eq!(index.checkpoints, full.checkpoints);
+        assert_eq!(index.total_lines(), full.total_lines());
+    }
+
+    #[test]
+    fn utf16_newline_only_on_unit_boundary() {
+        // U+0A41 的UTF-16编码中含有0x0A字节，但不是换行符
+        for terminator in [LineTerminator::Utf16Le, LineTerminator::Utf16Be] {
+            let data = utf16("a\u{0A41}b\r\nsecond\nthird", terminator);
+            let mut reader = Cursor::new(&data);
+            let index = LineIndex::build(&mut reader, terminator).unwrap();
+            assert_eq!(index.total_lines(), 3);
+
+            let window = index.read_window(&mut reader, 0, 3).unwrap();
+            let expected = ["a\u{0A41}b", "second", "third"].map(|line| utf16(line, terminator));
+            assert_eq!(window.lines, expected);
+        }
+    }
+
+    #[test]
+    fn utf16_tail_lines() {
+        let terminator = LineTerminator::Utf16Le;
+        let data = utf16("one\ntwo\u{0A0A}\nthree\n", terminator);
+        let range = ReadRange {
+            tail_lines: Some(2),
+            ..Default::default()
+        };
+        let result = read_range(&mut Cursor::new(&data), data.len() as u64, terminator, None, &range).unwrap();
+        assert_eq!(result.window.lines, [utf16("two\u{0A0A}", terminator), utf16("three", terminator)]);
+    }
+
+    #[test]
+    fn cached_index_extends_appended_file() {
+        let key = Path::new("test://cached_index_extends_appended_file");
+        let data = numbered_lines(100);
+        let half = &data[..data.len() / 2];
+        cached_index(key, &mut Cursor::new(half), half.len() as u64, None, LineTerminator::Byte, true).unwrap();
+
+        let index = cached_index(key, &mut Cursor::new(&data), data.len() as u64, None, LineTerminator::Byte, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(index.total_lines(), 100);
+    }
+
+    #[test]
+    fn cached_index_rebuilds_after_truncate_and_regrow() {
+        let key = Path::new("test://cached_index_rebuilds_after_truncate_and_regrow");
+        let before = numbered_lines(INDEX_STRIDE + 10);
+        cached_index(key, &mut Cursor::new(&before), before.len() as u64, None, LineTerminator::Byte, true).unwrap();
+
+        // copytruncate之后写入的新内容比原来更长，行的长度也不同
+        let after: Vec<u8> = (0..INDEX_STRIDE * 2).map(|i| format!("new entry {}\n", i)).collect::<String>().into_bytes();
+        assert!(after.len() > before.len());
+        let mut reader = Cursor::new(&after);
+        assert!(cached_index(key, &mut reader, after.len() as u64, None, LineTerminator::Byte, false)
+            .unwrap()
+            .is_none());
+
+        let index = cached_index(key, &mut reader, after.len() as u64, None, LineTerminator::Byte, true)
+            .unwrap()
+            .unwrap();
+        assert_eq!(index.total_lines(), INDEX_STRIDE * 2);
+        let window = index.read_window(&mut reader, INDEX_STRIDE, 1).unwrap();
+        assert_eq!(window.lines, [format!("new entry {}", INDEX_STRIDE).into_bytes()]);
+    }
+
+    #[test]
+    fn cache_is_bounded() {
+        let data = numbered_lines(3);
+        for i in 0..MAX_CACHED_INDEXES + 10 {
+            let key = PathBuf::from(format!("test://cache_is_bounded/{}", i));
+            cached_index(&key, &mut Cursor::new(&data), data.len() as u64, None, LineTerminator::Byte, true).unwrap();
+        }
+        assert!(LINE_INDEX_CACHE.lock().unwrap().len() <= MAX_CACHED_INDEXES);
+    }
+}
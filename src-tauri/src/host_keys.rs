@@ -0,0 +1,282 @@
+// SSH主机密钥校验：握手后、发送认证信息之前，用 ~/.ssh/known_hosts 和应用自己的 known_hosts 校验服务器密钥。
+// 未知的密钥需要用户确认指纹后才保存（首次使用时信任），已保存的密钥发生变化时直接拒绝连接
+use crate::error::EasyLogError;
+use base64::engine::general_purpose::{STANDARD as BASE64, STANDARD_NO_PAD as BASE64_NO_PAD};
+use base64::Engine;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use serde::Serialize;
+use ssh2::{CheckResult, HashType, HostKeyType, KnownHostFileKind, Session};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const KNOWN_HOSTS_FILE: &str = "known_hosts";
+
+lazy_static! {
+    static ref APP_KNOWN_HOSTS: Mutex<Option<PathBuf>> = Mutex::new(None);
+    // 等待用户确认的未知密钥，键为(host, port)
+    static ref PENDING_KEYS: Mutex<HashMap<(String, u16), UnknownHostKey>> = Mutex::new(HashMap::new());
+}
+
+// 应用启动时设置应用管理的 known_hosts 文件位置
+pub fn init(app_dir: &Path) {
+    *APP_KNOWN_HOSTS.lock().unwrap() = Some(app_dir.join(KNOWN_HOSTS_FILE));
+}
+
+// ssh-host-key-unknown 事件的数据，前端展示指纹让用户确认
+#[derive(Debug, Serialize, Clone)]
+pub struct UnknownHostKey {
+    host: String,
+    port: u16,
+    key_type: String,
+    fingerprint: String,
+    #[serde(skip)]
+    key: String, // base64编码的公钥，确认后写入 known_hosts
+}
+
+// known_hosts 中的主机名写法，非22端口写成 [host]:port
+fn host_entry(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+fn key_type_name(key_type: HostKeyType) -> Option<&'static str> {
+    match key_type {
+        HostKeyType::Rsa => Some("ssh-rsa"),
+        HostKeyType::Dss => Some("ssh-dss"),
+        HostKeyType::Ecdsa256 => Some("ecdsa-sha2-nistp256"),
+        HostKeyType::Ecdsa384 => Some("ecdsa-sha2-nistp384"),
+        HostKeyType::Ecdsa521 => Some("ecdsa-sha2-nistp521"),
+        HostKeyType::Ed25519 => Some("ssh-ed25519"),
+        HostKeyType::Unknown => None,
+    }
+}
+
+// 与 ssh-keygen -l 相同的指纹格式：SHA256:<base64>
+fn fingerprint(session: &Session) -> String {
+    match session.host_key_hash(HashType::Sha256) {
+        Some(hash) => format!("SHA256:{}", BASE64_NO_PAD.encode(hash)),
+        None => "SHA256:?".to_string(),
+    }
+}
+
+fn user_known_hosts() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join(KNOWN_HOSTS_FILE))
+}
+
+fn app_known_hosts() -> Option<PathBuf> {
+    APP_KNOWN_HOSTS.lock().unwrap().clone()
+}
+
+// 用一个 known_hosts 文件校验密钥。服务器可能有多种类型的密钥，文件里记录的类型不一定是这次协商出来的，
+// 所以只比较同一类型的记录，其它类型的记录不算作密钥改变
+fn check_file(session: &Session, path: &Path, host: &str, port: u16, key_type: &str, key: &[u8]) -> CheckResult {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return CheckResult::NotFound;
+    };
+    let Ok(mut known_hosts) = session.known_hosts() else {
+        return CheckResult::Failure;
+    };
+
+    for line in content.lines().map(str::trim) {
+        // 跳过注释以及 @cert-authority / @revoked 等带标记的记录
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+            continue;
+        }
+        if line.split_whitespace().nth(1) != Some(key_type) {
+            continue;
+        }
+        if let Err(e) = known_hosts.read_str(line, KnownHostFileKind::OpenSSH) {
+            warn!("Skipping invalid known_hosts entry in {}: {}", path.display(), e);
+        }
+    }
+    known_hosts.check_port(host, port, key)
+}
+
+// 握手完成后校验服务器的主机密钥，未通过时返回错误，不能继续认证
+pub fn verify(session: &Session, host: &str, port: u16) -> Result<(), EasyLogError> {
+    let (key, key_type) = session
+        .host_key()
+        .ok_or_else(|| EasyLogError::internal("无法获取服务器的主机密钥"))?;
+    let key_type = key_type_name(key_type).ok_or_else(|| EasyLogError::internal("不支持的主机密钥类型"))?;
+    let fingerprint = fingerprint(session);
+
+    let mut found = false;
+    for path in [user_known_hosts(), app_known_hosts()].into_iter().flatten() {
+        match check_file(session, &path, host, port, key_type, key) {
+            CheckResult::Match => found = true,
+            CheckResult::Mismatch => {
+                return Err(EasyLogError::HostKeyMismatch {
+                    host: host.to_string(),
+                    port,
+                    key_type: key_type.to_string(),
+                    fingerprint,
+                    known_hosts: path.display().to_string(),
+                });
+            }
+            CheckResult::NotFound => {}
+            CheckResult::Failure => warn!("Failed to check {} against {}", host, path.display()),
+        }
+    }
+    if found {
+        return Ok(());
+    }
+
+    // 未知的密钥：通知前端确认指纹，确认后重新连接
+    let unknown = UnknownHostKey {
+        host: host.to_string(),
+        port,
+        key_type: key_type.to_string(),
+        fingerprint: fingerprint.clone(),
+        key: BASE64.encode(key),
+    };
+    PENDING_KEYS
+        .lock()
+        .unwrap()
+        .insert((host.to_string(), port), unknown.clone());
+    crate::emit_app_event("ssh-host-key-unknown", unknown);
+
+    Err(EasyLogError::HostKeyUnknown {
+        host: host.to_string(),
+        port,
+        key_type: key_type.to_string(),
+        fingerprint,
+    })
+}
+
+fn known_hosts_path() -> Result<PathBuf, EasyLogError> {
+    app_known_hosts().ok_or_else(|| EasyLogError::internal("known_hosts 文件位置未初始化"))
+}
+
+// 用户确认指纹后，把密钥保存到应用的 known_hosts 中
+#[tauri::command]
+pub fn trust_host_key(host: String, port: Option<u16>, fingerprint: String) -> Result<(), EasyLogError> {
+    let port = port.unwrap_or(22);
+    let path = known_hosts_path()?;
+
+    let mut pending = PENDING_KEYS.lock().unwrap();
+    let key = pending
+        .get(&(host.clone(), port))
+        .ok_or_else(|| EasyLogError::invalid("host", format!("没有等待确认的 {}:{} 主机密钥", host, port)))?;
+    // 只保存用户看到的那个密钥，期间密钥又变化时需要重新确认
+    if key.fingerprint != fingerprint {
+        return Err(EasyLogError::invalid(
+            "fingerprint",
+            format!("主机密钥指纹不一致: {}", key.fingerprint),
+        ));
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| EasyLogError::io(&path, e))?;
+    writeln!(file, "{} {} {}", host_entry(&host, port), key.key_type, key.key).map_err(|e| EasyLogError::io(&path, e))?;
+
+    info!("Trusted host key {} {} for {}:{}", key.key_type, key.fingerprint, host, port);
+    pending.remove(&(host, port));
+    Ok(())
+}
+
+// 删除应用 known_hosts 中保存的主机密钥（服务器更换密钥后使用），~/.ssh/known_hosts 不做修改
+#[tauri::command]
+pub fn remove_host_key(host: String, port: Option<u16>) -> Result<usize, EasyLogError> {
+    let port = port.unwrap_or(22);
+    let path = known_hosts_path()?;
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return Ok(0);
+    };
+
+    let entry = host_entry(&host, port);
+    let kept: Vec<&str> = content
+        .lines()
+        .filter(|line| line.split_whitespace().next() != Some(entry.as_str()))
+        .collect();
+    let removed = content.lines().count() - kept.len();
+    if removed > 0 {
+        let mut output = kept.join("\n");
+        if !output.is_empty() {
+            output.push('\n');
+        }
+        std::fs::write(&path, output).map_err(|e| EasyLogError::io(&path, e))?;
+        info!("Removed {} host key(s) for {}", removed, entry);
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_1: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIKoff9QdF+aL1oSBJhLFqjRxkW5+i+ua/SWp9BQnJBSu";
+    const KEY_2: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIF/0aEog08CfMtR4DN+AyyrIREhsK/6cyxkbJLXJc6uY";
+    // ssh-keygen -H 生成的记录：example.com 使用KEY_1，[example.com]:2222 使用KEY_2
+    const HASHED: &str = "\
+|1|JS8vkqgo6FikiMyHM+nSd81GZEY=|novBzjs2FTsVicFqJN+rQz4pgkQ= ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIKoff9QdF+aL1oSBJhLFqjRxkW5+i+ua/SWp9BQnJBSu
+|1|/vZF3hYGZg9qIy7giO4xzMeITVg=|4WGArP7d/3KkjmrVheUQCVmk8KQ= ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIF/0aEog08CfMtR4DN+AyyrIREhsK/6cyxkbJLXJc6uY
+";
+
+    fn check(content: &str, host: &str, port: u16, key: &str) -> CheckResult {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(KNOWN_HOSTS_FILE);
+        std::fs::write(&path, content).unwrap();
+        let key = BASE64.decode(key).unwrap();
+        check_file(&Session::new().unwrap(), &path, host, port, "ssh-ed25519", &key)
+    }
+
+    #[test]
+    fn host_entry_brackets_non_default_ports() {
+        assert_eq!(host_entry("example.com", 22), "example.com");
+        assert_eq!(host_entry("example.com", 2222), "[example.com]:2222");
+    }
+
+    #[test]
+    fn plain_entries() {
+        let content = format!("example.com,10.0.0.1 ssh-ed25519 {}\n[example.com]:2222 ssh-ed25519 {}\n", KEY_1, KEY_2);
+        assert!(matches!(check(&content, "example.com", 22, KEY_1), CheckResult::Match));
+        assert!(matches!(check(&content, "10.0.0.1", 22, KEY_1), CheckResult::Match));
+        assert!(matches!(check(&content, "example.com", 22, KEY_2), CheckResult::Mismatch));
+        assert!(matches!(check(&content, "example.com", 2222, KEY_2), CheckResult::Match));
+        assert!(matches!(check(&content, "other.com", 22, KEY_1), CheckResult::NotFound));
+
+        let content = format!("[example.com]:2222 ssh-ed25519 {}\n", KEY_2);
+        assert!(matches!(check(&content, "example.com", 2222, KEY_1), CheckResult::Mismatch));
+        assert!(matches!(check(&content, "example.com", 22, KEY_2), CheckResult::NotFound));
+    }
+
+    #[test]
+    fn hashed_entries() {
+        assert!(matches!(check(HASHED, "example.com", 22, KEY_1), CheckResult::Match));
+        assert!(matches!(check(HASHED, "example.com", 22, KEY_2), CheckResult::Mismatch));
+        assert!(matches!(check(HASHED, "example.com", 2222, KEY_2), CheckResult::Match));
+        assert!(matches!(check(HASHED, "example.org", 22, KEY_1), CheckResult::NotFound));
+    }
+
+    #[test]
+    fn other_key_types_are_not_a_mismatch() {
+        let content = "example.com ecdsa-sha2-nistp256 AAAAE2VjZHNhLXNoYTItbmlzdHAyNTY=\n";
+        assert!(matches!(check(content, "example.com", 22, KEY_1), CheckResult::NotFound));
+    }
+
+    #[test]
+    fn skips_comments_markers_and_invalid_lines() {
+        let content = format!(
+            "# comment\n\n@revoked example.com ssh-ed25519 {}\nexample.com ssh-ed25519 not*base64\n  example.com ssh-ed25519 {}  \n",
+            KEY_2, KEY_1
+        );
+        assert!(matches!(check(&content, "example.com", 22, KEY_1), CheckResult::Match));
+    }
+
+    #[test]
+    fn missing_file_is_not_found() {
+        let key = BASE64.decode(KEY_1).unwrap();
+        let path = Path::new("/nonexistent/easylog/known_hosts");
+        let result = check_file(&Session::new().unwrap(), path, "example.com", 22, "ssh-ed25519", &key);
+        assert!(matches!(result, CheckResult::NotFound));
+    }
+}
@@ -0,0 +1,211 @@
+// JSON Lines（NDJSON）日志解析：每行一个JSON对象，自动识别常见的时间、级别、消息字段，
+// 嵌套字段按点号路径展开（例如 http.request.method），不是JSON对象的行交由调用方按原文保留
+use crate::parser::{normalize_level, parse_timestamp, LineParser, LogRecord};
+use serde_json::Value;
+
+// 常见的字段名，按优先级排列
+const TIMESTAMP_KEYS: &[&str] = &["@timestamp", "timestamp", "ts", "time", "datetime", "date", "t"];
+const LEVEL_KEYS: &[&str] = &["level", "severity", "lvl", "loglevel", "levelname", "log.level"];
+const MESSAGE_KEYS: &[&str] = &["message", "msg", "@message", "log", "text"];
+const LOGGER_KEYS: &[&str] = &["logger", "logger_name", "log.logger", "name"];
+const THREAD_KEYS: &[&str] = &["thread", "thread_name", "threadName", "process.thread.name"];
+
+pub struct JsonLinesParser;
+
+impl LineParser for JsonLinesParser {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn description(&self) -> &'static str {
+        "JSON Lines / NDJSON: one JSON object per line"
+    }
+
+    fn parse(&self, line: &str) -> Option<LogRecord> {
+        let trimmed = line.trim();
+        if !trimmed.starts_with('{') {
+            return None;
+        }
+        let Ok(Value::Object(object)) = serde_json::from_str::<Value>(trimmed) else {
+            return None;
+        };
+
+        let mut fields = Vec::new();
+        flatten("", &Value::Object(object), &mut fields);
+
+        let mut record = LogRecord {
+            raw: line.to_string(),
+            format: Some(self.name().to_string()),
+            ..Default::default()
+        };
+
+        if let Some(value) = take_first(&mut fields, TIMESTAMP_KEYS) {
+            record.timestamp_ms = json_timestamp(&value);
+            record.timestamp = Some(value_to_string(&value));
+        }
+        if let Some(value) = take_first(&mut fields, LEVEL_KEYS) {
+            record.level = Some(json_level(&value));
+        }
+        if let Some(value) = take_first(&mut fields, MESSAGE_KEYS) {
+            record.message = value_to_string(&value);
+        }
+        if let Some(value) = take_first(&mut fields, LOGGER_KEYS) {
+            record.logger = Some(value_to_string(&value));
+        }
+        if let Some(value) = take_first(&mut fields, THREAD_KEYS) {
+            record.thread = Some(value_to_string(&value));
+        }
+
+        record.fields = fields.into_iter().map(|(k, v)| (k, value_to_string(&v))).collect();
+        Some(record)
+    }
+}
+
+// 把嵌套对象和数组展开为点号路径，例如 {"http":{"status":200}} -> http.status
+fn flatten(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+
+    match value {
+        Value::Object(map) if !map.is_empty() || prefix.is_empty() => {
+            for (key, child) in map {
+                flatten(&join(key), child, out);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, child) in items.iter().enumerate() {
+                flatten(&join(&i.to_string()), child, out);
+            }
+        }
+        _ => out.push((prefix.to_string(), value.clone())),
+    }
+}
+
+// 取出第一个存在的字段
+fn take_first(fields: &mut Vec<(String, Value)>, keys: &[&str]) -> Option<Value> {
+    keys.iter().find_map(|key| {
+        let index = fields.iter().position(|(k, v)| k == key && !v.is_null())?;
+        Some(fields.remove(index).1)
+    })
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// 数字时间戳可能是秒（可带小数，如zap）或毫秒（如pino）
+fn json_timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => {
+            let n = n.as_f64()?;
+            if n > 1e12 {
+                Some(n as i64)
+            } else {
+                Some((n * 1000.0) as i64)
+            }
+        }
+        Value::String(s) => parse_timestamp(s),
+        _ => None,
+    }
+}
+
+// 数字级别采用bunyan/pino的约定：10 trace, 20 debug, 30 info, 40 warn, 50 error, 60 fatal
+fn json_level(value: &Value) -> String {
+    match value {
+        Value::Number(n) => match n.as_u64().unwrap_or(0) {
+            0..=19 => "TRACE",
+            20..=29 => "DEBUG",
+            30..=39 => "INFO",
+            40..=49 => "WARN",
+            50..=59 => "ERROR",
+            _ => "FATAL",
+        }
+        .to_string(),
+        other => normalize_level(&value_to_string(other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> LogRecord {
+        JsonLinesParser.parse(line).expect("json object")
+    }
+
+    #[test]
+    fn extracts_common_fields() {
+        let record = parse(
+            r#"{"@timestamp":"2024-01-02T03:04:05Z","level":"warning","message":"slow query","logger":"db","thread":"worker-1","took_ms":512}"#,
+        );
+        assert_eq!(record.format.as_deref(), Some("json"));
+        assert_eq!(record.timestamp.as_deref(), Some("2024-01-02T03:04:05Z"));
+        assert_eq!(record.timestamp_ms, Some(1_704_164_645_000));
+        assert_eq!(record.level.as_deref(), Some("WARN"));
+        assert_eq!(record.message, "slow query");
+        assert_eq!(record.logger.as_deref(), Some("db"));
+        assert_eq!(record.thread.as_deref(), Some("worker-1"));
+        assert_eq!(record.fields.get("took_ms").map(String::as_str), Some("512"));
+        assert!(!record.fields.contains_key("message"));
+    }
+
+    #[test]
+    fn flattens_nested_objects_and_arrays() {
+        let record = parse(r#"{"msg":"req","http":{"request":{"method":"GET"},"status":200},"tags":["a","b"],"empty":{},"none":[],"nil":null}"#);
+        let field = |name: &str| record.fields.get(name).map(String::as_str);
+        assert_eq!(field("http.request.method"), Some("GET"));
+        assert_eq!(field("http.status"), Some("200"));
+        assert_eq!(field("tags.0"), Some("a"));
+        assert_eq!(field("tags.1"), Some("b"));
+        assert_eq!(field("empty"), Some("{}"));
+        assert_eq!(field("none"), Some("[]"));
+        assert_eq!(field("nil"), Some("null"));
+    }
+
+    #[test]
+    fn dotted_paths_match_well_known_keys() {
+        let record = parse(r#"{"log":{"level":"error","logger":"svc"},"message":"boom"}"#);
+        assert_eq!(record.level.as_deref(), Some("ERROR"));
+        assert_eq!(record.logger.as_deref(), Some("svc"));
+    }
+
+    #[test]
+    fn numeric_timestamps_and_levels() {
+        // pino：毫秒时间戳，数字级别
+        let record = parse(r#"{"time":1704164645123,"level":50,"msg":"failed"}"#);
+        assert_eq!(record.timestamp_ms, Some(1_704_164_645_123));
+        assert_eq!(record.level.as_deref(), Some("ERROR"));
+
+        // zap：带小数的秒
+        let record = parse(r#"{"ts":1704164645.5,"level":"info","msg":"ok"}"#);
+        assert_eq!(record.timestamp_ms, Some(1_704_164_645_500));
+        assert_eq!(record.level.as_deref(), Some("INFO"));
+
+        let record = parse(r#"{"level":10}"#);
+        assert_eq!(record.level.as_deref(), Some("TRACE"));
+        let record = parse(r#"{"level":60}"#);
+        assert_eq!(record.level.as_deref(), Some("FATAL"));
+    }
+
+    #[test]
+    fn null_values_fall_through_to_next_key() {
+        let record = parse(r#"{"message":null,"msg":"fallback"}"#);
+        assert_eq!(record.message, "fallback");
+    }
+
+    #[test]
+    fn rejects_non_objects() {
+        assert!(JsonLinesParser.parse("plain text").is_none());
+        assert!(JsonLinesParser.parse("[1,2,3]").is_none());
+        assert!(JsonLinesParser.parse("{not json").is_none());
+        assert!(JsonLinesParser.parse("  {\"msg\":\"indented\"}  ").is_some());
+    }
+}
@@ -2,27 +2,164 @@
 use log::{debug, error, info, warn, LevelFilter};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read};
-use ssh2::Session;
-use std::path::Path;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use tauri::Manager;
-use tokio;
 use tauri::Emitter;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use lazy_static::lazy_static;
+use error::EasyLogError;
+use remote_command::{Program, RemoteCommand};
+use tokio_util::sync::CancellationToken;
 
-// 定义活跃连接管理器结构
+mod auth_prompt;
+mod compression;
+mod encoding;
+mod error;
+mod fanout;
+mod file_index;
+mod host_keys;
+mod json_lines;
+mod local_monitor;
+mod multiline;
+mod parser;
+mod profiles;
+mod remote_command;
+mod remote_files;
+mod rotation;
+mod search;
+mod ssh;
+mod ssh_config;
+mod stream_batch;
+mod time_filter;
+mod tunnel;
+
+// 一个正在运行的远程日志流，同一服务器上可以同时运行多个
+#[derive(Debug, Serialize, Clone)]
+struct RemoteStreamInfo {
+    stream_id: String,
+    host: String,
+    port: u16,
+    username: String,
+    log_path: String,
+    started_at: u64, // 开始监控的时间（Unix秒）
+    // 多主机合并监控中的一个成员时，所属合并流的id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fanout_id: Option<String>,
+}
+
+struct RemoteStream {
+    info: RemoteStreamInfo,
+    cancel: CancellationToken,
+    finished: tokio::sync::oneshot::Receiver<Teardown>, // 读取线程结束时发送清理结果
+}
+
+// 日志流结束时的清理结果
 #[derive(Debug, Default)]
+struct Teardown {
+    remote_pid: Option<u32>, // 最后一次连接上远程tail进程的进程号
+    process_killed: bool,    // 是否已经结束了远程的tail进程
+}
+
+// 停止后等待清理完成的最长时间
+const TEARDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+// 定义活跃连接管理器结构
+#[derive(Default)]
 struct ActiveConnections {
-    // 使用(host, port)作为键
-    connections: HashMap<(String, u16), String>, // 值为log_path，用于识别连接
+    // 使用流id作为键，每个流有自己的停止标志
+    streams: HashMap<String, RemoteStream>,
+}
+
+impl ActiveConnections {
+    // 请求停止指定的流，返回被停止的流，可以等待它完成清理
+    fn stop(&mut self, stream_id: &str) -> Option<RemoteStream> {
+        let stream = self.streams.remove(stream_id)?;
+        stream.cancel.cancel();
+        Some(stream)
+    }
+
+    // 请求停止属于某个合并流的所有成员
+    fn stop_fanout(&mut self, fanout_id: &str) -> Vec<RemoteStream> {
+        let ids: Vec<String> = self
+            .streams
+            .values()
+            .filter(|stream| stream.info.fanout_id.as_deref() == Some(fanout_id))
+            .map(|stream| stream.info.stream_id.clone())
+            .collect();
+        ids.iter().filter_map(|id| self.stop(id)).collect()
+    }
+
+    // 流自行结束时移除；被停止或被同一id的新流替换时已经移除并取消，不能再移除
+    fn finish(&mut self, stream_id: &str, cancel: &CancellationToken) {
+        if !cancel.is_cancelled() {
+            self.streams.remove(stream_id);
+        }
+    }
 }
 
 // 使用lazy_static创建全局的活跃连接管理器
 lazy_static! {
     static ref ACTIVE_CONNECTIONS: Arc<Mutex<ActiveConnections>> = Arc::new(Mutex::new(ActiveConnections::default()));
+    // 应用句柄，用于在没有窗口参数的地方（例如SSH连接过程中）向前端发送事件
+    static ref APP_HANDLE: Mutex<Option<tauri::AppHandle>> = Mutex::new(None);
+}
+
+// 向前端发送全局事件，应用还没有启动完成时返回false
+fn emit_app_event<S: Serialize + Clone>(event: &str, payload: S) -> bool {
+    match APP_HANDLE.lock().unwrap().as_ref() {
+        Some(app) => match app.emit(event, payload) {
+            Ok(()) => true,
+            Err(e) => {
+                error!("Failed to emit {}: {}", event, e);
+                false
+            }
+        },
+        None => false,
+    }
+}
+
+// 没有指定流id时按顺序生成
+static NEXT_STREAM_ID: AtomicU64 = AtomicU64::new(1);
+
+// ssh-log-connected / ssh-log-disconnected / ssh-log-error 事件的数据
+#[derive(Debug, Serialize, Clone)]
+struct RemoteStreamEvent {
+    #[serde(flatten)]
+    stream: RemoteStreamInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    // ssh-log-error 附带的结构化错误，与命令返回的错误格式相同
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<EasyLogError>,
+}
+
+fn emit_stream_event(window: &tauri::Window, event: &str, stream: &RemoteStreamInfo, message: Option<String>) {
+    send_stream_event(window, event, stream, message, None);
+}
+
+fn emit_stream_error(window: &tauri::Window, stream: &RemoteStreamInfo, error: EasyLogError) {
+    send_stream_event(window, "ssh-log-error", stream, Some(error.to_string()), Some(error));
+}
+
+fn send_stream_event(
+    window: &tauri::Window,
+    event: &str,
+    stream: &RemoteStreamInfo,
+    message: Option<String>,
+    error: Option<EasyLogError>,
+) {
+    let payload = RemoteStreamEvent {
+        stream: stream.clone(),
+        message,
+        error,
+    };
+    if let Err(e) = window.emit(event, payload) {
+        error!("Failed to emit {}: {}", event, e);
+    }
 }
 
 // 初始化日志系统的函数
@@ -37,12 +174,54 @@ fn setup_logging() {
 struct FileContent {
     content: String,
     total_lines: usize,
+    start_line: usize,  // 返回窗口的起始行（从0计数）
+    start_byte: u64,    // 返回窗口在文件中的起始字节偏移
+    end_byte: u64,      // 返回窗口在文件中的结束字节偏移（不含）
+    encoding: String,   // 解码使用的编码
+    // 行号是否已知：远程文件按最后N行/字节等方式读取且还没有行索引时为false，
+    // 此时start_line为0，total_lines为返回的行数
+    line_numbers_known: bool,
+    // 请求解析或多行分组时返回的记录，此时content为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    records: Option<Vec<parser::LogRecord>>,
+}
+
+impl FileContent {
+    // 把内容转换为记录，转换后不再返回原始文本：指定selection时解析为结构化记录；
+    // 指定记录起始规则时，续行合并到前一条记录中，只分组不解析时记录只包含原文
+    fn into_records(mut self, selection: Option<&str>, record_start: Option<&multiline::RecordStart>) -> Result<Self, EasyLogError> {
+        let first_line_number = self.start_line + 1;
+        let records = match (selection, record_start) {
+            (None, None) => return Ok(self),
+            (Some(selection), None) => parser::parse_content(selection, &self.content, first_line_number)?,
+            (Some(selection), Some(start)) => {
+                let mut record_parser = parser::RecordParser::new(selection)?;
+                multiline::group_lines(start, &self.content, first_line_number)?
+                    .iter()
+                    .map(|group| multiline::parse_group(&mut record_parser, group))
+                    .collect()
+            }
+            (None, Some(start)) => multiline::group_lines(start, &self.content, first_line_number)?
+                .into_iter()
+                .map(multiline::GroupedRecord::into_raw_record)
+                .collect(),
+        };
+        self.content = String::new();
+        self.records = Some(records);
+        Ok(self)
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct FileReadOptions {
     path: String,
-    max_lines: Option<usize>, // 可选参数，限制读取行数
+    max_lines: Option<usize>, // 可选参数，限制读取行数（等同于line_count，保留以兼容旧调用）
+    // 可选参数，读取范围：行区间、最后N行或N字节、按字节分块，与远程读取相同
+    #[serde(flatten)]
+    range: file_index::ReadRange,
+    encoding: Option<String>, // 可选参数，指定文件编码（如 "gbk"、"utf-16le"），为空或"auto"时自动识别
+    parse: Option<String>, // 可选参数，返回解析后的结构化记录，"auto"为自动识别格式，也可指定格式名称
+    record_start: Option<multiline::RecordStart>, // 可选参数，多行记录的起始行规则，指定后按记录返回
 }
 
 #[tauri::command]
@@ -52,77 +231,119 @@ fn greet(name: &str) -> String {
     format!("Hello, {}! You've been greeted from Rust!", name)
 }
 
+// 大文件的索引和读取可能比较耗时，放到阻塞线程池中执行，避免卡住界面
 #[tauri::command]
-fn read_file(options: FileReadOptions) -> Result<FileContent, String> {
+async fn read_file(options: FileReadOptions) -> Result<FileContent, EasyLogError> {
+    tokio::task::spawn_blocking(move || read_file_window(options))
+        .await
+        .map_err(|e| EasyLogError::internal(format!("Failed to read file: {}", e)))?
+}
+
+fn read_file_window(options: FileReadOptions) -> Result<FileContent, EasyLogError> {
     info!("Reading file with options: {:?}", options);
 
     // 添加文件存在性检查
-    if !std::path::Path::new(&options.path).exists() {
-        let err = format!("File not found: {}", options.path);
+    let path = Path::new(&options.path);
+    if !path.exists() {
+        let err = EasyLogError::not_found(path);
         error!("{}", err);
         return Err(err);
     }
 
-    let file = match File::open(&options.path) {
+    // 压缩文件（.gz/.zst/.bz2/.xz）先解压到缓存目录，之后按普通文件建立索引和分页读取
+    let path = match compression::materialize(path) {
+        Ok(p) => p,
+        Err(e) => {
+            let err = EasyLogError::io(path, e);
+            error!("Failed to decompress file: {}", err);
+            return Err(err);
+        }
+    };
+
+    let mut file = match File::open(&path) {
         Ok(f) => f,
         Err(e) => {
-            let err = format!("Failed to open file: {}", e);
-            error!("{}", err);
+            let err = EasyLogError::io(&path, e);
+            error!("Failed to open file: {}", err);
             return Err(err);
         }
     };
+    let metadata = file.metadata().map_err(|e| EasyLogError::io(&path, e))?;
 
-    let reader = BufReader::new(file);
-    let mut content = String::new();
+    let mut range = options.range.clone();
+    range.line_count = range.line_count.or(options.max_lines);
+    let result = read_file_range(
+        &mut file,
+        &path,
+        metadata.len(),
+        metadata.modified().ok(),
+        options.encoding.as_deref(),
+        &range,
+        true,
+    )
+    .map_err(|e| {
+        error!("{}", e);
+        e
+    })?;
 
-    // 如果指定了最大行数，就只读取指定行数
-    if let Some(max_lines) = options.max_lines {
-        for (i, line) in reader.lines().enumerate() {
-            if i >= max_lines {
-                break;
-            }
-            match line {
-                Ok(line) => {
-                    content.push_str(&line);
-                    content.push('\n');
-                }
-                Err(e) => {
-                    let err = format!("Error reading line: {}", e);
-                    error!("{}", err);
-                    return Err(err);
-                }
-            }
-        }
-    } else {
-        // 否则读取整个文件
-        for line in reader.lines() {
-            match line {
-                Ok(line) => {
-                    content.push_str(&line);
-                    content.push('\n');
-                }
-                Err(e) => {
-                    let err = format!("Error reading line: {}", e);
-                    error!("{}", err);
-                    return Err(err);
-                }
-            }
-        }
-    }
+    info!(
+        "Successfully read file: lines {}..{} of {}",
+        result.start_line,
+        result.start_line + result.content.lines().count(),
+        result.total_lines
+    );
+    result.into_records(options.parse.as_deref(), options.record_start.as_ref())
+}
 
-    let _file_name = std::path::Path::new(&options.path)
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+// 按读取范围读取已打开的文件，本地文件和通过SFTP打开的远程文件共用。
+// key用于缓存行索引；build_index为false时只使用已有的索引，不需要行号的读取方式不会扫描整个文件
+fn read_file_range<R: Read + Seek>(
+    reader: &mut R,
+    key: &Path,
+    len: u64,
+    modified: Option<std::time::SystemTime>,
+    encoding: Option<&str>,
+    range: &file_index::ReadRange,
+    build_index: bool,
+) -> Result<FileContent, EasyLogError> {
+    // 读取文件开头的内容识别编码
+    let mut sample = Vec::new();
+    reader
+        .seek(SeekFrom::Start(0))
+        .and_then(|_| (&mut *reader).take(encoding::DETECT_SAMPLE_SIZE as u64).read_to_end(&mut sample))
+        .map_err(|e| EasyLogError::io(key, e))?;
+    let detected = encoding::resolve_encoding(encoding, &sample)?;
+    let terminator = file_index::LineTerminator::for_encoding(detected.encoding);
 
-    let result = FileContent {
-        content: content.clone(),
-        total_lines: content.lines().count(),
-    };
+    // 每个文件只建立一次行索引，之后任意区间的读取都通过索引定位
+    let index = file_index::cached_index(key, reader, len, modified, terminator, build_index || range.needs_index())
+        .map_err(|e| EasyLogError::io(key, e))?;
+    let read = file_index::read_range(reader, len, terminator, index.as_ref(), range)
+        .map_err(|e| EasyLogError::io(key, e))?;
+    let window = read.window;
+
+    // 按识别出的编码解码，非法字节替换为U+FFFD，不会因为编码问题导致读取失败
+    let mut content = String::new();
+    for (i, line) in window.lines.iter().enumerate() {
+        let bytes = if i == 0 && window.start_byte == 0 {
+            &line[detected.bom_len.min(line.len())..]
+        } else {
+            &line[..]
+        };
+        content.push_str(&encoding::decode_lossy(detected.encoding, bytes));
+        content.push('\n');
+    }
 
-    info!("Successfully read file");
-    Ok(result)
+    Ok(FileContent {
+        content,
+        total_lines: read.total_lines.unwrap_or(window.lines.len()),
+        start_line: read.start_line.unwrap_or(0),
+        start_byte: window.start_byte,
+        end_byte: window.end_byte,
+        encoding: detected.name().to_string(),
+        line_numbers_known: read.start_line.is_some(),
+        records: None,
+    })
 }
 
 #[tauri::command]
@@ -140,20 +361,30 @@ fn log_to_frontend(level: String, message: String) {
 // 新添加的SSH连接和日志流相关结构体和类型
 #[derive(Debug, Deserialize, Clone)]
 pub struct SshCredentials {
-    host: String,
-    port: Option<u16>,
-    username: String,
+    host: String, // 主机名，也可以是 ~/.ssh/config 中的Host别名
+    port: Option<u16>, // 为空时使用 ~/.ssh/config 中的Port，默认22
+    #[serde(default)]
+    username: String, // 为空时使用 ~/.ssh/config 中的User
     #[serde(flatten)]
     auth_method: AuthMethod,
+    // 依次经过的跳板机，为空时使用 ~/.ssh/config 中的ProxyJump
+    #[serde(default)]
+    jump_hosts: Vec<SshCredentials>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, Hash)]
 #[serde(tag = "auth_type")]
 pub enum AuthMethod {
     #[serde(rename = "password")]
     Password { password: String },
     #[serde(rename = "key")]
-    PublicKey { private_key_path: String, passphrase: Option<String> },
+    PublicKey { private_key_path: String, passphrase: Option<String> }, // 私钥路径为空时使用 ~/.ssh/config 中的IdentityFile
+    // 使用ssh-agent中的身份，agent中没有可用身份时再尝试 ~/.ssh/config 中的IdentityFile
+    #[serde(rename = "agent")]
+    Agent,
+    // 键盘交互认证，密码提示使用password回答，其它提示（例如动态验证码）由用户在前端输入
+    #[serde(rename = "keyboard_interactive")]
+    KeyboardInteractive { password: Option<String> },
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -165,7 +396,14 @@ pub struct SshConnectionStatus {
 #[derive(Debug, Deserialize)]
 pub struct LogStreamOptions {
     log_file_path: String,
-    follow: bool,
+    #[serde(default)]
+    follow: bool, // 只读取最后一部分内容，实时跟踪由 monitor_remote_log 负责
+    // 可选参数，读取范围，与本地文件相同；都不指定时读取整个文件
+    #[serde(flatten)]
+    range: file_index::ReadRange,
+    encoding: Option<String>, // 可选参数，指定日志编码，为空或"auto"时自动识别
+    parse: Option<String>, // 可选参数，返回解析后的结构化记录，"auto"为自动识别格式
+    record_start: Option<multiline::RecordStart>, // 可选参数，多行记录的起始行规则
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -175,51 +413,61 @@ pub struct LogStreamData {
     pub source: Option<String>,
     pub error: Option<String>,
     pub timestamp: u64,
+    // 请求解析时附带的结构化记录
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<parser::LogRecord>,
+    // 远程日志流的id，用于区分同一服务器上的多个流
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_id: Option<String>,
+    // 多主机合并监控时，这一行来自的服务器
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
 }
 
+impl LogStreamData {
+    // 实时日志中的一行（或合并后的一条多行记录）
+    pub fn new_line(content: String, source: &str, record: Option<parser::LogRecord>) -> Self {
+        LogStreamData {
+            content,
+            is_complete: false,
+            source: Some(source.to_string()),
+            error: None,
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            record,
+            stream_id: None,
+            host: None,
+        }
+    }
+
+    // 远程日志流的数据，附带流id
+    fn for_stream(mut self, stream: &RemoteStreamInfo) -> Self {
+        self.stream_id = Some(stream.stream_id.clone());
+        self
+    }
+}
+
+// 多行记录在没有新内容多久之后视为结束并发送
+const RECORD_IDLE_FLUSH: std::time::Duration = std::time::Duration::from_millis(500);
+// 远程日志没有新数据时，最多等待这么久检查一次连接状态和正在合并的多行记录
+const TAIL_IDLE_WAKE: std::time::Duration = std::time::Duration::from_millis(250);
+
 // SSH连接测试
 #[tauri::command]
-async fn test_ssh_connection(credentials: SshCredentials) -> Result<SshConnectionStatus, String> {
+async fn test_ssh_connection(credentials: profiles::SshTarget) -> Result<SshConnectionStatus, EasyLogError> {
+    let credentials = credentials.resolve()?;
     info!("Testing SSH connection to: {}", credentials.host);
     
-    let port = credentials.port.unwrap_or(22);
-    
-    // 创建一个临时的TCP连接来测试SSH连接
-    match std::net::TcpStream::connect(format!("{}:{}", credentials.host, port)) {
-        Ok(tcp) => {
-            // 创建SSH会话
-            let mut sess = match ssh2::Session::new() {
-                Ok(s) => s,
-                Err(e) => return Err(format!("创建SSH会话失败: {}", e)),
-            };
-            
-            sess.set_tcp_stream(tcp);
-            if let Err(e) = sess.handshake() {
-                return Err(format!("SSH握手失败: {}", e));
-            }
-            
-            // 根据认证方式进行身份验证
-            let auth_result = match &credentials.auth_method {
-                AuthMethod::Password { password } => {
-                    sess.userauth_password(&credentials.username, password)
-                },
-                AuthMethod::PublicKey { private_key_path, passphrase } => {
-                    // 因为需要考虑跨平台，这里处理路径
-                    let path = std::path::Path::new(private_key_path);
-                    let passphrase_str = passphrase.as_deref().unwrap_or("");
-                    sess.userauth_pubkey_file(
-                        &credentials.username,
-                        None,
-                        path,
-                        Some(passphrase_str)
-                    )
-                }
-            };
-            
-            if let Err(e) = auth_result {
-                return Err(format!("SSH认证失败: {}", e));
-            }
-            
+    // 用不放入会话池的新连接确认认证信息有效，不影响池中会话上正在运行的日志流；
+    // 连接是阻塞操作，键盘交互认证还会等待前端回答，放到阻塞线程池中执行
+    let target = credentials.clone();
+    let result = tokio::task::spawn_blocking(move || ssh::test_connection(&target))
+        .await
+        .map_err(|e| EasyLogError::internal(format!("连接测试失败: {}", e)))?;
+    match result {
+        Ok(_) => {
             info!("SSH连接测试成功: {}", credentials.host);
             Ok(SshConnectionStatus {
                 connected: true,
@@ -228,410 +476,638 @@ async fn test_ssh_connection(credentials: SshCredentials) -> Result<SshConnectio
         },
         Err(e) => {
             error!("无法连接到SSH服务器: {}", e);
-            Err(format!("连接失败: {}", e))
+            Err(e)
         }
     }
 }
 
+// follow为true且没有指定读取范围时返回的行数
+const REMOTE_FOLLOW_LINES: usize = 1000;
+
 // 读取远程日志文件
 #[tauri::command]
-async fn read_remote_log(credentials: SshCredentials, options: LogStreamOptions) -> Result<FileContent, String> {
+async fn read_remote_log(credentials: profiles::SshTarget, options: LogStreamOptions) -> Result<FileContent, EasyLogError> {
+    let credentials = credentials.resolve()?;
     info!("Reading remote log file: {} from {}", options.log_file_path, credentials.host);
     
-    let port = credentials.port.unwrap_or(22);
-    
-    // 创建TCP连接
-    let tcp = match std::net::TcpStream::connect(format!("{}:{}", credentials.host, port)) {
-        Ok(s) => s,
-        Err(e) => return Err(format!("无法连接到服务器: {}", e)),
-    };
-    
-    // 创建SSH会话
-    let mut sess = match ssh2::Session::new() {
-        Ok(s) => s,
-        Err(e) => return Err(format!("创建SSH会话失败: {}", e)),
-    };
+    // SFTP读取是阻塞操作，放到阻塞线程池中执行
+    tokio::task::spawn_blocking(move || read_remote_window(&credentials, options))
+        .await
+        .map_err(|e| EasyLogError::internal(format!("读取日志内容失败: {}", e)))?
+}
+
+// 通过SFTP定位读取远程文件，只传输读取范围内的内容；压缩文件先下载解压到本地缓存，之后按本地文件读取
+fn read_remote_window(credentials: &SshCredentials, options: LogStreamOptions) -> Result<FileContent, EasyLogError> {
+    let sftp = ssh::sftp(credentials)?;
+    let path = Path::new(&options.log_file_path);
+    let stat = sftp.stat(path)?;
+    let len = stat.size.unwrap_or(0);
+    let modified = stat
+        .mtime
+        .map(|secs| std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+    // 缓存行索引和解压结果时使用的远程文件地址
+    let key = PathBuf::from(format!(
+        "sftp://{}@{}:{}{}",
+        credentials.username,
+        credentials.host,
+        credentials.port.unwrap_or(22),
+        options.log_file_path
+    ));
     
-    sess.set_tcp_stream(tcp);
-    if let Err(e) = sess.handshake() {
-        return Err(format!("SSH握手失败: {}", e));
+    let mut range = options.range.clone();
+    if options.follow && range.needs_index() && range.start_line.is_none() && range.line_count.is_none() {
+        range.tail_lines = Some(REMOTE_FOLLOW_LINES);
     }
     
-    // 根据认证方式进行身份验证
-    let auth_result = match &credentials.auth_method {
-        AuthMethod::Password { password } => {
-            sess.userauth_password(&credentials.username, password)
-        },
-        AuthMethod::PublicKey { private_key_path, passphrase } => {
-            let path = std::path::Path::new(private_key_path);
-            let passphrase_str = passphrase.as_deref().unwrap_or("");
-            sess.userauth_pubkey_file(
-                &credentials.username,
-                None,
-                path,
-                Some(passphrase_str)
-            )
+    let mut file = sftp.open(path)?;
+    let decompressed = compression::materialize_stream(&key, &mut file, len, modified)
+        .map_err(|e| EasyLogError::io(&key, e))?;
+    let result = match decompressed {
+        Some(local_path) => {
+            info!("Remote log {} is compressed, decompressed to {:?}", options.log_file_path, local_path);
+            let mut local = File::open(&local_path).map_err(|e| EasyLogError::io(&local_path, e))?;
+            let metadata = local.metadata().map_err(|e| EasyLogError::io(&local_path, e))?;
+            read_file_range(
+                &mut local,
+                &local_path,
+                metadata.len(),
+                metadata.modified().ok(),
+                options.encoding.as_deref(),
+                &range,
+                true,
+            )?
         }
+        None => read_file_range(&mut file, &key, len, modified, options.encoding.as_deref(), &range, false)?,
     };
     
-    if let Err(e) = auth_result {
-        return Err(format!("SSH认证失败: {}", e));
+    info!(
+        "Read remote log {}: bytes {}..{} of {}",
+        options.log_file_path, result.start_byte, result.end_byte, len
+    );
+    result.into_records(options.parse.as_deref(), options.record_start.as_ref())
+}
+
+// ssh-log-monitor-stopped 事件的数据，在远程进程结束、通道关闭之后发送
+#[derive(Debug, Serialize, Clone)]
+struct MonitorStoppedEvent {
+    #[serde(flatten)]
+    stream: RemoteStreamInfo,
+    remote_pid: Option<u32>,
+    process_killed: bool,
+    timed_out: bool, // 超过TEARDOWN_TIMEOUT仍没有完成清理
+}
+
+// 远程日志流的读取状态，重新连接后继续使用，不完整的行和多行记录可以接上
+struct RemoteTail {
+    sink: Box<dyn FnMut(LogStreamData) + Send>, // 处理读取到的每一行（或合并后的多行记录）
+    decoder: encoding::StreamDecoder,
+    record_parser: Option<parser::RecordParser>,
+    grouper: Option<multiline::RecordGrouper>,
+    accumulated_text: String,
+    line_count: usize,
+    lines_since_log: usize,
+    last_log_time: std::time::Instant,
+    offset: Option<u64>, // 已经收到的数据在文件中的结束位置，第一次连接成功之前为空
+    inode: Option<u64>, // offset所在文件的inode，重新连接时用来判断文件是否已被轮转
+    remote_pid: Option<u32>, // 当前连接上远程tail进程的进程号
+    pid_header: Option<Vec<u8>>, // 每次连接输出的第一行是进程号，读完之前保存已收到的部分
+}
+
+// 一次连接的读取结束的原因
+enum TailEnd {
+    Stopped,
+    Disconnected(String),
+}
+
+impl RemoteTail {
+    // 单个日志流的数据通过 ssh-log-batch 事件批量发送到前端
+    fn new(
+        window: tauri::Window,
+        stream: &RemoteStreamInfo,
+        batch: &stream_batch::BatchOptions,
+        encoding: Option<&str>,
+        parse: Option<&str>,
+        record_start: Option<&multiline::RecordStart>,
+    ) -> Result<Self, EasyLogError> {
+        let sender = stream_batch::BatchSender::spawn(window, "ssh-log-batch", Some(stream.stream_id.clone()), batch);
+        Self::with_sink(
+            Box::new(move |log_data| sender.push(log_data)),
+            encoding,
+            parse,
+            record_start,
+        )
     }
     
-    // 打开一个通道并执行命令读取日志文件
-    let mut channel = match sess.channel_session() {
-        Ok(c) => c,
-        Err(e) => return Err(format!("无法创建SSH通道: {}", e)),
-    };
-    
-    // 根据选项使用合适的命令
-    let command = if options.follow {
-        format!("tail -n 1000 -f \"{}\"", options.log_file_path)
-    } else {
-        format!("cat \"{}\"", options.log_file_path)
-    };
-    
-    if let Err(e) = channel.exec(&command) {
-        return Err(format!("执行命令失败: {}", e));
+    // 提前校验编码和解析格式参数
+    fn with_sink(
+        sink: Box<dyn FnMut(LogStreamData) + Send>,
+        encoding: Option<&str>,
+        parse: Option<&str>,
+        record_start: Option<&multiline::RecordStart>,
+    ) -> Result<Self, EasyLogError> {
+        Ok(RemoteTail {
+            sink,
+            decoder: encoding::StreamDecoder::new(encoding)?,
+            record_parser: parse.map(parser::RecordParser::new).transpose()?,
+            grouper: record_start.map(multiline::RecordGrouper::new).transpose()?,
+            accumulated_text: String::new(),
+            line_count: 0,
+            lines_since_log: 0,
+            last_log_time: std::time::Instant::now(),
+            offset: None,
+            inode: None,
+            remote_pid: None,
+            pid_header: None,
+        })
     }
     
-    // 读取输出 - 直接使用channel.read方法，不使用BufReader
-    let mut buffer = vec![0; 8192]; // 使用更大的缓冲区
-    let mut content = String::new();
+    // 处理收到的数据：解码、按行拆分后交给sink
+    fn feed(&mut self, stream: &RemoteStreamInfo, data: &[u8]) {
+        if let Some(offset) = self.offset.as_mut() {
+            *offset += data.len() as u64;
+        }
+        
+        // 将读取到的数据解码后添加到累积缓冲区，不完整的多字节字符会留到下次解码
+        self.accumulated_text.push_str(&self.decoder.feed(data, false));
+        
+        // 按行处理数据，最后不完整的行保留在缓冲区中
+        while let Some(idx) = self.accumulated_text.find('\n') {
+            let raw_line: String = self.accumulated_text.drain(..=idx).collect();
+            let line = raw_line.trim_end_matches('\n').trim_end_matches('\r').to_string();
+            self.line_count += 1;
+            self.lines_since_log += 1;
+            
+            // 每读取10000行或者每5秒输出一次调试日志
+            let now = std::time::Instant::now();
+            if self.lines_since_log >= 10_000 || now.duration_since(self.last_log_time).as_secs() >= 5 {
+                debug!("Read {} lines from remote log {}. Latest content: {}", 
+                     self.line_count, stream.log_path, line.chars().take(50).collect::<String>());
+                self.lines_since_log = 0;
+                self.last_log_time = now;
+            }
+            
+            // 指定了记录起始规则时，续行先合并，遇到下一条记录的起始行再整条发送
+            let log_data = match self.grouper.as_mut() {
+                Some(grouper) => match grouper.push(self.line_count, line) {
+                    Some(group) => group_stream_data(stream, group, &mut self.record_parser),
+                    None => continue,
+                },
+                None => {
+                    let record = self.record_parser.as_mut().map(|p| p.parse(self.line_count, &line));
+                    LogStreamData::new_line(line, &stream.log_path, record).for_stream(stream)
+                }
+            };
+            
+            (self.sink)(log_data);
+        }
+    }
     
-    // 读取所有数据
-    loop {
-        match channel.read(&mut buffer) {
-            Ok(n) => {
-                if n == 0 {
-                    break; // 读取结束
+    // 读取一次连接上的数据，直到被请求停止或者连接断开；没有数据时等待新数据到达，停止请求立即生效
+    fn read_channel(
+        &mut self,
+        stream: &RemoteStreamInfo,
+        channel: &mut ssh::PooledChannel,
+        cancel: &CancellationToken,
+    ) -> TailEnd {
+        let mut buffer = vec![0; 16 * 1024];
+        
+        loop {
+            match channel.read_wait(&mut buffer, TAIL_IDLE_WAKE, cancel) {
+                Ok(ssh::WaitRead::Cancelled) => {
+                    info!("日志流已被请求停止: {} for {}", stream.stream_id, stream.log_path);
+                    return TailEnd::Stopped;
                 }
-                
-                // 将读取的数据转换为字符串并添加到结果中
-                match std::str::from_utf8(&buffer[0..n]) {
-                    Ok(s) => content.push_str(s),
-                    Err(e) => {
-                        return Err(format!("解析日志内容失败: {}", e));
+                Ok(ssh::WaitRead::Data(0)) if channel.eof() => {
+                    // tail -f 不会自己结束，通道结束说明连接已经断开
+                    info!("Channel EOF detected after reading {} lines", self.line_count);
+                    return TailEnd::Disconnected("远程连接已关闭".to_string());
+                }
+                Ok(ssh::WaitRead::Data(bytes_read)) if bytes_read > 0 => {
+                    let mut data = &buffer[..bytes_read];
+                    // 进程号不是文件内容，不计入偏移
+                    if let Some(header) = self.pid_header.as_mut() {
+                        let Some(end) = data.iter().position(|&b| b == b'\n') else {
+                            header.extend_from_slice(data);
+                            continue;
+                        };
+                        header.extend_from_slice(&data[..end]);
+                        self.remote_pid = remote_command::parse_pid(header);
+                        self.pid_header = None;
+                        data = &data[end + 1..];
+                    }
+                    // tail -F 切换到轮转后的新文件时先在标准错误输出中提示，之后的内容从新文件的开头计算偏移；
+                    // 新文件的inode在重新连接时再获取
+                    if remote_command::tail_restarted(&channel.read_stderr_now()) {
+                        info!("远程日志 {} 已被轮转或截断，从新文件的开头继续读取", stream.log_path);
+                        self.offset = Some(0);
+                        self.inode = None;
+                    }
+                    if !data.is_empty() {
+                        self.feed(stream, data);
                     }
                 }
-            },
-            Err(e) => {
-                return Err(format!("读取日志内容失败: {}", e));
+                Ok(_) => {
+                    // keepalive检测到连接失效时会把会话移出会话池
+                    if !channel.session_alive() {
+                        return TailEnd::Disconnected("SSH连接已断开".to_string());
+                    }
+                    self.flush_idle(stream);
+                }
+                Err(e) => return TailEnd::Disconnected(format!("读取远程日志失败: {}", e)),
             }
         }
     }
     
-    // 等待通道关闭
-    channel.wait_close().ok();
+    // 一段时间没有新内容时，把正在合并的记录发送出去，避免最后一条记录迟迟不显示
+    fn flush_idle(&mut self, stream: &RemoteStreamInfo) {
+        if let Some(group) = self.grouper.as_mut().and_then(|g| g.flush_idle(RECORD_IDLE_FLUSH)) {
+            (self.sink)(group_stream_data(stream, group, &mut self.record_parser));
+        }
+    }
     
-    // 返回结果
-    Ok(FileContent {
-        content: content.clone(),
-        total_lines: content.lines().count(),
+    // 流结束时发送最后一条还没有结束的多行记录
+    fn finish(&mut self, stream: &RemoteStreamInfo) {
+        if let Some(group) = self.grouper.as_mut().and_then(|g| g.flush()) {
+            (self.sink)(group_stream_data(stream, group, &mut self.record_parser));
+        }
+    }
+}
+
+// 初次连接显示文件最后多少行
+const INITIAL_TAIL_LINES: usize = 100;
+// 计算最后几行的起始位置时最多读取的字节数
+const INITIAL_TAIL_WINDOW: u64 = 1024 * 1024;
+// 重新连接的等待时间从1秒开始每次翻倍，最长1分钟
+const RECONNECT_INITIAL_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: std::time::Duration = std::time::Duration::from_secs(60);
+
+// 获取远程文件的inode和大小；服务器的stat不支持 -c 格式时只用 wc -c 获取大小
+fn remote_file_identity(credentials: &SshCredentials, log_path: &str) -> Result<remote_command::FileIdentity, EasyLogError> {
+    match ssh::exec_output(credentials, &remote_command::file_identity(log_path)) {
+        Ok(output) => {
+            if let Some(identity) = remote_command::parse_identity(&output) {
+                return Ok(identity);
+            }
+        }
+        Err(e @ (EasyLogError::NotFound { .. } | EasyLogError::PermissionDenied { .. })) => return Err(e),
+        Err(e) => debug!("stat不可用，改用wc获取文件大小: {}", e),
+    }
+    let size = remote_command::parse_size(&ssh::exec_output(credentials, &remote_command::file_size(log_path))?)?;
+    Ok(remote_command::FileIdentity { inode: None, size })
+}
+
+// 第一次连接时计算最后100行的起始偏移。
+// 只在文件当前末尾之前的一段范围内统计最后几行的长度，之后写入的内容不影响起始位置
+fn initial_tail_offset(credentials: &SshCredentials, log_path: &str, size: u64) -> Result<u64, EasyLogError> {
+    if size == 0 {
+        return Ok(0);
+    }
+    let window = size.min(INITIAL_TAIL_WINDOW);
+    let command = remote_command::tail_from(log_path, size - window, false)
+        .pipe(Program::Head)
+        .arg("-c")
+        .arg(window.to_string())
+        .pipe(Program::Tail)
+        .arg("-n")
+        .arg(INITIAL_TAIL_LINES.to_string())
+        .pipe(Program::Wc)
+        .arg("-c");
+    let tail_len = remote_command::parse_size(&ssh::exec_output(credentials, &command)?)?;
+    Ok(size - tail_len.min(window))
+}
+
+// 从指定偏移开始持续读取远程日志，返回通道、起始偏移和文件的inode。
+// 第一次连接从最后100行开始，重新连接时从上次收到的位置继续，不会丢失或重复内容；
+// 断开期间文件被轮转（inode改变）或截断（比上次收到的位置短）时从新文件的开头读取
+fn open_remote_tail(
+    credentials: &SshCredentials,
+    log_path: &str,
+    offset: Option<u64>,
+    inode: Option<u64>,
+) -> Result<(ssh::PooledChannel, u64, Option<u64>), EasyLogError> {
+    let identity = remote_file_identity(credentials, log_path)?;
+    let replaced = matches!((inode, identity.inode), (Some(last), Some(current)) if last != current);
+    let start = match offset {
+        None => initial_tail_offset(credentials, log_path, identity.size)?,
+        Some(offset) if replaced || identity.size < offset => {
+            warn!(
+                "远程日志 {} 在断开期间已被轮转或截断（大小 {}，上次读取到 {}），从头开始读取",
+                log_path, identity.size, offset
+            );
+            0
+        }
+        Some(offset) => offset,
+    };
+    let channel = ssh::exec(credentials, &remote_command::tail_from(log_path, start, true).with_pid())?;
+    Ok((channel, start, identity.inode))
+}
+
+// 停止时先结束远程的tail进程再关闭通道：tail -f 在没有新内容时不会因为通道关闭而退出，会一直留在服务器上
+fn close_remote_tail(credentials: &SshCredentials, mut channel: ssh::PooledChannel, remote_pid: Option<u32>) -> Teardown {
+    let process_killed = match remote_pid {
+        Some(pid) if channel.session_alive() => match ssh::exec_output(credentials, &remote_command::kill(pid)) {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("结束远程进程 {} 失败: {}", pid, e);
+                false
+            }
+        },
+        _ => false,
+    };
+    channel.close();
+    Teardown {
+        remote_pid,
+        process_killed,
+    }
+}
+
+// ssh-log-reconnecting 事件的数据
+#[derive(Debug, Serialize, Clone)]
+struct ReconnectingEvent {
+    #[serde(flatten)]
+    stream: RemoteStreamInfo,
+    attempt: u32,   // 第几次重试
+    delay_ms: u64,  // 多久之后重试
+    offset: u64,    // 重新连接后从哪个字节偏移继续读取
+    message: String, // 断开的原因
+}
+
+// 通知前端即将重新连接并按退避时间等待，等待期间被请求停止时返回false
+fn wait_reconnect(
+    window: &tauri::Window,
+    stream: &RemoteStreamInfo,
+    cancel: &CancellationToken,
+    attempt: u32,
+    offset: u64,
+    message: String,
+) -> bool {
+    let delay = RECONNECT_INITIAL_DELAY
+        .saturating_mul(1 << attempt.min(16))
+        .min(RECONNECT_MAX_DELAY);
+    warn!("日志流 {} 断开: {}，{}秒后第{}次重新连接", stream.stream_id, message, delay.as_secs(), attempt + 1);
+    let payload = ReconnectingEvent {
+        stream: stream.clone(),
+        attempt: attempt + 1,
+        delay_ms: delay.as_millis() as u64,
+        offset,
+        message,
+    };
+    if let Err(e) = window.emit("ssh-log-reconnecting", payload) {
+        error!("Failed to emit ssh-log-reconnecting: {}", e);
+    }
+    
+    // 等待期间被停止时立即返回
+    ssh::block_on(async {
+        tokio::select! {
+            _ = cancel.cancelled() => false,
+            _ = tokio::time::sleep(delay) => true,
+        }
     })
 }
 
-// 实时监控远程日志文件（使用事件系统）
+// 实时监控远程日志文件（使用事件系统），返回流id；同一服务器上可以同时监控多个文件。
+// 连接断开后按指数退避自动重新连接，并从上次收到的字节偏移继续读取
 #[tauri::command]
-async fn monitor_remote_log(window: tauri::Window, credentials: SshCredentials, log_path: String) -> Result<(), String> {
+#[allow(clippy::too_many_arguments)] // 命令参数由前端按名称传入
+async fn monitor_remote_log(
+    window: tauri::Window,
+    credentials: profiles::SshTarget,
+    log_path: String,
+    encoding: Option<String>,
+    parse: Option<String>,
+    record_start: Option<multiline::RecordStart>,
+    stream_id: Option<String>, // 可选参数，指定流id，已存在相同id的流时替换它
+    batch: Option<stream_batch::BatchOptions>, // 可选参数，批量发送和缓冲区设置
+) -> Result<String, EasyLogError> {
+    let credentials = credentials.resolve()?;
     info!("Starting remote log monitoring for: {} on {}", log_path, credentials.host);
     
     let port = credentials.port.unwrap_or(22);
-    let conn_key = (credentials.host.clone(), port);
+    let stream = RemoteStreamInfo {
+        stream_id: stream_id
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| format!("stream-{}", NEXT_STREAM_ID.fetch_add(1, Ordering::Relaxed))),
+        host: credentials.host.clone(),
+        port,
+        username: credentials.username.clone(),
+        log_path: log_path.clone(),
+        started_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        fanout_id: None,
+    };
+    let tail = RemoteTail::new(
+        window.clone(),
+        &stream,
+        &batch.unwrap_or_default(),
+        encoding.as_deref(),
+        parse.as_deref(),
+        record_start.as_ref(),
+    )?;
+    let cancel = CancellationToken::new();
+    let (finished, finished_rx) = tokio::sync::oneshot::channel();
     
-    // 检查是否已有相同服务器的连接
+    // 添加到活跃连接列表，相同id的旧流停止
     {
         let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
-        if let Some(existing_log_path) = connections.connections.get(&conn_key) {
-            let err_msg = format!("已存在到服务器 {}:{} 的连接，正在监控日志: {}", conn_key.0, conn_key.1, existing_log_path);
-            error!("{}", err_msg);
-            // 移除旧连接
-            connections.connections.remove(&conn_key);
+        if let Some(existing) = connections.stop(&stream.stream_id) {
+            info!("替换日志流 {}: {} -> {}", existing.info.stream_id, existing.info.log_path, log_path);
         }
-
-        // 添加到活跃连接列表
-        connections.connections.insert(conn_key.clone(), log_path.clone());
+        connections.streams.insert(
+            stream.stream_id.clone(),
+            RemoteStream {
+                info: stream.clone(),
+                cancel: cancel.clone(),
+                finished: finished_rx,
+            },
+        );
     }
     
-    // 克隆window以便在tokio线程中使用
-    let window_clone = window.clone();
-    let host = credentials.host.clone();
+    let stream_id = stream.stream_id.clone();
     
-    // 使用tokio线程来处理实时日志监控
-    tokio::spawn(async move {
-        // 创建TCP连接
-        let tcp = match std::net::TcpStream::connect(format!("{}:{}", credentials.host, port)) {
-            Ok(s) => s,
-            Err(e) => {
-                let err_msg = format!("无法连接到服务器: {}", e);
-                error!("{}", err_msg);
-                let _ = window_clone.emit("ssh-log-error", err_msg);
-                
-                // 从活跃连接中移除
-                let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
-                connections.connections.remove(&conn_key);
-                return;
-            }
-        };
-        
-        // 创建SSH会话
-        let mut sess = match ssh2::Session::new() {
-            Ok(s) => s,
-            Err(e) => {
-                let err_msg = format!("创建SSH会话失败: {}", e);
-                error!("{}", err_msg);
-                let _ = window_clone.emit("ssh-log-error", err_msg);
-                
-                // 从活跃连接中移除
-                let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
-                connections.connections.remove(&conn_key);
-                return;
-            }
-        };
-        
-        sess.set_tcp_stream(tcp);
-        if let Err(e) = sess.handshake() {
-            let err_msg = format!("SSH握手失败: {}", e);
-            error!("{}", err_msg);
-            let _ = window_clone.emit("ssh-log-error", err_msg);
-            
-            // 从活跃连接中移除
-            let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
-            connections.connections.remove(&conn_key);
-            return;
-        }
-        
-        // 根据认证方式进行身份验证
-        let auth_result = match &credentials.auth_method {
-            AuthMethod::Password { password } => {
-                sess.userauth_password(&credentials.username, password)
-            },
-            AuthMethod::PublicKey { private_key_path, passphrase } => {
-                let path = std::path::Path::new(private_key_path);
-                let passphrase_str = passphrase.as_deref().unwrap_or("");
-                sess.userauth_pubkey_file(
-                    &credentials.username,
-                    None,
-                    path,
-                    Some(passphrase_str)
-                )
-            }
-        };
-        
-        if let Err(e) = auth_result {
-            let err_msg = format!("SSH认证失败: {}", e);
-            error!("{}", err_msg);
-            let _ = window_clone.emit("ssh-log-error", err_msg);
-            
-            // 从活跃连接中移除
-            let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
-            connections.connections.remove(&conn_key);
-            return;
-        }
-        
-        // 打开一个通道并执行命令读取日志文件
-        let mut channel = match sess.channel_session() {
-            Ok(c) => c,
-            Err(e) => {
-                let err_msg = format!("无法创建SSH通道: {}", e);
-                error!("{}", err_msg);
-                let _ = window_clone.emit("ssh-log-error", err_msg);
-                
-                // 从活跃连接中移除
-                let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
-                connections.connections.remove(&conn_key);
-                return;
+    // 每个日志流在独立线程中读取，等待数据时不占用异步运行时的工作线程
+    std::thread::spawn(move || run_remote_tail(&window, &credentials, tail, &stream, &cancel, finished));
+    
+    Ok(stream_id)
+}
+
+// 持续读取一个远程日志流直到被请求停止：连接断开后按退避时间重新连接，并从上次收到的位置继续。
+// 第一次连接失败时发送 ssh-log-error 并结束。结束后通过 finished 发送清理结果
+fn run_remote_tail(
+    window: &tauri::Window,
+    credentials: &SshCredentials,
+    mut tail: RemoteTail,
+    stream: &RemoteStreamInfo,
+    cancel: &CancellationToken,
+    finished: tokio::sync::oneshot::Sender<Teardown>,
+) {
+    let mut attempt = 0;
+    let mut teardown = Teardown::default();
+    
+    loop {
+        // 在会话池中的会话上打开通道，同一服务器的其它命令共用这个会话
+        let (mut channel, start, inode) = match open_remote_tail(credentials, &stream.log_path, tail.offset, tail.inode) {
+            Ok(opened) => opened,
+            Err(err) => {
+                // 第一次连接失败时直接报错，已经开始监控后的失败按退避时间重试
+                let Some(offset) = tail.offset else {
+                    error!("{}", err);
+                    emit_stream_error(window, stream, err);
+                    break;
+                };
+                if !wait_reconnect(window, stream, cancel, attempt, offset, err.to_string()) {
+                    break;
+                }
+                attempt += 1;
+                continue;
             }
         };
         
-        // 使用tail -f命令来实时读取文件内容，显示最后100行
-        let command = format!("tail -n 100 -f \"{}\"", log_path);
-        
-        if let Err(e) = channel.exec(&command) {
-            let err_msg = format!("执行命令失败: {}", e);
-            error!("{}", err_msg);
-            let _ = window_clone.emit("ssh-log-error", err_msg);
-            
-            // 从活跃连接中移除
-            let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
-            connections.connections.remove(&conn_key);
-            return;
-        }
-        
         // 通知前端连接成功
-        let _ = window_clone.emit("ssh-log-connected", log_path.clone());
-        
-        // 读取输出并发送到前端
-        info!("Starting to read remote log data from: {}", log_path);
-        
-        // 使用行计数器跟踪读取的日志行数
-        let mut line_count = 0;
-        let mut last_log_time = std::time::Instant::now();
-        
-        // 初始化一个缓冲区
-        let mut buffer = vec![0; 1024]; 
-        let mut accumulated_data = Vec::new();
+        info!("Reading remote log data from: {} ({}) at byte {}", stream.log_path, stream.stream_id, start);
+        tail.offset = Some(start);
+        tail.inode = inode;
+        tail.remote_pid = None;
+        tail.pid_header = Some(Vec::new());
+        attempt = 0;
+        emit_stream_event(window, "ssh-log-connected", stream, None);
         
-        // 设置为非阻塞模式 - SSH2的Channel不支持set_blocking，使用其他方式处理
-        // 使用session的设置来影响通道行为
-        sess.set_blocking(false);
-        info!("Session set to non-blocking mode, starting read loop");
-        
-        loop {
-            // 检查连接是否应该停止（被其他请求取消）
-            {
-                let connections = ACTIVE_CONNECTIONS.lock().unwrap();
-                if !connections.connections.contains_key(&conn_key) {
-                    info!("连接已被请求停止: {}:{} for {}", conn_key.0, conn_key.1, log_path);
-                    break;
-                }
+        match tail.read_channel(stream, &mut channel, cancel) {
+            TailEnd::Stopped => {
+                teardown = close_remote_tail(credentials, channel, tail.remote_pid);
+                break;
             }
-            
-            match channel.read(&mut buffer) {
-                Ok(bytes_read) => {
-                    if bytes_read == 0 {
-                        // 在非阻塞模式下，返回0可能意味着暂时没有数据或EOF
-                        if channel.eof() {
-                            info!("Channel EOF detected, connection closed after reading {} lines", line_count);
-                            break;
-                        }
-                        // 等待一下再继续读取
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                        continue;
-                    }
-                    
-                    // 将读取到的数据添加到累积缓冲区
-                    accumulated_data.extend_from_slice(&buffer[0..bytes_read]);
-                    
-                    // 按行处理数据
-                    let mut start_idx = 0;
-                    while let Some(idx) = accumulated_data[start_idx..].iter().position(|&b| b == b'\n') {
-                        let line_end_idx = start_idx + idx;
-                        
-                        // 尝试转换行数据为UTF-8字符串
-                        if let Ok(line) = String::from_utf8(accumulated_data[start_idx..line_end_idx].to_vec()) {
-                            line_count += 1;
-                            
-                            // 每读取100行或者每5秒输出一次调试日志
-                            let now = std::time::Instant::now();
-                            if line_count % 100 == 0 || now.duration_since(last_log_time).as_secs() >= 5 {
-                                info!("Read {} lines from remote log. Latest content: {}", 
-                                     line_count, line.chars().take(50).collect::<String>());
-                                last_log_time = now;
-                            }
-                            
-                            // 打印每一行日志内容到Rust控制台
-                            info!("[远程日志] {}: {}", log_path, line);
-                            
-                            // 为每行创建LogStreamData并发送到前端
-                            let log_data = LogStreamData {
-                                content: line,
-                                is_complete: false,
-                                source: Some(log_path.clone()),
-                                error: None,
-                                timestamp: std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap_or_default()
-                                    .as_secs(),
-                            };
-                            
-                            if let Err(e) = window_clone.emit("ssh-log-data", log_data) {
-                                error!("Failed to emit log data: {}", e);
-                            }
-                        }
-                        
-                        // 移动到下一行的开始
-                        start_idx = line_end_idx + 1;
-                    }
-                    
-                    // 保留未处理的数据（可能是不完整的行）
-                    if start_idx < accumulated_data.len() {
-                        accumulated_data = accumulated_data[start_idx..].to_vec();
-                    } else {
-                        accumulated_data.clear();
-                    }
-                },
-                Err(e) => {
-                    // 在非阻塞模式下，WouldBlock错误是正常的
-                    if e.kind() == std::io::ErrorKind::WouldBlock {
-                        std::thread::sleep(std::time::Duration::from_millis(100));
-                        continue;
-                    }
-                    
-                    let err_msg = format!("读取远程日志失败: {}", e);
-                    error!("{}", err_msg);
-                    
-                    let log_data = LogStreamData {
-                        content: String::new(),
-                        is_complete: true,
-                        source: Some(log_path.clone()),
-                        error: Some(err_msg.clone()),
-                        timestamp: std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_default()
-                            .as_secs(),
-                    };
-                    
-                    let _ = window_clone.emit("log-data", log_data);
-                    let _ = window_clone.emit("ssh-log-error", err_msg);
+            TailEnd::Disconnected(err_msg) => {
+                let offset = tail.offset.unwrap_or(start);
+                if !wait_reconnect(window, stream, cancel, attempt, offset, err_msg) {
                     break;
                 }
+                attempt += 1;
             }
         }
-        
-        // 从活跃连接中移除
-        {
-            let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
-            connections.connections.remove(&conn_key);
-        }
-        
-        // 通知前端监控已结束
-        let _ = window_clone.emit("ssh-log-disconnected", log_path);
-        info!("Remote log monitoring ended for {}:{}", host, port);
-    });
+    }
     
-    Ok(())
+    tail.finish(stream);
+    // 等待缓冲区中剩下的行发送完，再通知前端流已结束
+    drop(tail);
+    
+    // 从活跃连接中移除
+    ACTIVE_CONNECTIONS.lock().unwrap().finish(&stream.stream_id, cancel);
+    
+    // 被请求停止时由停止命令收到清理结果后通知前端，这里只通知自行结束的情况
+    if !cancel.is_cancelled() {
+        emit_stream_event(window, "ssh-log-disconnected", stream, None);
+    }
+    info!(
+        "Remote log monitoring ended for {} ({}:{}), remote process {:?} killed: {}",
+        stream.stream_id, stream.host, stream.port, teardown.remote_pid, teardown.process_killed
+    );
+    let _ = finished.send(teardown);
+}
+
+// 合并后的多行记录转换为事件数据
+fn group_stream_data(
+    stream: &RemoteStreamInfo,
+    group: multiline::GroupedRecord,
+    record_parser: &mut Option<parser::RecordParser>,
+) -> LogStreamData {
+    let record = record_parser.as_mut().map(|p| multiline::parse_group(p, &group));
+    LogStreamData::new_line(group.text, &stream.log_path, record).for_stream(stream)
+}
+
+// 等待被停止的流完成清理（结束远程进程、关闭通道）后通知前端，所有流一共最多等待TEARDOWN_TIMEOUT
+async fn confirm_stopped(window: &tauri::Window, streams: Vec<RemoteStream>) {
+    let deadline = tokio::time::Instant::now() + TEARDOWN_TIMEOUT;
+    for stream in streams {
+        let (teardown, timed_out) = match tokio::time::timeout_at(deadline, stream.finished).await {
+            Ok(result) => (result.unwrap_or_default(), false),
+            Err(_) => {
+                warn!("日志流 {} 没有在{}秒内完成清理", stream.info.stream_id, TEARDOWN_TIMEOUT.as_secs());
+                (Teardown::default(), true)
+            }
+        };
+        let payload = MonitorStoppedEvent {
+            stream: stream.info.clone(),
+            remote_pid: teardown.remote_pid,
+            process_killed: teardown.process_killed,
+            timed_out,
+        };
+        if let Err(e) = window.emit("ssh-log-monitor-stopped", payload) {
+            error!("Failed to emit ssh-log-monitor-stopped: {}", e);
+        }
+        emit_stream_event(window, "ssh-log-disconnected", &stream.info, None);
+        info!("成功停止日志流 {}，日志路径: {}", stream.info.stream_id, stream.info.log_path);
+    }
 }
 
-// 停止监控远程日志
+// 停止监控远程日志（按流id），多主机合并流的id会停止它的所有成员；
+// 等到远程的tail进程结束、通道关闭之后才返回
 #[tauri::command]
-async fn stop_remote_log_monitor(window: tauri::Window, log_path: String) -> Result<(), String> {
-    info!("Stopping remote log monitor for: {}", log_path);
+async fn stop_remote_log_monitor(window: tauri::Window, stream_id: String) -> Result<(), EasyLogError> {
+    info!("Stopping remote log stream: {}", stream_id);
     
-    // 发送一个事件通知前端连接已关闭
-    let _ = window.emit("ssh-log-monitor-stopped", log_path);
+    let stopped = {
+        let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
+        match connections.stop(&stream_id) {
+            Some(stream) => vec![stream],
+            None => connections.stop_fanout(&stream_id),
+        }
+    };
+    if stopped.is_empty() {
+        return Err(EasyLogError::StreamNotFound { stream_id });
+    }
     
+    confirm_stopped(&window, stopped).await;
     Ok(())
 }
 
-// 停止指定服务器的日志流
+// 停止指定服务器上的所有日志流
 #[tauri::command]
-async fn stop_log_stream(window: tauri::Window, host: String, port: Option<u16>) -> Result<(), String> {
+async fn stop_log_stream(window: tauri::Window, host: String, port: Option<u16>) -> Result<(), EasyLogError> {
     let port = port.unwrap_or(22);
-    let conn_key = (host.clone(), port);
     
     info!("尝试停止服务器 {}:{} 的日志流", host, port);
     
     // 从活跃连接中移除
-    let log_path;
-    {
+    let stopped: Vec<RemoteStream> = {
         let mut connections = ACTIVE_CONNECTIONS.lock().unwrap();
-        if let Some(path) = connections.connections.remove(&conn_key) {
-            log_path = path;
-            info!("已移除服务器 {}:{} 的连接", host, port);
-        } else {
-            return Err(format!("未找到服务器 {}:{} 的活跃连接", host, port));
-        }
+        let ids: Vec<String> = connections
+            .streams
+            .values()
+            .filter(|stream| stream.info.host == host && stream.info.port == port)
+            .map(|stream| stream.info.stream_id.clone())
+            .collect();
+        ids.iter().filter_map(|id| connections.stop(id)).collect()
+    };
+    if stopped.is_empty() {
+        return Err(EasyLogError::StreamNotFound {
+            stream_id: format!("{}:{}", host, port),
+        });
     }
     
+    let count = stopped.len();
     // 通知前端连接已关闭
-    let _ = window.emit("ssh-log-monitor-stopped", &log_path);
-    let _ = window.emit("ssh-log-disconnected", &log_path);
+    confirm_stopped(&window, stopped).await;
     
-    info!("成功停止服务器 {}:{} 的日志流，日志路径: {}", host, port, log_path);
+    info!("成功停止服务器 {}:{} 的 {} 个日志流", host, port, count);
     Ok(())
 }
 
+// 列出正在运行的远程日志流
+#[tauri::command]
+fn list_remote_log_streams() -> Vec<RemoteStreamInfo> {
+    let mut streams: Vec<RemoteStreamInfo> = ACTIVE_CONNECTIONS
+        .lock()
+        .unwrap()
+        .streams
+        .values()
+        .map(|stream| stream.info.clone())
+        .collect();
+    streams.sort_by(|a, b| a.started_at.cmp(&b.started_at).then_with(|| a.stream_id.cmp(&b.stream_id)));
+    streams
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct LogFileInfo {
     path: String,
@@ -639,91 +1115,55 @@ struct LogFileInfo {
     is_remote: bool,
 }
 
+// 键盘交互认证会等待前端回答，查找在阻塞线程池中执行，不能阻塞主线程
 #[tauri::command]
-fn validate_ssh_logs(credentials: SshCredentials) -> Result<Vec<LogFileInfo>, String> {
+async fn validate_ssh_logs(credentials: profiles::SshTarget) -> Result<Vec<LogFileInfo>, EasyLogError> {
+    let credentials = credentials.resolve()?;
     info!("Validating SSH logs from: {}", credentials.host);
-    
-    let port = credentials.port.unwrap_or(22);
-    
-    // 创建TCP连接
-    let tcp = match std::net::TcpStream::connect(format!("{}:{}", credentials.host, port)) {
-        Ok(s) => s,
-        Err(e) => return Err(format!("无法连接到服务器: {}", e)),
-    };
-    
-    // 创建SSH会话
-    let mut sess = match ssh2::Session::new() {
-        Ok(s) => s,
-        Err(e) => return Err(format!("创建SSH会话失败: {}", e)),
-    };
-    
-    sess.set_tcp_stream(tcp);
-    if let Err(e) = sess.handshake() {
-        return Err(format!("SSH握手失败: {}", e));
-    }
-    
-    // 根据认证方式进行身份验证
-    let auth_result = match &credentials.auth_method {
-        AuthMethod::Password { password } => {
-            sess.userauth_password(&credentials.username, password)
-        },
-        AuthMethod::PublicKey { private_key_path, passphrase } => {
-            let path = std::path::Path::new(private_key_path);
-            let passphrase_str = passphrase.as_deref().unwrap_or("");
-            sess.userauth_pubkey_file(
-                &credentials.username,
-                None,
-                path,
-                Some(passphrase_str)
-            )
-        }
-    };
-    
-    if let Err(e) = auth_result {
-        return Err(format!("SSH认证失败: {}", e));
-    }
-    
-    // 查找常见日志目录中的日志文件
-    let common_dirs = vec![
-        "/var/log",
-        "/var/log/system",
-        "/var/log/messages",
-        "/var/log/syslog",
-        "/var/log/nginx",
-        "/var/log/apache2",
-        "/var/log/httpd",
+    tokio::task::spawn_blocking(move || find_remote_logs(&credentials))
+        .await
+        .map_err(|e| EasyLogError::internal(format!("查找远程日志失败: {}", e)))?
+}
+
+fn find_remote_logs(credentials: &SshCredentials) -> Result<Vec<LogFileInfo>, EasyLogError> {
+    // 查找常见日志目录和用户收藏的目录中的日志文件；find会递归查找子目录，
+    // 所以 /var/log 已经包含了 nginx、apache2 等子目录
+    let mut dirs = vec![
+        "/var/log".to_string(),
         // 可以添加更多常见日志目录
     ];
+    dirs.extend(remote_files::favorite_dirs(&credentials.host, credentials.port));
     
     let mut log_files = Vec::new();
+    let mut seen = std::collections::HashSet::new();
     
-    for dir in common_dirs {
-        // 打开一个通道并执行命令列出目录中的文件
-        let mut channel = match sess.channel_session() {
+    for dir in &dirs {
+        // 包含轮转后的日志（app.log.1、app.log.2.gz 等），压缩文件在读取时会自动解压；
+        // messages、syslog 等系统日志没有 .log 扩展名
+        let command = RemoteCommand::new(Program::Find)
+            .path(dir)
+            .args(["-type", "f", "(", "-name", "*.log", "-o", "-name", "*.out"])
+            .args(["-o", "-name", "*.log.[0-9]*", "-o", "-name", "*.log-[0-9]*"])
+            .args(["-o", "-name", "messages*", "-o", "-name", "syslog*", ")"])
+            .pipe(Program::Sort)
+            .pipe(Program::Head)
+            .arg("-50");
+        
+        // 所有目录的查找共用会话池中的同一个会话
+        let mut channel = match ssh::exec(credentials, &command) {
             Ok(c) => c,
-            Err(_e) => continue, // 如果无法创建通道，尝试下一个目录
+            Err(_e) => continue, // 如果命令执行失败，尝试下一个目录
         };
         
-        let command = format!("find \"{}\" -type f -name \"*.log\" -o -name \"*.out\" | grep -v \".gz\" | sort | head -50", dir);
-        
-        if let Err(_) = channel.exec(&command) {
-            continue; // 如果命令执行失败，尝试下一个目录
-        }
-        
         // 读取命令输出 - 直接使用channel.read方法，不使用BufReader
         let mut buffer = vec![0; 4096];
         let mut output = Vec::new();
         
-        loop {
-            match channel.read(&mut buffer) {
-                Ok(n) => {
-                    if n == 0 {
-                        break;
-                    }
-                    output.extend_from_slice(&buffer[0..n]);
-                },
-                Err(_) => break,
+        while let Ok(n) = channel.read(&mut buffer) {
+            if n == 0 {
+                break;
             }
+            output.extend_from_slice(&buffer[0..n]);
         }
         
         // 将输出转换为字符串并分割成行
@@ -742,6 +1182,11 @@ fn validate_ssh_logs(credentials: SshCredentials) -> Result<Vec<LogFileInfo>, St
                     None => continue,
                 };
                 
+                // 收藏的目录可能在 /var/log 之下，同一个文件只返回一次
+                if !seen.insert(line.to_string()) {
+                    continue;
+                }
+                
                 log_files.push(LogFileInfo {
                     path: line.to_string(),
                     name: file_name,
@@ -759,6 +1204,8 @@ pub fn run() {
     // 在应用启动时初始化日志系统
     setup_logging();
     info!("Starting application...");
+    // 清理上次运行留下的解压缓存，不阻塞启动
+    std::thread::spawn(compression::clean_cache);
 
     tauri::Builder::default()
         .plugin(tauri_plugin_http::init())
@@ -775,12 +1222,40 @@ pub fn run() {
             monitor_remote_log,
             stop_remote_log_monitor,
             stop_log_stream,
-            validate_ssh_logs
+            list_remote_log_streams,
+            fanout::monitor_remote_logs_merged,
+            validate_ssh_logs,
+            local_monitor::monitor_local_log,
+            local_monitor::stop_local_log_monitor,
+            rotation::read_rotation_group,
+            search::search_log,
+            parser::list_log_parsers,
+            parser::parse_log_lines,
+            time_filter::read_time_range,
+            profiles::ssh_profiles_status,
+            profiles::unlock_ssh_profiles,
+            profiles::lock_ssh_profiles,
+            profiles::change_master_password,
+            profiles::list_ssh_profiles,
+            profiles::create_ssh_profile,
+            profiles::update_ssh_profile,
+            profiles::delete_ssh_profile,
+            host_keys::trust_host_key,
+            host_keys::remove_host_key,
+            auth_prompt::answer_ssh_auth_prompt,
+            remote_files::list_remote_directory,
+            remote_files::list_remote_favorites,
+            remote_files::add_remote_favorite,
+            remote_files::remove_remote_favorite
         ])
         .setup(|app| {
             // 正确获取应用数据目录
             let app_dir = app.path().app_data_dir().expect("无法获取应用数据目录");
             std::fs::create_dir_all(&app_dir).unwrap();
+            profiles::init(&app_dir);
+            host_keys::init(&app_dir);
+            remote_files::init(&app_dir);
+            *APP_HANDLE.lock().unwrap() = Some(app.handle().clone());
             let highlighter_path = app_dir.join("highlighter.json");
             if !highlighter_path.exists() {
                 let default_highlighter = HighlighterConfig {
@@ -0,0 +1,456 @@
+// 本地日志文件实时监控：轮询文件变化，把新追加的行以事件形式发送给前端，
+// 事件名与SSH监控保持一致的命名方式（local-log-*），日志行同样通过批量事件（local-log-batch）发送
+use crate::encoding::{resolve_encoding, StreamDecoder, DETECT_SAMPLE_SIZE};
+use crate::error::EasyLogError;
+use crate::file_index::LineTerminator;
+use crate::multiline::{self, GroupedRecord, RecordGrouper, RecordStart};
+use crate::parser::RecordParser;
+use crate::stream_batch::{BatchOptions, BatchSender};
+use crate::{LogStreamData, RECORD_IDLE_FLUSH};
+use lazy_static::lazy_static;
+use log::{error, info};
+use std::collections::HashMap;
+use std::fs::{File, Metadata};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Emitter;
+
+// 轮询文件变化的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+// 每次读取的块大小
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+// 默认先显示文件末尾的行数，与远程监控的 tail -n 100 保持一致
+const DEFAULT_TAIL_LINES: usize = 100;
+// 记录文件开头的字节数，用于识别截断后又在一次轮询内写回到原位置之后的情况
+const HEAD_FINGERPRINT_SIZE: u64 = 512;
+
+lazy_static! {
+    // 正在监控的本地文件，值为停止标志
+    static ref LOCAL_MONITORS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+}
+
+// 文件标识，用于识别logrotate的create模式（原文件被重命名，新建同名文件）
+#[cfg(unix)]
+fn file_identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+// 非unix平台没有inode，退而使用文件创建时间区分新旧文件
+#[cfg(not(unix))]
+fn file_identity(metadata: &Metadata) -> Option<(u64, u64)> {
+    let created = metadata.created().ok()?;
+    let since_epoch = created.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some((since_epoch.as_secs(), since_epoch.subsec_nanos() as u64))
+}
+
+// 从文件末尾向前查找，返回倒数第lines行的起始字节偏移
+fn tail_start_offset(file: &mut File, lines: usize, terminator: LineTerminator) -> std::io::Result<u64> {
+    let len = file.metadata()?.len();
+    if lines == 0 || len == 0 {
+        return Ok(len);
+    }
+
+    // 按编码单元对齐，READ_CHUNK_SIZE是单元长度的整数倍，保证每块的起点也是对齐的
+    let unit = terminator.unit_len();
+    let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+    let mut end = len - len % unit as u64;
+    let mut newlines = 0;
+    // 文件末尾的换行符属于最后一行，不计入
+    let mut skip_trailing = true;
+
+    while end > 0 {
+        let start = end.saturating_sub(READ_CHUNK_SIZE as u64);
+        let size = (end - start) as usize;
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut buffer[..size])?;
+
+        for i in (0..size).step_by(unit).rev() {
+            if !terminator.is_newline(&buffer[i..i + unit]) {
+                skip_trailing = false;
+                continue;
+            }
+            if skip_trailing {
+                skip_trailing = false;
+                continue;
+            }
+            newlines += 1;
+            if newlines == lines {
+                return Ok(start + (i + unit) as u64);
+            }
+        }
+
+        end = start;
+    }
+
+    Ok(0)
+}
+
+// 单个本地文件的跟踪状态
+struct LocalTail {
+    path: String,
+    file: File,
+    position: u64,
+    identity: Option<(u64, u64)>,
+    // 最近一次看到的文件开头内容，copytruncate不改变inode，只能靠它判断文件是否被重写
+    head: Vec<u8>,
+    decoder: StreamDecoder,
+    // 已解码但还没有遇到换行符的内容
+    pending: String,
+    // 请求解析时使用的解析器，以及已输出的行数（用作记录的行号）
+    parser: Option<RecordParser>,
+    emitted_lines: usize,
+    // 指定记录起始规则时，用于合并多行记录
+    grouper: Option<RecordGrouper>,
+}
+
+impl LocalTail {
+    fn open(
+        path: &str,
+        tail_lines: usize,
+        encoding: Option<&str>,
+        parse: Option<&str>,
+        record_start: Option<&RecordStart>,
+    ) -> Result<Self, EasyLogError> {
+        let parser = parse.map(RecordParser::new).transpose()?;
+        let grouper = record_start.map(RecordGrouper::new).transpose()?;
+
+        let mut file = File::open(path).map_err(|e| EasyLogError::io(path, e))?;
+
+        // 用文件开头的内容识别编码，跟踪过程中沿用同一编码
+        let mut sample = Vec::new();
+        (&mut file)
+            .take(DETECT_SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)
+            .map_err(|e| EasyLogError::io(path, e))?;
+        let detected = resolve_encoding(encoding, &sample)?;
+        info!("Detected encoding {} for {}", detected.name(), path);
+
+        let terminator = LineTerminator::for_encoding(detected.encoding);
+        let head = sample[..sample.len().min(HEAD_FINGERPRINT_SIZE as usize)].to_vec();
+        let identity = file
+            .metadata()
+            .map(|m| file_identity(&m))
+            .map_err(|e| EasyLogError::io(path, e))?;
+        let position = tail_start_offset(&mut file, tail_lines, terminator)
+            .and_then(|position| file.seek(SeekFrom::Start(position)))
+            .map_err(|e| EasyLogError::io(path, e))?;
+
+        Ok(LocalTail {
+            path: path.to_string(),
+            file,
+            position,
+            identity,
+            head,
+            decoder: StreamDecoder::with_encoding(detected.encoding),
+            pending: String::new(),
+            parser,
+            emitted_lines: 0,
+            grouper,
+        })
+    }
+
+    // 读取当前句柄上新追加的数据，返回完整的行
+    fn read_new_lines(&mut self) -> std::io::Result<Vec<String>> {
+        let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+        loop {
+            let n = self.file.read(&mut buffer)?;
+            if n == 0 {
+                break;
+            }
+            self.position += n as u64;
+            let text = self.decoder.feed(&buffer[..n], false);
+            self.pending.push_str(&text);
+        }
+
+        let mut lines = Vec::new();
+        while let Some(idx) = self.pending.find('\n') {
+            let line: String = self.pending.drain(..=idx).collect();
+            lines.push(trim_newline(&line).to_string());
+        }
+
+        Ok(lines)
+    }
+
+    // 文件被截断或轮转时，把缓冲中最后一段不完整的内容作为一行输出
+    fn take_pending(&mut self) -> Option<String> {
+        let rest = self.decoder.feed(&[], true);
+        self.pending.push_str(&rest);
+        self.decoder.reset();
+
+        if self.pending.is_empty() {
+            return None;
+        }
+        let line = trim_newline(&self.pending).to_string();
+        self.pending.clear();
+        Some(line)
+    }
+
+    // 读取文件开头的内容，之后把读取位置恢复到当前偏移
+    fn read_head(&mut self) -> std::io::Result<Vec<u8>> {
+        let mut head = Vec::new();
+        self.file.seek(SeekFrom::Start(0))?;
+        (&mut self.file).take(HEAD_FINGERPRINT_SIZE).read_to_end(&mut head)?;
+        self.file.seek(SeekFrom::Start(self.position))?;
+        Ok(head)
+    }
+
+    // 检查文件是否被截断或轮转，返回描述变化的信息以及旧文件中残留的不完整行
+    fn check_rotation(&mut self) -> std::io::Result<Option<(String, Option<String>)>> {
+        // 原文件被重命名后新文件可能还没创建，继续读取旧句柄等待
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(m) => m,
+            Err(_) => return Ok(None),
+        };
+
+        let identity = file_identity(&metadata);
+        if identity.is_some() && identity != self.identity {
+            // create模式：旧文件已被移走，打开新文件并从头读取
+            let leftover = self.take_pending();
+            self.file = File::open(&self.path)?;
+            self.identity = file_identity(&self.file.metadata()?);
+            self.position = 0;
+            self.head.clear();
+            return Ok(Some((format!("日志文件已轮转，重新打开: {}", self.path), leftover)));
+        }
+
+        // copytruncate模式：文件被原地截断。截断后可能在一次轮询内又写回到原位置之后，
+        // 所以除了比较长度，还要确认文件开头的内容没有变化
+        let head = self.read_head()?;
+        if metadata.len() < self.position || !head.starts_with(&self.head) {
+            let leftover = self.take_pending();
+            self.file.seek(SeekFrom::Start(0))?;
+            self.position = 0;
+            self.head.clear();
+            return Ok(Some((format!("日志文件已被截断，从头读取: {}", self.path), leftover)));
+        }
+
+        // 文件开头不足HEAD_FINGERPRINT_SIZE时，随着文件增长补全记录的内容
+        self.head = head;
+        Ok(None)
+    }
+}
+
+fn trim_newline(line: &str) -> &str {
+    line.trim_end_matches('\n').trim_end_matches('\r')
+}
+
+fn emit_group(batch: &BatchSender, tail: &mut LocalTail, group: GroupedRecord) {
+    let record = tail.parser.as_mut().map(|p| multiline::parse_group(p, &group));
+    batch.push(LogStreamData::new_line(group.text, &tail.path, record));
+}
+
+fn emit_line(batch: &BatchSender, tail: &mut LocalTail, line: String) {
+    tail.emitted_lines += 1;
+    let line_number = tail.emitted_lines;
+
+    // 多行记录要等到下一条记录的起始行出现后才整条发送
+    if let Some(grouper) = tail.grouper.as_mut() {
+        if let Some(group) = grouper.push(line_number, line) {
+            emit_group(batch, tail, group);
+        }
+        return;
+    }
+
+    let record = tail.parser.as_mut().map(|p| p.parse(line_number, &line));
+    batch.push(LogStreamData::new_line(line, &tail.path, record));
+}
+
+// 一段时间没有新内容时，发送正在合并的记录；idle为None时立即发送
+fn flush_group(batch: &BatchSender, tail: &mut LocalTail, idle: Option<Duration>) {
+    let group = tail.grouper.as_mut().and_then(|grouper| match idle {
+        Some(idle) => grouper.flush_idle(idle),
+        None => grouper.flush(),
+    });
+    if let Some(group) = group {
+        emit_group(batch, tail, group);
+    }
+}
+
+fn run_local_monitor(window: tauri::Window, mut tail: LocalTail, batch: BatchSender, stop_flag: Arc<AtomicBool>) {
+    let path = tail.path.clone();
+
+    while !stop_flag.load(Ordering::SeqCst) {
+        let result = tail.read_new_lines().and_then(|lines| {
+            for line in lines {
+                emit_line(&batch, &mut tail, line);
+            }
+
+            // 旧句柄上的数据读完后再检查轮转，保证轮转前写入的行不会丢失
+            if let Some((message, leftover)) = tail.check_rotation()? {
+                if let Some(line) = leftover {
+                    emit_line(&batch, &mut tail, line);
+                }
+                flush_group(&batch, &mut tail, None);
+                info!("{}", message);
+                let _ = window.emit("local-log-rotated", &path);
+            }
+            flush_group(&batch, &mut tail, Some(RECORD_IDLE_FLUSH));
+            Ok(())
+        });
+
+        if let Err(e) = result {
+            let err_msg = format!("读取本地日志失败: {}", e);
+            error!("{}", err_msg);
+            let _ = window.emit("local-log-error", err_msg);
+            break;
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+
+    flush_group(&batch, &mut tail, None);
+    // 等待缓冲区中剩下的行发送完，再通知前端监控已结束
+    drop(batch);
+
+    // 只移除自己的停止标志，避免误删同一路径上新启动的监控
+    {
+        let mut monitors = LOCAL_MONITORS.lock().unwrap();
+        if monitors.get(&path).is_some_and(|flag| Arc::ptr_eq(flag, &stop_flag)) {
+            monitors.remove(&path);
+        }
+    }
+
+    let _ = window.emit("local-log-disconnected", &path);
+    info!("Local log monitoring ended for {}", path);
+}
+
+// 实时监控本地日志文件（使用事件系统）
+#[tauri::command]
+pub async fn monitor_local_log(
+    window: tauri::Window,
+    path: String,
+    tail_lines: Option<usize>,
+    encoding: Option<String>,
+    parse: Option<String>,
+    record_start: Option<RecordStart>,
+    batch: Option<BatchOptions>, // 可选参数，批量发送和缓冲区设置
+) -> Result<(), EasyLogError> {
+    info!("Starting local log monitoring for: {}", path);
+
+    if !Path::new(&path).exists() {
+        let err = EasyLogError::not_found(&path);
+        error!("{}", err);
+        return Err(err);
+    }
+
+    let tail = LocalTail::open(
+        &path,
+        tail_lines.unwrap_or(DEFAULT_TAIL_LINES),
+        encoding.as_deref(),
+        parse.as_deref(),
+        record_start.as_ref(),
+    )?;
+
+    // 同一个文件只保留一个监控，停止旧的监控
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    {
+        let mut monitors = LOCAL_MONITORS.lock().unwrap();
+        if let Some(old_flag) = monitors.insert(path.clone(), stop_flag.clone()) {
+            info!("已存在对 {} 的监控，停止旧的监控", path);
+            old_flag.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let _ = window.emit("local-log-connected", &path);
+
+    // 文件读取和轮询都是阻塞操作，使用独立线程处理
+    let batch = BatchSender::spawn(window.clone(), "local-log-batch", Some(path.clone()), &batch.unwrap_or_default());
+    std::thread::spawn(move || run_local_monitor(window, tail, batch, stop_flag));
+
+    Ok(())
+}
+
+// 停止监控本地日志
+#[tauri::command]
+pub async fn stop_local_log_monitor(window: tauri::Window, path: String) -> Result<(), EasyLogError> {
+    info!("Stopping local log monitor for: {}", path);
+
+    let stop_flag = LOCAL_MONITORS.lock().unwrap().remove(&path);
+    match stop_flag {
+        Some(flag) => {
+            flag.store(true, Ordering::SeqCst);
+            let _ = window.emit("local-log-monitor-stopped", &path);
+            Ok(())
+        }
+        None => Err(EasyLogError::StreamNotFound { stream_id: path }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_file(path: &Path, content: &str) {
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn open_tail(path: &Path) -> LocalTail {
+        LocalTail::open(path.to_str().unwrap(), 0, None, None, None).unwrap()
+    }
+
+    #[test]
+    fn reads_appended_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        write_file(&path, "old\n");
+        let mut tail = open_tail(&path);
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"first\nsecond\npart").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["first", "second"]);
+        assert!(tail.check_rotation().unwrap().is_none());
+
+        file.write_all(b"ial\n").unwrap();
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["partial"]);
+    }
+
+    #[test]
+    fn detects_truncation_to_shorter_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        write_file(&path, "line one\nline two\n");
+        let mut tail = open_tail(&path);
+
+        write_file(&path, "new\n");
+        assert!(tail.read_new_lines().unwrap().is_empty());
+        assert!(tail.check_rotation().unwrap().is_some());
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["new"]);
+    }
+
+    #[test]
+    fn detects_truncation_that_regrew_past_position() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        write_file(&path, "old line\n");
+        let mut tail = open_tail(&path);
+
+        // 截断后在一次轮询内写入了比原来更多的内容
+        let mut file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(0).unwrap();
+        file.write_all(b"new head line\nmore lines\n").unwrap();
+
+        assert!(tail.check_rotation().unwrap().is_some());
+        assert_eq!(tail.read_new_lines().unwrap(), vec!["new head line", "more lines"]);
+    }
+
+    #[test]
+    fn growing_short_file_is_not_truncation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        write_file(&path, "a\n");
+        let mut tail = open_tail(&path);
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        for i in 0..200 {
+            writeln!(file, "line {}", i).unwrap();
+            tail.read_new_lines().unwrap();
+            assert!(tail.check_rotation().unwrap().is_none());
+        }
+        assert_eq!(tail.head.len(), HEAD_FINGERPRINT_SIZE as usize);
+    }
+}
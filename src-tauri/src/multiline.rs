@@ -0,0 +1,231 @@
+// 多行记录分组：按记录起始行的规则，把异常堆栈等续行合并到前一条记录中，
+// 读取文件和实时监控都以完整的记录为单位返回，而不是逐行拆开
+use crate::error::EasyLogError;
+use crate::parser::{leading_timestamp, LogRecord, RecordParser};
+use regex::Regex;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+// 单条记录最多合并的行数，避免没有起始行的大段内容一直累积
+const MAX_RECORD_LINES: usize = 1000;
+
+// 记录起始行的识别规则
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum RecordStart {
+    // 以时间开头的行是新记录的开始
+    #[serde(rename = "timestamp")]
+    Timestamp,
+    // 匹配正则表达式的行是新记录的开始
+    #[serde(rename = "regex")]
+    Regex { pattern: String },
+}
+
+// 合并后的一条记录
+#[derive(Debug, Clone)]
+pub struct GroupedRecord {
+    pub line_number: usize, // 第一行的行号
+    pub line_count: usize,
+    pub text: String, // 各行之间用\n连接
+}
+
+impl GroupedRecord {
+    fn new(line_number: usize, line: String) -> Self {
+        GroupedRecord {
+            line_number,
+            line_count: 1,
+            text: line,
+        }
+    }
+
+    // 不解析格式时的记录：整条记录作为消息
+    pub fn into_raw_record(self) -> LogRecord {
+        LogRecord::unparsed(self.line_number, &self.text)
+    }
+}
+
+pub struct RecordGrouper {
+    matcher: Option<Regex>, // 为空时按行首时间识别
+    current: Option<GroupedRecord>,
+    last_push: Instant,
+}
+
+impl RecordGrouper {
+    pub fn new(start: &RecordStart) -> Result<Self, EasyLogError> {
+        let matcher = match start {
+            RecordStart::Timestamp => None,
+            RecordStart::Regex { pattern } => {
+                Some(Regex::new(pattern).map_err(|e| EasyLogError::invalid("record_start", e))?)
+            }
+        };
+        Ok(RecordGrouper {
+            matcher,
+            current: None,
+            last_push: Instant::now(),
+        })
+    }
+
+    fn is_start(&self, line: &str) -> bool {
+        match &self.matcher {
+            Some(matcher) => matcher.is_match(line),
+            None => leading_timestamp(line).is_some(),
+        }
+    }
+
+    // 加入一行，遇到新记录的起始行时返回已经完整的上一条记录
+    pub fn push(&mut self, line_number: usize, line: String) -> Option<GroupedRecord> {
+        self.last_push = Instant::now();
+
+        let is_start = self.is_start(&line);
+        match self.current.as_mut() {
+            Some(current) if !is_start && current.line_count < MAX_RECORD_LINES => {
+                current.text.push('\n');
+                current.text.push_str(&line);
+                current.line_count += 1;
+                None
+            }
+            _ => self.current.replace(GroupedRecord::new(line_number, line)),
+        }
+    }
+
+    // 取出还没有结束的记录（数据读完或者长时间没有新内容时调用）
+    pub fn flush(&mut self) -> Option<GroupedRecord> {
+        self.current.take()
+    }
+
+    // 超过idle时间没有新行时，认为当前记录已经结束
+    pub fn flush_idle(&mut self, idle: Duration) -> Option<GroupedRecord> {
+        if self.last_push.elapsed() >= idle {
+            self.flush()
+        } else {
+            None
+        }
+    }
+}
+
+// 把一段文本按记录分组，first_line_number为第一行的行号
+pub fn group_lines(start: &RecordStart, content: &str, first_line_number: usize) -> Result<Vec<GroupedRecord>, EasyLogError> {
+    let mut grouper = RecordGrouper::new(start)?;
+    let mut groups: Vec<GroupedRecord> = content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| grouper.push(first_line_number + i, line.to_string()))
+        .collect();
+    groups.extend(grouper.flush());
+    Ok(groups)
+}
+
+// 解析合并后的记录：用第一行识别格式，续行追加到消息和原文中
+pub fn parse_group(parser: &mut RecordParser, group: &GroupedRecord) -> LogRecord {
+    let (first, rest) = match group.text.split_once('\n') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (group.text.as_str(), None),
+    };
+
+    let mut record = parser.parse(group.line_number, first);
+    if let Some(rest) = rest {
+        record.message.push('\n');
+        record.message.push_str(rest);
+        record.raw = group.text.clone();
+    }
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STACK_TRACE: &str = "2024-01-02 03:04:05,000 [main] ERROR a.B - failed\n\
+java.lang.IllegalStateException: boom\n\
+\tat a.B.run(B.java:10)\n\
+2024-01-02 03:04:06,000 [main] INFO a.B - recovered\n";
+
+    #[test]
+    fn groups_continuation_lines_by_timestamp() {
+        let groups = group_lines(&RecordStart::Timestamp, STACK_TRACE, 1).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].line_number, 1);
+        assert_eq!(groups[0].line_count, 3);
+        assert!(groups[0].text.ends_with("\tat a.B.run(B.java:10)"));
+        assert_eq!(groups[1].line_number, 4);
+        assert_eq!(groups[1].line_count, 1);
+    }
+
+    #[test]
+    fn leading_continuation_lines_form_their_own_record() {
+        let content = "\tat x.Y(Y.java:1)\n\tat x.Z(Z.java:2)\n2024-01-02 03:04:05 start\n";
+        let groups = group_lines(&RecordStart::Timestamp, content, 10).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].line_number, 10);
+        assert_eq!(groups[0].line_count, 2);
+        assert_eq!(groups[1].line_number, 12);
+    }
+
+    #[test]
+    fn groups_by_regex() {
+        let start = RecordStart::Regex {
+            pattern: r"^\[\d+\]".to_string(),
+        };
+        let groups = group_lines(&start, "[1] a\n  b\n[2] c\n[3] d\n  e\n  f\n", 1).unwrap();
+        let texts: Vec<_> = groups.iter().map(|g| g.text.as_str()).collect();
+        assert_eq!(texts, ["[1] a\n  b", "[2] c", "[3] d\n  e\n  f"]);
+
+        let invalid = RecordStart::Regex {
+            pattern: "(".to_string(),
+        };
+        assert!(group_lines(&invalid, "x", 1).is_err());
+    }
+
+    #[test]
+    fn records_are_capped_at_max_lines() {
+        let content: String = std::iter::once("2024-01-02 03:04:05 start\n".to_string())
+            .chain((0..MAX_RECORD_LINES + 5).map(|i| format!("  continuation {}\n", i)))
+            .collect();
+        let groups = group_lines(&RecordStart::Timestamp, &content, 1).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].line_count, MAX_RECORD_LINES);
+        assert_eq!(groups[1].line_number, MAX_RECORD_LINES + 1);
+        assert_eq!(groups[1].line_count, 6);
+    }
+
+    #[test]
+    fn flush_idle_waits_for_idle_time() {
+        let mut grouper = RecordGrouper::new(&RecordStart::Timestamp).unwrap();
+        assert!(grouper.push(1, "2024-01-02 03:04:05 start".to_string()).is_none());
+        assert!(grouper.flush_idle(Duration::from_secs(60)).is_none());
+        let group = grouper.flush_idle(Duration::ZERO).unwrap();
+        assert_eq!(group.line_number, 1);
+        assert!(grouper.flush().is_none());
+    }
+
+    #[test]
+    fn parse_group_appends_continuation_lines() {
+        let groups = group_lines(&RecordStart::Timestamp, STACK_TRACE, 1).unwrap();
+        let mut parser = RecordParser::new("auto").unwrap();
+
+        let record = parse_group(&mut parser, &groups[0]);
+        assert_eq!(record.format.as_deref(), Some("logback"));
+        assert_eq!(record.line_number, 1);
+        assert_eq!(record.level.as_deref(), Some("ERROR"));
+        assert_eq!(
+            record.message,
+            "failed\njava.lang.IllegalStateException: boom\n\tat a.B.run(B.java:10)"
+        );
+        assert_eq!(record.raw, groups[0].text);
+
+        let record = parse_group(&mut parser, &groups[1]);
+        assert_eq!(record.line_number, 4);
+        assert_eq!(record.message, "recovered");
+        assert_eq!(record.raw, "2024-01-02 03:04:06,000 [main] INFO a.B - recovered");
+    }
+
+    #[test]
+    fn raw_records_keep_grouped_text() {
+        let groups = group_lines(&RecordStart::Timestamp, STACK_TRACE, 5).unwrap();
+        let record = groups[0].clone().into_raw_record();
+        assert_eq!(record.line_number, 5);
+        assert_eq!(record.format, None);
+        assert_eq!(record.message, groups[0].text);
+        assert_eq!(record.raw, groups[0].text);
+    }
+}
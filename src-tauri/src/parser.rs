@@ -0,0 +1,544 @@
+// 日志行解析：把原始文本行解析为包含时间、级别、logger、线程、消息等字段的结构化记录，
+// 内置常见格式（JSON Lines、log4j/logback、Python logging、syslog、nginx/apache combined、env_logger）
+use crate::error::EasyLogError;
+use crate::json_lines::JsonLinesParser;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LogRecord {
+    pub line_number: usize,          // 从1开始的行号
+    pub timestamp: Option<String>,   // 日志中的原始时间文本
+    pub timestamp_ms: Option<i64>,   // 解析后的Unix毫秒时间戳，没有时区的时间按本地时区处理
+    pub level: Option<String>,       // 统一为大写：TRACE/DEBUG/INFO/WARN/ERROR/FATAL
+    pub logger: Option<String>,
+    pub thread: Option<String>,
+    pub message: String,
+    pub fields: BTreeMap<String, String>, // 格式特有的其它字段
+    pub format: Option<String>,      // 匹配到的格式名称，未识别时为空
+    pub raw: String,
+}
+
+impl LogRecord {
+    // 无法识别格式的行，整行作为消息
+    pub fn unparsed(line_number: usize, line: &str) -> Self {
+        LogRecord {
+            line_number,
+            message: line.to_string(),
+            raw: line.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+// 单一格式的解析器
+pub trait LineParser: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    fn parse(&self, line: &str) -> Option<LogRecord>;
+}
+
+// 基于正则命名分组的解析器：ts/level/logger/thread/msg 映射到对应字段，其余分组放入fields
+struct RegexParser {
+    name: &'static str,
+    description: &'static str,
+    patterns: Vec<Regex>,
+    // 根据已解析的字段推导级别（例如syslog的PRI、HTTP状态码）
+    derive_level: Option<fn(&LogRecord) -> Option<String>>,
+}
+
+impl LineParser for RegexParser {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn parse(&self, line: &str) -> Option<LogRecord> {
+        let (pattern, captures) = self
+            .patterns
+            .iter()
+            .find_map(|p| p.captures(line).map(|c| (p, c)))?;
+
+        let mut record = LogRecord {
+            raw: line.to_string(),
+            format: Some(self.name.to_string()),
+            ..Default::default()
+        };
+
+        for name in pattern.capture_names().flatten() {
+            let Some(value) = captures.name(name) else { continue };
+            let value = value.as_str();
+            match name {
+                "ts" => {
+                    record.timestamp = Some(value.to_string());
+                    record.timestamp_ms = parse_timestamp(value);
+                }
+                "level" => record.level = Some(normalize_level(value)),
+                "logger" => record.logger = Some(value.to_string()),
+                "thread" => record.thread = Some(value.to_string()),
+                "msg" => record.message = value.to_string(),
+                _ => {
+                    record.fields.insert(name.to_string(), value.to_string());
+                }
+            }
+        }
+
+        if record.level.is_none() {
+            if let Some(derive) = self.derive_level {
+                record.level = derive(&record);
+            }
+        }
+
+        Some(record)
+    }
+}
+
+// 统一级别名称
+pub fn normalize_level(level: &str) -> String {
+    match level.trim().to_ascii_uppercase().as_str() {
+        "WARNING" => "WARN".to_string(),
+        "CRITICAL" | "CRIT" | "EMERG" | "EMERGENCY" | "ALERT" | "PANIC" => "FATAL".to_string(),
+        "ERR" | "SEVERE" => "ERROR".to_string(),
+        "NOTICE" | "INFORMATION" => "INFO".to_string(),
+        "FINE" | "FINER" | "FINEST" => "DEBUG".to_string(),
+        other => other.to_string(),
+    }
+}
+
+// syslog PRI的低3位是严重级别
+fn syslog_level(record: &LogRecord) -> Option<String> {
+    let pri: u32 = record.fields.get("pri")?.parse().ok()?;
+    let level = match pri % 8 {
+        0..=2 => "FATAL",
+        3 => "ERROR",
+        4 => "WARN",
+        5 | 6 => "INFO",
+        _ => "DEBUG",
+    };
+    Some(level.to_string())
+}
+
+// 访问日志根据HTTP状态码推导级别
+fn http_status_level(record: &LogRecord) -> Option<String> {
+    let status: u16 = record.fields.get("status")?.parse().ok()?;
+    let level = match status {
+        500.. => "ERROR",
+        400..=499 => "WARN",
+        _ => "INFO",
+    };
+    Some(level.to_string())
+}
+
+const LEVELS: &str = r"TRACE|DEBUG|INFO|NOTICE|WARN|WARNING|ERROR|SEVERE|CRITICAL|FATAL";
+const ISO_TS: &str = r"\d{4}-\d{2}-\d{2}[ T]\d{2}:\d{2}:\d{2}(?:[.,]\d{1,9})?(?:Z|[+-]\d{2}:?\d{2})?";
+
+fn regex(pattern: &str) -> Regex {
+    Regex::new(&pattern.replace("{LEVELS}", LEVELS).replace("{ISO_TS}", ISO_TS)).expect("valid built-in pattern")
+}
+
+lazy_static! {
+    // 内置解析器，自动识别时按顺序尝试，格式越严格的越靠前
+    static ref BUILTIN_PARSERS: Vec<Box<dyn LineParser>> = vec![
+        Box::new(JsonLinesParser),
+        Box::new(RegexParser {
+            name: "syslog_rfc5424",
+            description: "Syslog RFC 5424: <PRI>VERSION TIMESTAMP HOST APP PROCID MSGID [SD] MSG",
+            patterns: vec![regex(
+                r"^<(?P<pri>\d{1,3})>(?P<version>\d{1,2}) (?P<ts>\S+) (?P<host>\S+) (?P<logger>\S+) (?P<procid>\S+) (?P<msgid>\S+) (?P<structured_data>-|(?:\[[^\]]*\])+)(?: (?P<msg>.*))?$",
+            )],
+            derive_level: Some(syslog_level),
+        }),
+        Box::new(RegexParser {
+            name: "syslog_rfc3164",
+            description: "Syslog RFC 3164: <PRI>Mmm dd hh:mm:ss HOST TAG[PID]: MSG",
+            patterns: vec![regex(
+                r"^(?:<(?P<pri>\d{1,3})>)?(?P<ts>[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}) (?P<host>\S+) (?P<logger>[^:\[\s]+)(?:\[(?P<pid>\d+)\])?: (?P<msg>.*)$",
+            )],
+            derive_level: Some(syslog_level),
+        }),
+        Box::new(RegexParser {
+            name: "combined",
+            description: "Nginx / Apache combined access log",
+            patterns: vec![regex(
+                r#"^(?P<remote_addr>\S+) (?P<ident>\S+) (?P<remote_user>\S+) \[(?P<ts>[^\]]+)\] "(?P<msg>[^"]*)" (?P<status>\d{3}) (?P<body_bytes>\S+)(?: "(?P<referer>[^"]*)" "(?P<user_agent>[^"]*)")?.*$"#,
+            )],
+            derive_level: Some(http_status_level),
+        }),
+        Box::new(RegexParser {
+            name: "env_logger",
+            description: "Rust env_logger: [TIMESTAMP LEVEL target] message",
+            patterns: vec![regex(
+                r"^\[(?:(?P<ts>\d{4}-\d{2}-\d{2}T[^\s\]]+)\s+)?(?P<level>TRACE|DEBUG|INFO|WARN|ERROR)\s+(?P<logger>[^\]\s]+)\]\s?(?P<msg>.*)$",
+            )],
+            derive_level: None,
+        }),
+        Box::new(RegexParser {
+            name: "logback",
+            description: "Logback / log4j2: TIMESTAMP [thread] LEVEL logger - message",
+            patterns: vec![regex(
+                r"^(?P<ts>{ISO_TS})\s+\[(?P<thread>[^\]]+)\]\s+(?P<level>{LEVELS})\s+(?P<logger>\S+)\s+-\s?(?P<msg>.*)$",
+            )],
+            derive_level: None,
+        }),
+        Box::new(RegexParser {
+            name: "log4j",
+            description: "log4j: TIMESTAMP LEVEL [thread] logger - message",
+            patterns: vec![
+                regex(r"^(?P<ts>{ISO_TS})\s+(?P<level>{LEVELS})\s+\[(?P<thread>[^\]]+)\]\s+(?P<logger>\S+)\s+-\s?(?P<msg>.*)$"),
+                regex(r"^(?P<ts>{ISO_TS})\s+(?P<level>{LEVELS})\s+(?P<logger>\S+)(?:\s+-|:)\s?(?P<msg>.*)$"),
+            ],
+            derive_level: None,
+        }),
+        Box::new(RegexParser {
+            name: "python",
+            description: "Python logging: TIMESTAMP - name - LEVEL - message / LEVEL:name:message",
+            patterns: vec![
+                regex(r"^(?P<ts>{ISO_TS}) - (?P<logger>\S+) - (?P<level>{LEVELS}) - (?P<msg>.*)$"),
+                regex(r"^(?P<level>DEBUG|INFO|WARNING|ERROR|CRITICAL):(?P<logger>[^:]+):(?P<msg>.*)$"),
+            ],
+            derive_level: None,
+        }),
+    ];
+}
+
+pub fn builtin_parsers() -> &'static [Box<dyn LineParser>] {
+    &BUILTIN_PARSERS
+}
+
+// 解析时间文本，返回Unix毫秒时间戳；没有时区信息的时间按本地时区处理
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    let text = text.trim();
+
+    // RFC 3339 / ISO 8601（带时区），逗号分隔的毫秒先统一为点号
+    let normalized = text.replacen(',', ".", 1);
+    if let Ok(dt) = DateTime::parse_from_rfc3339(&normalized.replacen(' ', "T", 1)) {
+        return Some(dt.timestamp_millis());
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f%z", "%Y-%m-%dT%H:%M:%S%.f%z", "%d/%b/%Y:%H:%M:%S %z"] {
+        if let Ok(dt) = DateTime::parse_from_str(&normalized, format) {
+            return Some(dt.timestamp_millis());
+        }
+    }
+
+    // 不带时区的时间，包括紧凑格式 20240102T030405 / 20240102030405
+    for format in [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y/%m/%d %H:%M:%S%.f",
+        "%Y%m%dT%H%M%S",
+        "%Y%m%d%H%M%S",
+    ] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(&normalized, format) {
+            return local_millis(naive);
+        }
+    }
+
+    // syslog RFC 3164 的时间没有年份，取当前年份；如果因此落在未来，则认为是去年
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let now = Local::now();
+    for year in [now.year(), now.year() - 1] {
+        let with_year = format!("{} {}", year, collapsed);
+        if let Ok(naive) = NaiveDateTime::parse_from_str(&with_year, "%Y %b %d %H:%M:%S") {
+            let millis = local_millis(naive)?;
+            if millis <= now.timestamp_millis() + 86_400_000 {
+                return Some(millis);
+            }
+        }
+    }
+
+    // 只有日期的情况
+    ["%Y-%m-%d", "%Y%m%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(text, format).ok())
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .and_then(local_millis)
+}
+
+lazy_static! {
+    // 行首的时间：ISO格式或syslog格式，可以带 [ 或 <PRI> 前缀
+    static ref LEADING_TIMESTAMP: Regex = regex(
+        r"^(?:<\d{1,3}>(?:\d{1,2} )?)?\[?((?:\d{4}[-/]\d{2}[-/]\d{2}[ T]\d{2}:\d{2}:\d{2}(?:[.,]\d{1,9})?(?:Z|[+-]\d{2}:?\d{2})?)|(?:[A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2}))",
+    );
+}
+
+// 返回行首的时间文本，用于没有匹配到内置格式的行，以及识别多行记录的起始行
+pub fn leading_timestamp(line: &str) -> Option<&str> {
+    LEADING_TIMESTAMP
+        .captures(line)
+        .and_then(|c| c.get(1))
+        .map(|m| m.as_str())
+}
+
+fn local_millis(naive: NaiveDateTime) -> Option<i64> {
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+}
+
+// 按调用方选择的格式解析日志行："auto"表示逐行自动识别，其它值为内置解析器名称
+pub struct RecordParser {
+    fixed: Option<&'static dyn LineParser>,
+    // 自动识别时优先尝试上一次匹配成功的解析器
+    last_matched: Option<usize>,
+}
+
+impl RecordParser {
+    pub fn new(selection: &str) -> Result<Self, EasyLogError> {
+        let selection = selection.trim();
+        if selection.is_empty() || selection == "auto" {
+            return Ok(RecordParser {
+                fixed: None,
+                last_matched: None,
+            });
+        }
+
+        let parser = builtin_parsers()
+            .iter()
+            .find(|p| p.name() == selection)
+            .ok_or_else(|| EasyLogError::invalid("parse", format!("未知的日志格式: {}", selection)))?;
+        Ok(RecordParser {
+            fixed: Some(parser.as_ref()),
+            last_matched: None,
+        })
+    }
+
+    pub fn parse(&mut self, line_number: usize, line: &str) -> LogRecord {
+        let record = match self.fixed {
+            Some(parser) => parser.parse(line),
+            None => self.parse_auto(line),
+        };
+
+        let mut record = record.unwrap_or_else(|| LogRecord::unparsed(line_number, line));
+        record.line_number = line_number;
+        record
+    }
+
+    fn parse_auto(&mut self, line: &str) -> Option<LogRecord> {
+        let parsers = builtin_parsers();
+        if let Some(record) = self.last_matched.and_then(|i| parsers[i].parse(line)) {
+            return Some(record);
+        }
+
+        parsers.iter().enumerate().find_map(|(i, parser)| {
+            let record = parser.parse(line)?;
+            self.last_matched = Some(i);
+            Some(record)
+        })
+    }
+}
+
+// 解析一段文本中的所有行，first_line_number为第一行的行号
+pub fn parse_content(selection: &str, content: &str, first_line_number: usize) -> Result<Vec<LogRecord>, EasyLogError> {
+    let mut parser = RecordParser::new(selection)?;
+    Ok(content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| parser.parse(first_line_number + i, line))
+        .collect())
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ParserInfo {
+    name: &'static str,
+    description: &'static str,
+}
+
+// 列出内置的日志格式
+#[tauri::command]
+pub fn list_log_parsers() -> Vec<ParserInfo> {
+    builtin_parsers()
+        .iter()
+        .map(|p| ParserInfo {
+            name: p.name(),
+            description: p.description(),
+        })
+        .collect()
+}
+
+// 解析前端传入的日志行
+#[tauri::command]
+pub fn parse_log_lines(lines: Vec<String>, parser: Option<String>, first_line_number: Option<usize>) -> Result<Vec<LogRecord>, EasyLogError> {
+    let mut record_parser = RecordParser::new(parser.as_deref().unwrap_or("auto"))?;
+    let first = first_line_number.unwrap_or(1);
+    Ok(lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| record_parser.parse(first + i, line))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_with(name: &str, line: &str) -> LogRecord {
+        let record = RecordParser::new(name).unwrap().parse(1, line);
+        assert_eq!(record.format.as_deref(), Some(name), "{} did not match: {}", name, line);
+        record
+    }
+
+    fn field<'a>(record: &'a LogRecord, name: &str) -> &'a str {
+        record.fields.get(name).map(String::as_str).unwrap_or_default()
+    }
+
+    #[test]
+    fn syslog_rfc5424() {
+        let record = parse_with(
+            "syslog_rfc5424",
+            r#"<165>1 2024-01-02T03:04:05.000Z host1 myapp 1234 ID47 [exampleSDID@32473 iut="3"] started worker"#,
+        );
+        assert_eq!(record.timestamp_ms, Some(1_704_164_645_000));
+        assert_eq!(record.logger.as_deref(), Some("myapp"));
+        assert_eq!(record.level.as_deref(), Some("INFO"));
+        assert_eq!(field(&record, "host"), "host1");
+        assert_eq!(field(&record, "procid"), "1234");
+        assert_eq!(record.message, "started worker");
+
+        let record = parse_with("syslog_rfc5424", "<11>1 2024-01-02T03:04:05Z host app - - -");
+        assert_eq!(record.level.as_deref(), Some("ERROR"));
+        assert_eq!(record.message, "");
+    }
+
+    #[test]
+    fn syslog_rfc3164() {
+        let record = parse_with("syslog_rfc3164", "<28>Jan  2 03:04:05 host1 sshd[4321]: Connection closed");
+        assert_eq!(record.timestamp.as_deref(), Some("Jan  2 03:04:05"));
+        assert!(record.timestamp_ms.is_some());
+        assert_eq!(record.logger.as_deref(), Some("sshd"));
+        assert_eq!(field(&record, "pid"), "4321");
+        assert_eq!(record.level.as_deref(), Some("WARN"));
+        assert_eq!(record.message, "Connection closed");
+
+        // 没有PRI时不推导级别
+        let record = parse_with("syslog_rfc3164", "Jan 12 03:04:05 host1 kernel: eth0 up");
+        assert_eq!(record.level, None);
+        assert_eq!(field(&record, "pid"), "");
+    }
+
+    #[test]
+    fn combined_access_log() {
+        let record = parse_with(
+            "combined",
+            r#"10.0.0.1 - alice [02/Jan/2024:03:04:05 +0000] "GET /index.html HTTP/1.1" 503 1234 "https://example.com/" "curl/8.0""#,
+        );
+        assert_eq!(record.timestamp_ms, Some(1_704_164_645_000));
+        assert_eq!(record.message, "GET /index.html HTTP/1.1");
+        assert_eq!(record.level.as_deref(), Some("ERROR"));
+        assert_eq!(field(&record, "remote_addr"), "10.0.0.1");
+        assert_eq!(field(&record, "remote_user"), "alice");
+        assert_eq!(field(&record, "status"), "503");
+        assert_eq!(field(&record, "user_agent"), "curl/8.0");
+
+        let record = parse_with("combined", r#"::1 - - [02/Jan/2024:03:04:05 +0000] "GET / HTTP/1.1" 404 -"#);
+        assert_eq!(record.level.as_deref(), Some("WARN"));
+    }
+
+    #[test]
+    fn env_logger() {
+        let record = parse_with("env_logger", "[2024-01-02T03:04:05Z WARN  my_crate::net] retrying");
+        assert_eq!(record.timestamp_ms, Some(1_704_164_645_000));
+        assert_eq!(record.level.as_deref(), Some("WARN"));
+        assert_eq!(record.logger.as_deref(), Some("my_crate::net"));
+        assert_eq!(record.message, "retrying");
+
+        let record = parse_with("env_logger", "[INFO app] no timestamp");
+        assert_eq!(record.timestamp, None);
+        assert_eq!(record.message, "no timestamp");
+    }
+
+    #[test]
+    fn logback() {
+        let record = parse_with(
+            "logback",
+            "2024-01-02 03:04:05,123+00:00 [http-nio-8080-exec-1] ERROR com.example.Service - request failed",
+        );
+        assert_eq!(record.timestamp_ms, Some(1_704_164_645_123));
+        assert_eq!(record.thread.as_deref(), Some("http-nio-8080-exec-1"));
+        assert_eq!(record.level.as_deref(), Some("ERROR"));
+        assert_eq!(record.logger.as_deref(), Some("com.example.Service"));
+        assert_eq!(record.message, "request failed");
+    }
+
+    #[test]
+    fn log4j() {
+        let record = parse_with("log4j", "2024-01-02T03:04:05.5Z WARNING [main] org.app.Main - low disk");
+        assert_eq!(record.timestamp_ms, Some(1_704_164_645_500));
+        assert_eq!(record.level.as_deref(), Some("WARN"));
+        assert_eq!(record.thread.as_deref(), Some("main"));
+        assert_eq!(record.logger.as_deref(), Some("org.app.Main"));
+        assert_eq!(record.message, "low disk");
+
+        let record = parse_with("log4j", "2024-01-02 03:04:05Z INFO org.app.Main: ready");
+        assert_eq!(record.thread, None);
+        assert_eq!(record.message, "ready");
+    }
+
+    #[test]
+    fn python_logging() {
+        let record = parse_with("python", "2024-01-02 03:04:05,250Z - app.db - CRITICAL - pool exhausted");
+        assert_eq!(record.timestamp_ms, Some(1_704_164_645_250));
+        assert_eq!(record.logger.as_deref(), Some("app.db"));
+        assert_eq!(record.level.as_deref(), Some("FATAL"));
+        assert_eq!(record.message, "pool exhausted");
+
+        let record = parse_with("python", "WARNING:root:value: 3");
+        assert_eq!(record.level.as_deref(), Some("WARN"));
+        assert_eq!(record.logger.as_deref(), Some("root"));
+        assert_eq!(record.message, "value: 3");
+    }
+
+    #[test]
+    fn auto_detection_and_unparsed_lines() {
+        let mut parser = RecordParser::new("auto").unwrap();
+        let record = parser.parse(7, "2024-01-02 03:04:05Z [main] INFO a.B - hi");
+        assert_eq!(record.format.as_deref(), Some("logback"));
+        assert_eq!(record.line_number, 7);
+
+        let record = parser.parse(8, "    at com.example.Foo(Foo.java:12)");
+        assert_eq!(record.format, None);
+        assert_eq!(record.message, "    at com.example.Foo(Foo.java:12)");
+        assert_eq!(record.raw, record.message);
+
+        assert!(RecordParser::new("no_such_format").is_err());
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_timestamp("2024-01-02T03:04:05Z"), Some(1_704_164_645_000));
+        assert_eq!(parse_timestamp("2024-01-02 03:04:05.123+01:00"), Some(1_704_161_045_123));
+        assert_eq!(parse_timestamp("2024-01-02 03:04:05,5+0000"), Some(1_704_164_645_500));
+        assert_eq!(parse_timestamp("02/Jan/2024:03:04:05 +0000"), Some(1_704_164_645_000));
+        assert!(parse_timestamp("2024-01-02 03:04:05").is_some());
+        assert!(parse_timestamp("2024/01/02 03:04:05").is_some());
+        assert!(parse_timestamp("2024-01-02").is_some());
+        assert_eq!(parse_timestamp("20240102"), parse_timestamp("2024-01-02"));
+        assert_eq!(parse_timestamp("20240102030405"), parse_timestamp("2024-01-02 03:04:05"));
+        assert_eq!(parse_timestamp("20240102T030405"), parse_timestamp("2024-01-02 03:04:05"));
+        assert_eq!(parse_timestamp("not a time"), None);
+    }
+
+    #[test]
+    fn leading_timestamps() {
+        assert_eq!(leading_timestamp("2024-01-02 03:04:05,123 INFO x"), Some("2024-01-02 03:04:05,123"));
+        assert_eq!(leading_timestamp("[2024-01-02T03:04:05Z INFO a] b"), Some("2024-01-02T03:04:05Z"));
+        assert_eq!(leading_timestamp("<34>Jan  2 03:04:05 host x"), Some("Jan  2 03:04:05"));
+        assert_eq!(leading_timestamp("    at Foo.bar"), None);
+    }
+
+    #[test]
+    fn normalizes_levels() {
+        assert_eq!(normalize_level("warning"), "WARN");
+        assert_eq!(normalize_level("crit"), "FATAL");
+        assert_eq!(normalize_level("err"), "ERROR");
+        assert_eq!(normalize_level("notice"), "INFO");
+        assert_eq!(normalize_level("finest"), "DEBUG");
+        assert_eq!(normalize_level(" Debug "), "DEBUG");
+    }
+}
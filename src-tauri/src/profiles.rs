@@ -0,0 +1,510 @@
+// SSH连接配置：由后端统一保存，SSH相关命令可以只传入配置id；
+// 密码和私钥口令使用主密码派生的密钥（Argon2id）以AES-256-GCM加密后保存，解锁后的密钥只保存在内存中；
+// 配置id作为附加数据参与加密，密文不能被换到其它配置上使用
+use crate::error::EasyLogError;
+use crate::{AuthMethod, SshCredentials};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use lazy_static::lazy_static;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const STORE_FILE: &str = "ssh_profiles.json";
+// 用主密码加密这段固定内容，解锁时通过解密它校验主密码是否正确
+const VERIFIER_PLAINTEXT: &[u8] = b"easylog-ssh-profiles";
+const NONCE_LEN: usize = 12;
+const SALT_LEN: usize = 16;
+
+lazy_static! {
+    static ref STORE_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+    // 解锁后的加密密钥，锁定或应用退出后失效
+    static ref MASTER_KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+    // 保证配置文件的读取-修改-写入不会交错
+    static ref STORE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// 应用启动时设置配置文件所在的目录
+pub fn init(app_dir: &Path) {
+    *STORE_PATH.lock().unwrap() = Some(app_dir.join(STORE_FILE));
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct ProfileStore {
+    salt: Option<String>,     // 派生密钥使用的盐，设置主密码之前为空
+    verifier: Option<String>, // 用于校验主密码的密文
+    profiles: Vec<StoredProfile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct StoredProfile {
+    id: String,
+    name: String,
+    host: String,
+    port: Option<u16>,
+    username: String,
+    auth_type: String, // "password"、"key"、"agent" 或 "keyboard_interactive"，与AuthMethod的auth_type一致
+    private_key_path: Option<String>,
+    secret: Option<String>, // 加密后的密码或私钥口令（base64编码的nonce + 密文）
+    #[serde(default)]
+    group: Option<String>, // 主机分组，例如 "prod-web"，可以按分组同时监控多台服务器
+    #[serde(default)]
+    jump_hosts: Vec<String>, // 依次经过的跳板机的配置id，为空时使用 ~/.ssh/config 中的ProxyJump
+}
+
+// 返回给前端的配置信息，不包含任何密文
+#[derive(Debug, Serialize, Clone)]
+pub struct SshProfile {
+    id: String,
+    name: String,
+    host: String,
+    port: Option<u16>,
+    username: String,
+    auth_type: String,
+    private_key_path: Option<String>,
+    has_secret: bool,
+    group: Option<String>,
+    jump_hosts: Vec<String>,
+}
+
+impl From<&StoredProfile> for SshProfile {
+    fn from(profile: &StoredProfile) -> Self {
+        SshProfile {
+            id: profile.id.clone(),
+            name: profile.name.clone(),
+            host: profile.host.clone(),
+            port: profile.port,
+            username: profile.username.clone(),
+            auth_type: profile.auth_type.clone(),
+            private_key_path: profile.private_key_path.clone(),
+            has_secret: profile.secret.is_some(),
+            group: profile.group.clone(),
+            jump_hosts: profile.jump_hosts.clone(),
+        }
+    }
+}
+
+// 创建或修改配置时的参数，认证字段与SshCredentials相同
+#[derive(Deserialize)]
+pub struct SshProfileInput {
+    name: String,
+    host: String,
+    port: Option<u16>,
+    username: String,
+    #[serde(flatten)]
+    auth_method: AuthMethod,
+    // 修改配置时密码或口令为空表示保留原来的值，需要清除私钥口令时设置为true
+    #[serde(default)]
+    clear_secret: bool,
+    #[serde(default)]
+    group: Option<String>,
+    #[serde(default)]
+    jump_hosts: Vec<String>, // 跳板机的配置id
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProfileStoreStatus {
+    initialized: bool, // 是否已经设置主密码
+    unlocked: bool,
+}
+
+// SSH命令的连接目标：已保存的配置id，或者完整的连接信息
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum SshTarget {
+    Profile { profile_id: String },
+    Credentials(SshCredentials),
+}
+
+impl SshTarget {
+    // 得到实际用于连接的认证信息，配置中的密文在这里解密
+    pub fn resolve(&self) -> Result<SshCredentials, EasyLogError> {
+        match self {
+            SshTarget::Credentials(credentials) => Ok(credentials.clone()),
+            SshTarget::Profile { profile_id } => {
+                let store = {
+                    let _guard = STORE_LOCK.lock().unwrap();
+                    load_store()?
+                };
+                resolve_profile(&store, profile_id, &mut Vec::new())
+            }
+        }
+    }
+}
+
+// 把配置转换为连接信息，跳板机的配置递归转换；visiting为正在转换的配置，用于发现循环引用
+fn resolve_profile(store: &ProfileStore, id: &str, visiting: &mut Vec<String>) -> Result<SshCredentials, EasyLogError> {
+    if visiting.iter().any(|v| v == id) {
+        return Err(EasyLogError::invalid("jump_hosts", format!("跳板机配置循环引用: {}", id)));
+    }
+    let profile = find_profile(store, id)?;
+    let secret = match &profile.secret {
+        Some(secret) => Some(decrypt_secret(&unlocked_key()?, &profile.id, secret)?),
+        None => None,
+    };
+
+    let auth_method = match profile.auth_type.as_str() {
+        "key" => AuthMethod::PublicKey {
+            private_key_path: profile.private_key_path.clone().unwrap_or_default(),
+            passphrase: secret,
+        },
+        "agent" => AuthMethod::Agent,
+        "keyboard_interactive" => AuthMethod::KeyboardInteractive { password: secret },
+        _ => AuthMethod::Password {
+            password: secret.unwrap_or_default(),
+        },
+    };
+
+    visiting.push(id.to_string());
+    let jump_hosts = profile
+        .jump_hosts
+        .iter()
+        .map(|jump| resolve_profile(store, jump, visiting))
+        .collect::<Result<Vec<_>, _>>()?;
+    visiting.pop();
+
+    Ok(SshCredentials {
+        host: profile.host.clone(),
+        port: profile.port,
+        username: profile.username.clone(),
+        auth_method,
+        jump_hosts,
+    })
+}
+
+// 分组中所有配置对应的连接目标，按配置名称排序
+pub fn group_targets(group: &str) -> Result<Vec<SshTarget>, EasyLogError> {
+    let mut profiles = {
+        let _guard = STORE_LOCK.lock().unwrap();
+        load_store()?.profiles
+    };
+    profiles.retain(|p| p.group.as_deref() == Some(group));
+    if profiles.is_empty() {
+        return Err(EasyLogError::invalid("group", format!("主机分组中没有SSH配置: {}", group)));
+    }
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles
+        .into_iter()
+        .map(|p| SshTarget::Profile { profile_id: p.id })
+        .collect())
+}
+
+fn store_path() -> Result<PathBuf, EasyLogError> {
+    STORE_PATH
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| EasyLogError::internal("SSH配置存储尚未初始化"))
+}
+
+fn load_store() -> Result<ProfileStore, EasyLogError> {
+    let path = store_path()?;
+    if !path.exists() {
+        return Ok(ProfileStore::default());
+    }
+    let json = std::fs::read_to_string(&path).map_err(|e| EasyLogError::io(&path, e))?;
+    serde_json::from_str(&json).map_err(|e| EasyLogError::internal(format!("SSH配置文件格式错误: {}", e)))
+}
+
+// 先写入临时文件再重命名，避免写入中断导致配置文件损坏；
+// 临时文件创建时就只有当前用户可以读写，写入的内容任何时候都不会被其它用户读到
+fn save_store(store: &ProfileStore) -> Result<(), EasyLogError> {
+    let path = store_path()?;
+    let json = serde_json::to_string_pretty(store)
+        .map_err(|e| EasyLogError::internal(format!("保存SSH配置失败: {}", e)))?;
+    let tmp_path = path.with_extension("json.tmp");
+    // 上次中断留下的临时文件的权限不一定正确，删除后重新创建
+    let _ = std::fs::remove_file(&tmp_path);
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp_path).map_err(|e| EasyLogError::io(&tmp_path, e))?;
+    file.write_all(json.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| EasyLogError::io(&tmp_path, e))?;
+    drop(file);
+
+    std::fs::rename(&tmp_path, &path).map_err(|e| EasyLogError::io(&path, e))
+}
+
+fn find_profile<'a>(store: &'a ProfileStore, id: &str) -> Result<&'a StoredProfile, EasyLogError> {
+    store
+        .profiles
+        .iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| EasyLogError::ProfileNotFound { id: id.to_string() })
+}
+
+fn derive_key(master_password: &str, salt: &[u8]) -> Result<[u8; 32], EasyLogError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(master_password.as_bytes(), salt, &mut key)
+        .map_err(|e| EasyLogError::internal(format!("派生密钥失败: {}", e)))?;
+    Ok(key)
+}
+
+// aad为附加数据：不加密但参与认证，解密时必须提供相同的内容
+fn encrypt(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Result<String, EasyLogError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| EasyLogError::internal("加密失败"))?;
+
+    let mut data = nonce.to_vec();
+    data.extend(ciphertext);
+    Ok(BASE64.encode(data))
+}
+
+fn decrypt(key: &[u8; 32], encoded: &str, aad: &[u8]) -> Result<Vec<u8>, EasyLogError> {
+    let data = BASE64.decode(encoded).map_err(|_| corrupted_secret())?;
+    if data.len() < NONCE_LEN {
+        return Err(corrupted_secret());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| EasyLogError::internal("解密失败，主密码不正确或数据已损坏"))
+}
+
+fn corrupted_secret() -> EasyLogError {
+    EasyLogError::internal("SSH配置中的密文已损坏")
+}
+
+// 配置中的密文以配置id作为附加数据
+fn decrypt_secret(key: &[u8; 32], id: &str, encoded: &str) -> Result<String, EasyLogError> {
+    String::from_utf8(decrypt(key, encoded, id.as_bytes())?).map_err(|_| corrupted_secret())
+}
+
+fn unlocked_key() -> Result<[u8; 32], EasyLogError> {
+    MASTER_KEY
+        .lock()
+        .unwrap()
+        .ok_or(EasyLogError::ProfilesLocked)
+}
+
+// 用主密码派生密钥并校验，store未设置主密码时返回None
+fn verify_master_password(store: &ProfileStore, master_password: &str) -> Result<Option<[u8; 32]>, EasyLogError> {
+    let (Some(salt), Some(verifier)) = (&store.salt, &store.verifier) else {
+        return Ok(None);
+    };
+    let salt = BASE64
+        .decode(salt)
+        .map_err(|_| EasyLogError::internal("SSH配置文件格式错误"))?;
+    let key = derive_key(master_password, &salt)?;
+    match decrypt(&key, verifier, b"") {
+        Ok(plaintext) if plaintext == VERIFIER_PLAINTEXT => Ok(Some(key)),
+        _ => Err(EasyLogError::WrongPassword),
+    }
+}
+
+// 设置新的主密码：生成新的盐和校验密文
+fn set_master_password(store: &mut ProfileStore, master_password: &str) -> Result<[u8; 32], EasyLogError> {
+    if master_password.is_empty() {
+        return Err(EasyLogError::invalid("master_password", "主密码不能为空"));
+    }
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(master_password, &salt)?;
+    store.salt = Some(BASE64.encode(salt));
+    store.verifier = Some(encrypt(&key, VERIFIER_PLAINTEXT, b"")?);
+    Ok(key)
+}
+
+fn new_profile_id() -> String {
+    format!("{:016x}", OsRng.next_u64())
+}
+
+// 把输入中的认证信息写入配置，密码和口令需要已解锁才能加密
+fn apply_input(profile: &mut StoredProfile, input: SshProfileInput) -> Result<(), EasyLogError> {
+    let (auth_type, private_key_path, secret) = match input.auth_method {
+        AuthMethod::Password { password } => ("password", None, Some(password)),
+        AuthMethod::PublicKey {
+            private_key_path,
+            passphrase,
+        } => ("key", Some(private_key_path), passphrase),
+        AuthMethod::Agent => ("agent", None, None),
+        AuthMethod::KeyboardInteractive { password } => ("keyboard_interactive", None, password),
+    };
+    let secret = secret.filter(|s| !s.is_empty());
+
+    // 认证方式改变或明确要求清除时，原来的密文不再有效
+    if profile.auth_type != auth_type || input.clear_secret {
+        profile.secret = None;
+    }
+    if let Some(secret) = secret {
+        profile.secret = Some(encrypt(&unlocked_key()?, secret.as_bytes(), profile.id.as_bytes())?);
+    }
+
+    profile.name = input.name;
+    profile.host = input.host;
+    profile.port = input.port;
+    profile.username = input.username;
+    profile.auth_type = auth_type.to_string();
+    profile.private_key_path = private_key_path;
+    profile.group = input.group.map(|g| g.trim().to_string()).filter(|g| !g.is_empty());
+    profile.jump_hosts = input.jump_hosts;
+    Ok(())
+}
+
+// 跳板机必须是已保存的其它配置；间接的循环引用在连接时发现
+fn check_jump_hosts(store: &ProfileStore, id: &str, jump_hosts: &[String]) -> Result<(), EasyLogError> {
+    for jump in jump_hosts {
+        if jump == id {
+            return Err(EasyLogError::invalid("jump_hosts", "不能把配置自己作为跳板机"));
+        }
+        find_profile(store, jump)?;
+    }
+    Ok(())
+}
+
+// 查询主密码是否已设置、当前是否已解锁
+#[tauri::command]
+pub fn ssh_profiles_status() -> Result<ProfileStoreStatus, EasyLogError> {
+    let store = {
+        let _guard = STORE_LOCK.lock().unwrap();
+        load_store()?
+    };
+    Ok(ProfileStoreStatus {
+        initialized: store.salt.is_some(),
+        unlocked: MASTER_KEY.lock().unwrap().is_some(),
+    })
+}
+
+// 使用主密码解锁；第一次调用时设置主密码
+#[tauri::command]
+pub async fn unlock_ssh_profiles(master_password: String) -> Result<(), EasyLogError> {
+    // Argon2派生密钥比较耗时，放到阻塞线程池中执行
+    tokio::task::spawn_blocking(move || {
+        let _guard = STORE_LOCK.lock().unwrap();
+        let mut store = load_store()?;
+        let key = match verify_master_password(&store, &master_password)? {
+            Some(key) => key,
+            None => {
+                info!("Initializing SSH profile store with a new master password");
+                let key = set_master_password(&mut store, &master_password)?;
+                save_store(&store)?;
+                key
+            }
+        };
+        *MASTER_KEY.lock().unwrap() = Some(key);
+        Ok(())
+    })
+    .await
+    .map_err(|e| EasyLogError::internal(format!("解锁SSH配置失败: {}", e)))?
+}
+
+// 锁定后需要重新输入主密码才能使用带密码的配置
+#[tauri::command]
+pub fn lock_ssh_profiles() {
+    *MASTER_KEY.lock().unwrap() = None;
+}
+
+// 修改主密码，所有密文使用新密钥重新加密
+#[tauri::command]
+pub async fn change_master_password(old_password: String, new_password: String) -> Result<(), EasyLogError> {
+    tokio::task::spawn_blocking(move || {
+        let _guard = STORE_LOCK.lock().unwrap();
+        let mut store = load_store()?;
+        let old_key = verify_master_password(&store, &old_password)?
+            .ok_or_else(|| EasyLogError::invalid("old_password", "尚未设置主密码"))?;
+
+        let new_key = set_master_password(&mut store, &new_password)?;
+        for profile in store.profiles.iter_mut() {
+            if let Some(secret) = &profile.secret {
+                let plaintext = decrypt(&old_key, secret, profile.id.as_bytes())?;
+                profile.secret = Some(encrypt(&new_key, &plaintext, profile.id.as_bytes())?);
+            }
+        }
+        save_store(&store)?;
+        *MASTER_KEY.lock().unwrap() = Some(new_key);
+        Ok(())
+    })
+    .await
+    .map_err(|e| EasyLogError::internal(format!("修改主密码失败: {}", e)))?
+}
+
+#[tauri::command]
+pub fn list_ssh_profiles() -> Result<Vec<SshProfile>, EasyLogError> {
+    let _guard = STORE_LOCK.lock().unwrap();
+    let mut profiles: Vec<SshProfile> = load_store()?.profiles.iter().map(SshProfile::from).collect();
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(profiles)
+}
+
+#[tauri::command]
+pub fn create_ssh_profile(profile: SshProfileInput) -> Result<SshProfile, EasyLogError> {
+    let _guard = STORE_LOCK.lock().unwrap();
+    let mut store = load_store()?;
+
+    let mut stored = StoredProfile {
+        id: new_profile_id(),
+        name: String::new(),
+        host: String::new(),
+        port: None,
+        username: String::new(),
+        auth_type: String::new(),
+        private_key_path: None,
+        secret: None,
+        group: None,
+        jump_hosts: Vec::new(),
+    };
+    check_jump_hosts(&store, &stored.id, &profile.jump_hosts)?;
+    apply_input(&mut stored, profile)?;
+    info!("Creating SSH profile {} ({})", stored.name, stored.id);
+
+    let result = SshProfile::from(&stored);
+    store.profiles.push(stored);
+    save_store(&store)?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn update_ssh_profile(id: String, profile: SshProfileInput) -> Result<SshProfile, EasyLogError> {
+    let _guard = STORE_LOCK.lock().unwrap();
+    let mut store = load_store()?;
+    check_jump_hosts(&store, &id, &profile.jump_hosts)?;
+
+    let stored = store
+        .profiles
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| EasyLogError::ProfileNotFound { id: id.clone() })?;
+    apply_input(stored, profile)?;
+    info!("Updating SSH profile {} ({})", stored.name, stored.id);
+
+    let result = SshProfile::from(&*stored);
+    save_store(&store)?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn delete_ssh_profile(id: String) -> Result<(), EasyLogError> {
+    let _guard = STORE_LOCK.lock().unwrap();
+    let mut store = load_store()?;
+
+    // 其它配置经过它连接时不能删除，否则那些配置会失去跳板机
+    if let Some(user) = store.profiles.iter().find(|p| p.jump_hosts.contains(&id)) {
+        return Err(EasyLogError::invalid("id", format!("配置正在被 {} 用作跳板机", user.name)));
+    }
+
+    let before = store.profiles.len();
+    store.profiles.retain(|p| p.id != id);
+    if store.profiles.len() == before {
+        return Err(EasyLogError::ProfileNotFound { id });
+    }
+    info!("Deleting SSH profile {}", id);
+    save_store(&store)
+}
@@ -0,0 +1,270 @@
+// 远程命令构造：所有参数都按POSIX shell规则加引号，文件路径中的引号、$()、反引号等不会被shell执行。
+// 远程功能只能使用这里列出的程序，多个程序之间只能用管道连接；需要结束的长时间运行的命令可以先输出自己的进程号
+use crate::error::EasyLogError;
+use std::fmt;
+
+// 允许在远程服务器上执行的程序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Program {
+    Cat,
+    Tail,
+    Head,
+    Find,
+    Stat, // 获取文件的inode和大小，判断实时监控的文件是否已被轮转
+    Grep,
+    Zgrep, // 搜索gzip压缩的日志
+    Rg,    // 服务器安装了ripgrep时优先使用，支持各种压缩格式
+    Wc,   // 统计文件大小和行数
+    Sort, // 对find的结果排序
+    Kill, // 结束实时监控启动的 tail -f
+}
+
+impl Program {
+    pub fn name(self) -> &'static str {
+        match self {
+            Program::Cat => "cat",
+            Program::Tail => "tail",
+            Program::Head => "head",
+            Program::Find => "find",
+            Program::Stat => "stat",
+            Program::Grep => "grep",
+            Program::Zgrep => "zgrep",
+            Program::Rg => "rg",
+            Program::Wc => "wc",
+            Program::Sort => "sort",
+            Program::Kill => "kill",
+        }
+    }
+}
+
+// 不需要加引号的字符
+fn is_safe_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "_-+=./:,@%".contains(c)
+}
+
+// 按POSIX shell规则给参数加引号：放在单引号中，参数本身的单引号写成 '\''
+pub fn quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(is_safe_char) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+struct Stage {
+    program: Program,
+    args: Vec<String>, // 已经加好引号的参数
+}
+
+// 一个远程命令，可以是用管道连接的多个程序
+pub struct RemoteCommand {
+    stages: Vec<Stage>,
+    report_pid: bool,
+}
+
+impl RemoteCommand {
+    pub fn new(program: Program) -> Self {
+        RemoteCommand {
+            stages: vec![Stage {
+                program,
+                args: Vec::new(),
+            }],
+            report_pid: false,
+        }
+    }
+
+    // 先输出一行进程号，再用exec执行命令，命令的进程号与输出的相同，之后可以用kill结束它。
+    // 用sh执行，不依赖登录shell是否支持 $$；管道中的每个程序是独立的进程，只能用于单个程序
+    pub fn with_pid(mut self) -> Self {
+        debug_assert_eq!(self.stages.len(), 1, "with_pid only supports a single program");
+        self.report_pid = true;
+        self
+    }
+
+    // 通过管道把输出交给下一个程序，之后添加的参数属于这个程序
+    pub fn pipe(mut self, program: Program) -> Self {
+        self.stages.push(Stage {
+            program,
+            args: Vec::new(),
+        });
+        self
+    }
+
+    pub fn arg(mut self, arg: impl AsRef<str>) -> Self {
+        let quoted = quote(arg.as_ref());
+        self.stages.last_mut().expect("command has at least one stage").args.push(quoted);
+        self
+    }
+
+    pub fn args<I, S>(self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        args.into_iter().fold(self, |command, arg| command.arg(arg))
+    }
+
+    // 文件路径参数：以 - 开头的相对路径前面加上 ./，避免被当作选项
+    pub fn path(self, path: &str) -> Self {
+        if path.starts_with('-') {
+            self.arg(format!("./{}", path))
+        } else {
+            self.arg(path)
+        }
+    }
+
+    fn write_stages(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        for (i, stage) in self.stages.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" | ")?;
+            }
+            f.write_str(stage.program.name())?;
+            for arg in &stage.args {
+                write!(f, " {}", arg)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RemoteCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.report_pid {
+            return self.write_stages(f);
+        }
+        let mut script = String::from("echo $$; exec ");
+        self.write_stages(&mut script)?;
+        write!(f, "sh -c {}", quote(&script))
+    }
+}
+
+// 常用的远程命令
+
+// 读取整个文件
+pub fn cat(path: &str) -> RemoteCommand {
+    RemoteCommand::new(Program::Cat).path(path)
+}
+
+// 读取文件开头的bytes个字节
+pub fn head_bytes(path: &str, bytes: usize) -> RemoteCommand {
+    RemoteCommand::new(Program::Head).arg("-c").arg(bytes.to_string()).path(path)
+}
+
+// 从第offset个字节（从0计数）开始读取，follow为true时持续读取新写入的内容；
+// 使用 -F 按文件名跟踪，文件被轮转或截断后继续读取新的文件
+pub fn tail_from(path: &str, offset: u64, follow: bool) -> RemoteCommand {
+    let command = RemoteCommand::new(Program::Tail).arg("-c").arg(format!("+{}", offset + 1));
+    let command = if follow { command.arg("-F") } else { command };
+    command.path(path)
+}
+
+// tail -F 切换到新文件或发现文件被截断时，在标准错误输出中的提示（GNU coreutils）
+pub fn tail_restarted(stderr: &str) -> bool {
+    stderr.contains("following new file") || stderr.contains("file truncated")
+}
+
+// 结束with_pid启动的远程进程
+pub fn kill(pid: u32) -> RemoteCommand {
+    RemoteCommand::new(Program::Kill).arg("-TERM").arg(pid.to_string())
+}
+
+// 解析with_pid输出的第一行
+pub fn parse_pid(line: &[u8]) -> Option<u32> {
+    std::str::from_utf8(line).ok()?.trim().parse().ok()
+}
+
+// 获取文件大小，输出的第一个字段为字节数
+pub fn file_size(path: &str) -> RemoteCommand {
+    RemoteCommand::new(Program::Wc).arg("-c").path(path)
+}
+
+// 远程文件的inode和大小，inode为空表示服务器的stat不支持 -c 格式（例如BSD）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileIdentity {
+    pub inode: Option<u64>,
+    pub size: u64,
+}
+
+// 获取文件的inode和大小，符号链接返回其指向的文件的信息，输出为 "inode 字节数"
+pub fn file_identity(path: &str) -> RemoteCommand {
+    RemoteCommand::new(Program::Stat).arg("-L").arg("-c").arg("%i %s").path(path)
+}
+
+// 解析 stat -c '%i %s' 的输出
+pub fn parse_identity(output: &str) -> Option<FileIdentity> {
+    let mut fields = output.split_whitespace().map(|field| field.parse().ok());
+    let inode = fields.next()??;
+    let size = fields.next()??;
+    Some(FileIdentity {
+        inode: Some(inode),
+        size,
+    })
+}
+
+// 解析 wc -c 的输出
+pub fn parse_size(output: &str) -> Result<u64, EasyLogError> {
+    output
+        .split_whitespace()
+        .next()
+        .and_then(|size| size.parse().ok())
+        .ok_or_else(|| EasyLogError::internal(format!("无法获取文件大小: {}", output.trim())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    // 用本地的sh解析加好引号的参数，返回shell看到的参数
+    fn shell_parse(quoted: &str) -> String {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("printf '%s' {}", quoted))
+            .output()
+            .expect("sh is available");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn safe_args_are_not_quoted() {
+        assert_eq!(quote("/var/log/app.log"), "/var/log/app.log");
+        assert_eq!(quote("-c"), "-c");
+        assert_eq!(quote("+101"), "+101");
+    }
+
+    #[test]
+    fn quotes_special_characters() {
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("my app.log"), "'my app.log'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote("$(reboot)"), "'$(reboot)'");
+        assert_eq!(quote("`reboot`"), "'`reboot`'");
+        assert_eq!(quote("a\nb"), "'a\nb'");
+    }
+
+    #[test]
+    fn shell_sees_original_arg() {
+        for arg in ["", "my app.log", "it's", "'; rm -rf / #", "$HOME", "$(id)", "`id`", "a\nb", "\\", "\"quoted\"", "*", "-n"] {
+            assert_eq!(shell_parse(&quote(arg)), arg);
+        }
+    }
+
+    #[test]
+    fn leading_dash_path_is_not_an_option() {
+        assert_eq!(cat("-rf").to_string(), "cat ./-rf");
+        assert_eq!(cat("/var/log/-x.log").to_string(), "cat /var/log/-x.log");
+    }
+
+    #[test]
+    fn with_pid_runs_the_quoted_command() {
+        let command = tail_from("/tmp/it's $(id).log", 0, false).with_pid();
+        let script = command.to_string();
+        let inner = script.strip_prefix("sh -c ").unwrap();
+        assert_eq!(shell_parse(inner), "echo $$; exec tail -c +1 '/tmp/it'\\''s $(id).log'");
+    }
+
+    #[test]
+    fn pipes_keep_args_with_their_program() {
+        let command = RemoteCommand::new(Program::Find).path("/var/log").pipe(Program::Head).arg("-50");
+        assert_eq!(command.to_string(), "find /var/log | head -50");
+    }
+}
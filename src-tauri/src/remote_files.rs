@@ -0,0 +1,435 @@
+// 远程文件浏览：通过SFTP列出任意目录的内容（大小、修改时间、权限），支持按通配符过滤文件、
+// 展开带通配符的路径，以及按主机保存常用的日志目录（收藏夹）
+use crate::error::EasyLogError;
+use crate::profiles::SshTarget;
+use crate::{ssh, SshCredentials};
+use lazy_static::lazy_static;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const FAVORITES_FILE: &str = "remote_favorites.json";
+
+lazy_static! {
+    static ref FAVORITES_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+    // 保证收藏夹文件的读取-修改-写入不会交错
+    static ref FAVORITES_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// 应用启动时设置收藏夹文件所在的目录
+pub fn init(app_dir: &Path) {
+    *FAVORITES_PATH.lock().unwrap() = Some(app_dir.join(FAVORITES_FILE));
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RemoteFileEntry {
+    name: String,
+    path: String,
+    is_dir: bool, // 指向目录的符号链接也视为目录，可以进入
+    is_symlink: bool,
+    size: Option<u64>,
+    modified: Option<u64>, // 修改时间，Unix时间戳（秒）
+    permissions: String,   // ls -l 格式，例如 "drwxr-xr-x"
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RemoteDirectoryListing {
+    path: String,           // 解析后的绝对路径
+    parent: Option<String>, // 上级目录，根目录为None
+    entries: Vec<RemoteFileEntry>,
+}
+
+// 把权限位格式化为 ls -l 的形式
+fn format_permissions(mode: u32) -> String {
+    let kind = match mode & 0o170000 {
+        0o040000 => 'd',
+        0o120000 => 'l',
+        0o010000 => 'p',
+        0o020000 => 'c',
+        0o060000 => 'b',
+        0o140000 => 's',
+        _ => '-',
+    };
+    let mut result = String::with_capacity(10);
+    result.push(kind);
+    // (读, 写, 执行, 特殊位, 特殊位且可执行时的字符, 特殊位且不可执行时的字符)
+    let groups = [
+        (0o400, 0o200, 0o100, 0o4000, 's', 'S'),
+        (0o040, 0o020, 0o010, 0o2000, 's', 'S'),
+        (0o004, 0o002, 0o001, 0o1000, 't', 'T'),
+    ];
+    for (read, write, exec, special, special_exec, special_only) in groups {
+        result.push(if mode & read != 0 { 'r' } else { '-' });
+        result.push(if mode & write != 0 { 'w' } else { '-' });
+        result.push(match (mode & exec != 0, mode & special != 0) {
+            (true, true) => special_exec,
+            (false, true) => special_only,
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    result
+}
+
+// 模式开头的单个字符元素（普通字符、? 或 [abc]、[a-z]、[!abc]）是否匹配c，返回是否匹配以及元素的长度
+fn match_element(pattern: &[char], c: char) -> (bool, usize) {
+    match pattern[0] {
+        '?' => (true, 1),
+        '[' => {
+            // 没有对应的 ] 时按普通字符处理
+            let Some(end) = pattern.iter().skip(2).position(|&p| p == ']').map(|i| i + 2) else {
+                return (c == '[', 1);
+            };
+            let (negated, set) = match pattern[1] {
+                '!' | '^' => (true, &pattern[2..end]),
+                _ => (false, &pattern[1..end]),
+            };
+            let mut matched = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == '-' {
+                    matched |= set[i] <= c && c <= set[i + 2];
+                    i += 3;
+                } else {
+                    matched |= set[i] == c;
+                    i += 1;
+                }
+            }
+            (matched != negated, end + 1)
+        }
+        p => (p == c, 1),
+    }
+}
+
+// 通配符匹配，支持 *、? 和 [abc]、[a-z]、[!abc]。匹配失败时只回退到最近的一个 *，
+// 时间复杂度为 O(模式长度 × 文本长度)，*a*a*a*b 这样的模式不会出现指数级的回溯
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // 最近的 * 在模式中的位置，以及它之后的内容从文本的哪个位置开始匹配
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if pattern.get(p) == Some(&'*') {
+            star = Some((p, t));
+            p += 1;
+            continue;
+        }
+        if p < pattern.len() {
+            let (matched, len) = match_element(&pattern[p..], text[t]);
+            if matched {
+                p += len;
+                t += 1;
+                continue;
+            }
+        }
+        match star {
+            // 让 * 多匹配一个字符后重新尝试
+            Some((star_p, star_t)) => {
+                star = Some((star_p, star_t + 1));
+                p = star_p + 1;
+                t = star_t + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+// 过滤条件可以包含多个用逗号或空格分隔的通配符，匹配任意一个即可
+fn matches_filter(patterns: &[Vec<char>], name: &str) -> bool {
+    let name: Vec<char> = name.chars().collect();
+    patterns.is_empty() || patterns.iter().any(|pattern| glob_match(pattern, &name))
+}
+
+fn parse_filter(filter: Option<&str>) -> Vec<Vec<char>> {
+    filter
+        .unwrap_or("")
+        .split([',', ' '])
+        .filter(|p| !p.is_empty())
+        .map(|p| p.chars().collect())
+        .collect()
+}
+
+fn list_directory(credentials: &SshTarget, path: &str, filter: Option<&str>) -> Result<RemoteDirectoryListing, EasyLogError> {
+    let credentials = credentials.resolve()?;
+    let sftp = ssh::sftp(&credentials)?;
+    let path = sftp.realpath(Path::new(if path.is_empty() { "." } else { path }))?;
+    info!("Listing remote directory {} on {}", path.display(), credentials.host);
+
+    let patterns = parse_filter(filter);
+    let mut entries: Vec<RemoteFileEntry> = sftp
+        .readdir(&path)?
+        .into_iter()
+        .filter_map(|(entry_path, stat)| {
+            let name = entry_path.file_name()?.to_string_lossy().into_owned();
+            let mode = stat.perm.unwrap_or(0);
+            let is_symlink = mode & 0o170000 == 0o120000;
+            // 符号链接需要再获取一次指向的文件的信息，链接已失效时按普通文件显示
+            let target = if is_symlink { sftp.stat(&entry_path).ok() } else { None };
+            let is_dir = target.as_ref().unwrap_or(&stat).is_dir();
+            // 目录总是显示，便于继续浏览；过滤条件只作用于文件
+            if !is_dir && !matches_filter(&patterns, &name) {
+                return None;
+            }
+            let size = if is_dir { None } else { target.as_ref().unwrap_or(&stat).size };
+            Some(RemoteFileEntry {
+                name,
+                path: entry_path.to_string_lossy().into_owned(),
+                is_dir,
+                is_symlink,
+                size,
+                modified: stat.mtime,
+                permissions: format_permissions(mode),
+            })
+        })
+        .collect();
+    // 目录在前，同类按名称排序
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+
+    Ok(RemoteDirectoryListing {
+        path: path.to_string_lossy().into_owned(),
+        parent: path.parent().map(|p| p.to_string_lossy().into_owned()),
+        entries,
+    })
+}
+
+// 列出远程目录的内容，path为空时列出登录用户的主目录；filter为文件名通配符，例如 "*.log,*.gz"
+#[tauri::command]
+pub async fn list_remote_directory(
+    credentials: SshTarget,
+    path: Option<String>,
+    filter: Option<String>,
+) -> Result<RemoteDirectoryListing, EasyLogError> {
+    tokio::task::spawn_blocking(move || list_directory(&credentials, path.as_deref().unwrap_or(""), filter.as_deref()))
+        .await
+        .map_err(|e| EasyLogError::internal(format!("读取远程目录失败: {}", e)))?
+}
+
+fn has_wildcard(component: &str) -> bool {
+    component.contains(['*', '?', '['])
+}
+
+// 在远程服务器上展开路径模式，例如 /var/log/app-*/service.log；每一段都可以包含通配符，
+// 中间的部分只匹配目录，最后一部分只匹配文件。和shell一样，通配符不匹配以.开头的名称
+pub fn expand_path_pattern(credentials: &SshCredentials, pattern: &str) -> Result<Vec<String>, EasyLogError> {
+    if !has_wildcard(pattern) {
+        return Ok(vec![pattern.to_string()]);
+    }
+    let sftp = ssh::sftp(credentials)?;
+    let absolute = pattern.starts_with('/');
+    let components: Vec<&str> = pattern.split('/').filter(|c| !c.is_empty()).collect();
+    let mut candidates = vec![PathBuf::from(if absolute { "/" } else { "." })];
+
+    for (i, component) in components.iter().enumerate() {
+        let last = i + 1 == components.len();
+        let mut next = Vec::new();
+        for dir in &candidates {
+            if !has_wildcard(component) {
+                let path = dir.join(component);
+                // 通配符之后的固定部分需要确认存在
+                if !last || sftp.stat(&path).is_ok() {
+                    next.push(path);
+                }
+                continue;
+            }
+            let glob: Vec<char> = component.chars().collect();
+            // 目录不存在或没有权限时跳过
+            let Ok(entries) = sftp.readdir(dir) else {
+                continue;
+            };
+            for (path, stat) in entries {
+                let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+                    continue;
+                };
+                if name.starts_with('.') && !component.starts_with('.') {
+                    continue;
+                }
+                if !glob_match(&glob, &name.chars().collect::<Vec<char>>()) {
+                    continue;
+                }
+                let is_dir = if stat.perm.unwrap_or(0) & 0o170000 == 0o120000 {
+                    sftp.stat(&path).map(|target| target.is_dir()).unwrap_or(false)
+                } else {
+                    stat.is_dir()
+                };
+                if is_dir != last {
+                    next.push(path);
+                }
+            }
+        }
+        candidates = next;
+    }
+
+    let mut paths: Vec<String> = candidates
+        .into_iter()
+        .map(|path| match path.strip_prefix(".") {
+            Ok(relative) if !absolute => relative.to_string_lossy().into_owned(),
+            _ => path.to_string_lossy().into_owned(),
+        })
+        .collect();
+    paths.sort();
+    if paths.is_empty() {
+        return Err(EasyLogError::not_found(pattern));
+    }
+    Ok(paths)
+}
+
+// 收藏夹按主机保存，键为 host:port
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct FavoriteStore {
+    hosts: HashMap<String, Vec<String>>,
+}
+
+fn host_key(host: &str, port: Option<u16>) -> String {
+    format!("{}:{}", host, port.unwrap_or(22))
+}
+
+fn favorites_path() -> Result<PathBuf, EasyLogError> {
+    FAVORITES_PATH
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| EasyLogError::internal("收藏夹尚未初始化"))
+}
+
+fn load_favorites() -> Result<FavoriteStore, EasyLogError> {
+    let path = favorites_path()?;
+    if !path.exists() {
+        return Ok(FavoriteStore::default());
+    }
+    let json = std::fs::read_to_string(&path).map_err(|e| EasyLogError::io(&path, e))?;
+    serde_json::from_str(&json).map_err(|e| EasyLogError::internal(format!("收藏夹文件格式错误: {}", e)))
+}
+
+fn save_favorites(store: &FavoriteStore) -> Result<(), EasyLogError> {
+    let path = favorites_path()?;
+    let json = serde_json::to_string_pretty(store)
+        .map_err(|e| EasyLogError::internal(format!("保存收藏夹失败: {}", e)))?;
+    std::fs::write(&path, json).map_err(|e| EasyLogError::io(&path, e))
+}
+
+// 读取某个主机收藏的目录，供其它功能（例如查找日志文件）使用
+pub fn favorite_dirs(host: &str, port: Option<u16>) -> Vec<String> {
+    let _guard = FAVORITES_LOCK.lock().unwrap();
+    load_favorites()
+        .map(|mut store| store.hosts.remove(&host_key(host, port)).unwrap_or_default())
+        .unwrap_or_default()
+}
+
+// 修改收藏夹，返回该主机修改后的收藏目录
+fn update_favorites(host: &str, port: Option<u16>, update: impl FnOnce(&mut Vec<String>)) -> Result<Vec<String>, EasyLogError> {
+    let _guard = FAVORITES_LOCK.lock().unwrap();
+    let mut store = load_favorites()?;
+    let key = host_key(host, port);
+    let dirs = store.hosts.entry(key.clone()).or_default();
+    update(dirs);
+    let result = dirs.clone();
+    if result.is_empty() {
+        store.hosts.remove(&key);
+    }
+    save_favorites(&store)?;
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn list_remote_favorites(host: String, port: Option<u16>) -> Vec<String> {
+    favorite_dirs(&host, port)
+}
+
+#[tauri::command]
+pub fn add_remote_favorite(host: String, port: Option<u16>, path: String) -> Result<Vec<String>, EasyLogError> {
+    let path = path.trim().to_string();
+    if path.is_empty() {
+        return Err(EasyLogError::invalid("path", "目录不能为空"));
+    }
+    info!("Adding favourite directory {} for {}", path, host_key(&host, port));
+    update_favorites(&host, port, |dirs| {
+        if !dirs.contains(&path) {
+            dirs.push(path);
+        }
+    })
+}
+
+#[tauri::command]
+pub fn remove_remote_favorite(host: String, port: Option<u16>, path: String) -> Result<Vec<String>, EasyLogError> {
+    info!("Removing favourite directory {} for {}", path, host_key(&host, port));
+    update_favorites(&host, port, |dirs| dirs.retain(|dir| dir != &path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        glob_match(&pattern, &text)
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(glob("*.log", "app.log"));
+        assert!(glob("*.log", ".log"));
+        assert!(!glob("*.log", "app.log.1"));
+        assert!(glob("app.log.*", "app.log.1"));
+        assert!(glob("app-?.log", "app-1.log"));
+        assert!(!glob("app-?.log", "app-10.log"));
+        assert!(glob("*", ""));
+        assert!(!glob("?", ""));
+        assert!(glob("**a**", "xay"));
+        assert!(glob("日志*", "日志-2024.txt"));
+    }
+
+    #[test]
+    fn glob_character_classes() {
+        assert!(glob("app.[0-9]", "app.7"));
+        assert!(!glob("app.[0-9]", "app.x"));
+        assert!(glob("app.[!0-9]", "app.x"));
+        assert!(glob("app.[^0-9]", "app.x"));
+        assert!(!glob("app.[!0-9]", "app.3"));
+        assert!(glob("[abc]*.log", "b-service.log"));
+        assert!(glob("[]x]", "]"));
+        assert!(glob("*.[gx]z", "a.xz"));
+        // 没有对应的 ] 时按普通字符处理
+        assert!(glob("app[", "app["));
+        assert!(!glob("app[", "appx"));
+    }
+
+    #[test]
+    fn glob_does_not_backtrack_exponentially() {
+        let name = "a".repeat(5_000);
+        assert!(!glob("*a*a*a*a*a*a*a*a*b", &name));
+        assert!(glob("*a*a*a*a*a*a*a*a*", &name));
+    }
+
+    #[test]
+    fn permissions_like_ls() {
+        assert_eq!(format_permissions(0o100644), "-rw-r--r--");
+        assert_eq!(format_permissions(0o040755), "drwxr-xr-x");
+        assert_eq!(format_permissions(0o120777), "lrwxrwxrwx");
+        assert_eq!(format_permissions(0o104755), "-rwsr-xr-x");
+        assert_eq!(format_permissions(0o102644), "-rw-r-Sr--");
+        assert_eq!(format_permissions(0o041777), "drwxrwxrwt");
+        assert_eq!(format_permissions(0o041776), "drwxrwxrwT");
+        assert_eq!(format_permissions(0o010600), "prw-------");
+        assert_eq!(format_permissions(0o140755), "srwxr-xr-x");
+        assert_eq!(format_permissions(0o020620), "crw--w----");
+        assert_eq!(format_permissions(0o060660), "brw-rw----");
+    }
+
+    #[test]
+    fn filters() {
+        let patterns = parse_filter(Some("*.log, *.gz  app-*"));
+        assert_eq!(patterns.len(), 3);
+        assert!(matches_filter(&patterns, "error.log"));
+        assert!(matches_filter(&patterns, "error.log.2.gz"));
+        assert!(matches_filter(&patterns, "app-1.txt"));
+        assert!(!matches_filter(&patterns, "notes.txt"));
+
+        // 没有过滤条件时显示所有文件
+        assert!(parse_filter(None).is_empty());
+        assert!(parse_filter(Some(" , ")).is_empty());
+        assert!(matches_filter(&parse_filter(None), "anything"));
+    }
+}
@@ -0,0 +1,237 @@
+// 轮转日志组：把 app.log、app.log.1、app.log.2.gz 等文件按时间顺序拼接成一个虚拟文件读取
+use crate::error::EasyLogError;
+use crate::file_index::ReadRange;
+use crate::{read_file_window, FileReadOptions};
+use log::info;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RotationGroupOptions {
+    path: String,             // 组内任意一个文件的路径，例如 app.log 或 app.log.2.gz
+    start_line: Option<usize>, // 可选参数，虚拟文件中的起始行（从0计数）
+    line_count: Option<usize>, // 可选参数，读取的行数，为空时读取到末尾
+    encoding: Option<String>, // 可选参数，指定编码，为空或"auto"时每个文件单独识别
+}
+
+// 组内的一个文件在虚拟文件中的位置
+#[derive(Debug, Serialize, Clone)]
+pub struct RotationSegment {
+    path: String,
+    start_line: usize,
+    total_lines: usize,
+    compressed: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RotationGroupContent {
+    content: String,
+    total_lines: usize,
+    start_line: usize,
+    segments: Vec<RotationSegment>,
+}
+
+// 去掉轮转后缀，得到组的基础文件名：app.log.2.gz -> app.log
+fn base_name(file_name: &str) -> &str {
+    let mut name = file_name;
+    for ext in [".gz", ".zst", ".bz2", ".xz"] {
+        if let Some(stripped) = name.strip_suffix(ext) {
+            name = stripped;
+            break;
+        }
+    }
+    match name.rsplit_once(['.', '-']) {
+        Some((base, suffix)) if !suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_digit()) => base,
+        _ => name,
+    }
+}
+
+// 轮转后缀中的数字，app.log.3.gz -> 3，日期后缀 app.log-20240101 -> 20240101
+fn rotation_number(file_name: &str, base: &str) -> Option<u64> {
+    let suffix = file_name.strip_prefix(base)?;
+    let suffix = suffix.strip_prefix(['.', '-'])?;
+    let digits: String = suffix.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.is_empty() {
+        return None;
+    }
+    let rest = &suffix[digits.len()..];
+    if !rest.is_empty() && !crate::compression::is_compressed_path(rest) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+// 查找组内的所有文件，按从旧到新的顺序排列
+fn find_group_members(path: &Path) -> Result<Vec<PathBuf>, EasyLogError> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| EasyLogError::invalid("path", format!("Invalid file path: {}", path.display())))?;
+    let base = base_name(file_name);
+
+    let entries = std::fs::read_dir(dir).map_err(|e| EasyLogError::io(dir, e))?;
+    let mut current = None;
+    let mut rotated: Vec<(PathBuf, u64, Option<SystemTime>)> = Vec::new();
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == base {
+            current = Some(entry.path());
+        } else if let Some(number) = rotation_number(&name, base) {
+            let modified = entry.metadata().and_then(|m| m.modified()).ok();
+            rotated.push((entry.path(), number, modified));
+        }
+    }
+
+    // 数字后缀越大越旧（logrotate的编号方式）；日期后缀数字越大越新，
+    // 两种情况都以修改时间为准排序，修改时间相同时再按编号从大到小
+    rotated.sort_by(|a, b| a.2.cmp(&b.2).then(b.1.cmp(&a.1)));
+
+    let mut members: Vec<PathBuf> = rotated.into_iter().map(|(p, _, _)| p).collect();
+    members.extend(current);
+    Ok(members)
+}
+
+fn read_group(options: RotationGroupOptions) -> Result<RotationGroupContent, EasyLogError> {
+    info!("Reading rotation group with options: {:?}", options);
+
+    let members = find_group_members(Path::new(&options.path))?;
+    if members.is_empty() {
+        return Err(EasyLogError::not_found(&options.path));
+    }
+
+    // 先统计每个文件的行数，确定它们在虚拟文件中的位置
+    let mut segments = Vec::with_capacity(members.len());
+    let mut total_lines = 0;
+    for member in &members {
+        let member_path = member.to_string_lossy().into_owned();
+        let summary = read_file_window(FileReadOptions {
+            path: member_path.clone(),
+            max_lines: None,
+            range: ReadRange {
+                line_count: Some(0),
+                ..Default::default()
+            },
+            encoding: options.encoding.clone(),
+            parse: None,
+            record_start: None,
+        })?;
+        segments.push(RotationSegment {
+            compressed: crate::compression::is_compressed_path(&member_path),
+            path: member_path,
+            start_line: total_lines,
+            total_lines: summary.total_lines,
+        });
+        total_lines += summary.total_lines;
+    }
+
+    // 把请求的行区间映射到各个文件上
+    let start_line = options.start_line.unwrap_or(0);
+    let end_line = options
+        .line_count
+        .map(|count| start_line.saturating_add(count).min(total_lines))
+        .unwrap_or(total_lines);
+    let mut content = String::new();
+
+    for segment in &segments {
+        let segment_end = segment.start_line + segment.total_lines;
+        if segment_end <= start_line || segment.start_line >= end_line {
+            continue;
+        }
+        let local_start = start_line.saturating_sub(segment.start_line);
+        let local_end = end_line.min(segment_end) - segment.start_line;
+        let window = read_file_window(FileReadOptions {
+            path: segment.path.clone(),
+            max_lines: None,
+            range: ReadRange {
+                start_line: Some(local_start),
+                line_count: Some(local_end - local_start),
+                ..Default::default()
+            },
+            encoding: options.encoding.clone(),
+            parse: None,
+            record_start: None,
+        })?;
+        content.push_str(&window.content);
+    }
+
+    Ok(RotationGroupContent {
+        content,
+        total_lines,
+        start_line,
+        segments,
+    })
+}
+
+// 读取轮转日志组，返回按时间顺序拼接后的内容
+#[tauri::command]
+pub async fn read_rotation_group(options: RotationGroupOptions) -> Result<RotationGroupContent, EasyLogError> {
+    tokio::task::spawn_blocking(move || read_group(options))
+        .await
+        .map_err(|e| EasyLogError::internal(format!("Failed to read rotation group: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn base_name_strips_rotation_suffixes() {
+        assert_eq!(base_name("app.log"), "app.log");
+        assert_eq!(base_name("app.log.1"), "app.log");
+        assert_eq!(base_name("app.log.2.gz"), "app.log");
+        assert_eq!(base_name("app.log-20240101"), "app.log");
+        assert_eq!(base_name("app.log-20240101.zst"), "app.log");
+        assert_eq!(base_name("app.log.gz"), "app.log");
+        // 非数字后缀不是轮转编号
+        assert_eq!(base_name("app.log.old"), "app.log.old");
+    }
+
+    #[test]
+    fn rotation_number_parses_suffix() {
+        assert_eq!(rotation_number("app.log.1", "app.log"), Some(1));
+        assert_eq!(rotation_number("app.log.12.gz", "app.log"), Some(12));
+        assert_eq!(rotation_number("app.log-20240101", "app.log"), Some(20240101));
+        assert_eq!(rotation_number("app.log", "app.log"), None);
+        assert_eq!(rotation_number("app.log.1.bak", "app.log"), None);
+        assert_eq!(rotation_number("app.logx.1", "app.log"), None);
+        assert_eq!(rotation_number("other.log.1", "app.log"), None);
+    }
+
+    #[test]
+    fn group_members_are_ordered_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        // 数字越大越旧
+        for (name, age) in [("app.log", 0), ("app.log.1", 1), ("app.log.2.gz", 2), ("app.log.3.gz", 3), ("other.log.1", 4)] {
+            let file = File::create(dir.path().join(name)).unwrap();
+            file.set_modified(base - std::time::Duration::from_secs(age * 60)).unwrap();
+        }
+
+        let members = find_group_members(&dir.path().join("app.log.2.gz")).unwrap();
+        let names: Vec<_> = members
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["app.log.3.gz", "app.log.2.gz", "app.log.1", "app.log"]);
+    }
+
+    #[test]
+    fn equal_mtimes_fall_back_to_number() {
+        let dir = tempfile::tempdir().unwrap();
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        for name in ["app.log.1", "app.log.2", "app.log.10"] {
+            File::create(dir.path().join(name)).unwrap().set_modified(modified).unwrap();
+        }
+
+        let members = find_group_members(&dir.path().join("app.log.1")).unwrap();
+        let names: Vec<_> = members
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["app.log.10", "app.log.2", "app.log.1"]);
+    }
+}
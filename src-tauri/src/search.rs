@@ -0,0 +1,595 @@
+// 日志搜索：支持本地和远程日志，支持普通文本、正则、大小写敏感和全词匹配，
+// 搜索结果分批通过 search-log-results 事件发送给前端，大文件不需要等到全部扫描完成。
+// 远程日志在服务器上用 rg/grep/zgrep 搜索，只传输匹配行和上下文
+use crate::encoding::{self, DETECT_SAMPLE_SIZE};
+use crate::error::EasyLogError;
+use crate::file_index::LineTerminator;
+use crate::profiles::SshTarget;
+use crate::remote_command::{self, Program, RemoteCommand};
+use crate::{compression, ssh, SshCredentials};
+use encoding_rs::{Encoding, UTF_8};
+use log::{error, info};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+// 每批最多发送的匹配数
+const BATCH_SIZE: usize = 200;
+// 距离上次发送超过这个时间就发送一批，保证前端能尽快看到结果
+const BATCH_INTERVAL: Duration = Duration::from_millis(250);
+// 默认最多返回的匹配数
+const DEFAULT_MAX_RESULTS: usize = 10_000;
+
+// 日志来源：本地文件或SSH远程文件
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum LogSource {
+    #[serde(rename = "local")]
+    Local { path: String },
+    #[serde(rename = "remote")]
+    Remote { credentials: SshTarget, path: String },
+}
+
+impl LogSource {
+    pub fn path(&self) -> &str {
+        match self {
+            LogSource::Local { path } => path,
+            LogSource::Remote { path, .. } => path,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchOptions {
+    pattern: String,
+    #[serde(default)]
+    is_regex: bool,
+    #[serde(default)]
+    case_sensitive: bool,
+    #[serde(default)]
+    whole_word: bool,
+    context_lines: Option<usize>, // 可选参数，匹配行前后附带的上下文行数
+    context_before: Option<usize>, // 可选参数，单独指定匹配行之前的上下文行数（grep -B）
+    context_after: Option<usize>,  // 可选参数，单独指定匹配行之后的上下文行数（grep -A）
+    max_results: Option<usize>,   // 可选参数，最多返回的匹配数
+    encoding: Option<String>,     // 可选参数，指定日志编码
+    search_id: Option<String>,    // 可选参数，用于区分事件属于哪一次搜索
+}
+
+// 匹配内容在行内的字节区间 [start, end)
+#[derive(Debug, Serialize, Clone)]
+pub struct MatchSpan {
+    start: usize,
+    end: usize,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchMatch {
+    line_number: usize, // 从1开始的行号
+    line: String,
+    spans: Vec<MatchSpan>,
+    context_before: Vec<String>,
+    context_after: Vec<String>,
+}
+
+// 通过事件分批发送的搜索结果
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchResultBatch {
+    search_id: String,
+    matches: Vec<SearchMatch>,
+    scanned_lines: usize,
+    done: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SearchSummary {
+    search_id: String,
+    total_matches: usize,
+    scanned_lines: usize,
+    truncated: bool, // 是否还有超过max_results的匹配没有返回
+}
+
+// 根据搜索选项构建正则表达式
+pub fn build_matcher(pattern: &str, is_regex: bool, case_sensitive: bool, whole_word: bool) -> Result<Regex, EasyLogError> {
+    if pattern.is_empty() {
+        return Err(EasyLogError::invalid("pattern", "搜索内容不能为空"));
+    }
+
+    let mut expr = if is_regex { pattern.to_string() } else { regex::escape(pattern) };
+    if whole_word {
+        expr = format!(r"\b(?:{})\b", expr);
+    }
+
+    RegexBuilder::new(&expr)
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| EasyLogError::invalid("pattern", e))
+}
+
+// 逐行匹配并收集上下文，攒够一批后交给emit发送
+struct Searcher<F: FnMut(Vec<SearchMatch>, usize)> {
+    matcher: Regex,
+    context_before: usize,
+    context_after: usize,
+    max_results: usize,
+    before: VecDeque<(usize, String)>, // (行号, 内容)
+    // 还在等待后续上下文的匹配
+    waiting: VecDeque<SearchMatch>,
+    ready: Vec<SearchMatch>,
+    total_matches: usize,
+    // 已返回max_results个匹配之后又遇到了匹配行
+    truncated: bool,
+    scanned_lines: usize,
+    last_emit: Instant,
+    emit: F,
+}
+
+impl<F: FnMut(Vec<SearchMatch>, usize)> Searcher<F> {
+    // 处理下一行，返回false表示已达到最大结果数
+    fn push_line(&mut self, line: String) -> bool {
+        let spans = Some(self.find_spans(&line)).filter(|spans| !spans.is_empty());
+        self.push_numbered(self.scanned_lines + 1, line, spans)
+    }
+
+    fn find_spans(&self, line: &str) -> Vec<MatchSpan> {
+        self.matcher
+            .find_iter(line)
+            .filter(|m| !m.is_empty())
+            .map(|m| MatchSpan { start: m.start(), end: m.end() })
+            .collect()
+    }
+
+    // 处理指定行号的一行，spans为Some表示这一行是匹配行。
+    // 服务器端grep只返回匹配行和上下文，行号不连续时，前面的行不再作为后面匹配的上下文
+    fn push_numbered(&mut self, line_number: usize, line: String, spans: Option<Vec<MatchSpan>>) -> bool {
+        self.scanned_lines = line_number;
+
+        while self
+            .before
+            .front()
+            .is_some_and(|(n, _)| n + self.context_before < line_number)
+        {
+            self.before.pop_front();
+        }
+        while self
+            .waiting
+            .front()
+            .is_some_and(|m| m.line_number + self.context_after < line_number)
+        {
+            let m = self.waiting.pop_front().unwrap();
+            self.ready.push(m);
+        }
+
+        // 先把这一行作为后续上下文补充给之前的匹配
+        for waiting in self.waiting.iter_mut() {
+            waiting.context_after.push(line.clone());
+        }
+        while self
+            .waiting
+            .front()
+            .is_some_and(|m| m.context_after.len() >= self.context_after)
+        {
+            let m = self.waiting.pop_front().unwrap();
+            self.ready.push(m);
+        }
+
+        // 已经返回了max_results个匹配时，再遇到的匹配只说明结果被截断了
+        if spans.is_some() && self.total_matches >= self.max_results {
+            self.truncated = true;
+        } else if let Some(spans) = spans {
+            self.total_matches += 1;
+            let m = SearchMatch {
+                line_number,
+                line: line.clone(),
+                spans,
+                context_before: self.before.iter().map(|(_, line)| line.clone()).collect(),
+                context_after: Vec::new(),
+            };
+            if self.context_after == 0 {
+                self.ready.push(m);
+            } else {
+                self.waiting.push_back(m);
+            }
+        }
+
+        if self.context_before > 0 {
+            if self.before.len() == self.context_before {
+                self.before.pop_front();
+            }
+            self.before.push_back((line_number, line));
+        }
+
+        if self.ready.len() >= BATCH_SIZE || self.last_emit.elapsed() >= BATCH_INTERVAL {
+            self.flush();
+        }
+
+        // 确认还有更多匹配后，等最后一个匹配的上下文收集完再结束
+        !self.truncated || !self.waiting.is_empty()
+    }
+
+    fn flush(&mut self) {
+        let batch = std::mem::take(&mut self.ready);
+        (self.emit)(batch, self.scanned_lines);
+        self.last_emit = Instant::now();
+    }
+
+    // 文件结束时，把还在等待上下文的匹配也发送出去
+    fn finish(&mut self) {
+        self.ready.extend(self.waiting.drain(..));
+        self.flush();
+    }
+}
+
+// 逐行读取本地文件（压缩文件先解压），按识别出的编码解码
+fn for_each_local_line<F: FnMut(String) -> bool>(path: &str, encoding: Option<&str>, mut on_line: F) -> Result<(), EasyLogError> {
+    let path = std::path::Path::new(path);
+    if !path.exists() {
+        return Err(EasyLogError::not_found(path));
+    }
+    let path = compression::materialize(path).map_err(|e| EasyLogError::io(path, e))?;
+    let mut file = File::open(&path).map_err(|e| EasyLogError::io(&path, e))?;
+
+    let mut sample = Vec::new();
+    (&mut file)
+        .take(DETECT_SAMPLE_SIZE as u64)
+        .read_to_end(&mut sample)
+        .map_err(|e| EasyLogError::io(&path, e))?;
+    let detected = encoding::resolve_encoding(encoding, &sample)?;
+    let terminator = LineTerminator::for_encoding(detected.encoding);
+
+    let file = File::open(&path).map_err(|e| EasyLogError::io(&path, e))?;
+    let mut reader = BufReader::new(file);
+    let mut line = Vec::new();
+    let mut first = true;
+
+    loop {
+        line.clear();
+        let n = terminator
+            .read_line(&mut reader, &mut line)
+            .map_err(|e| EasyLogError::io(&path, e))?;
+        if n == 0 {
+            break;
+        }
+        let bytes = terminator.trim(&line);
+        let bytes = if first { &bytes[detected.bom_len.min(bytes.len())..] } else { bytes };
+        first = false;
+        if !on_line(encoding::decode_lossy(detected.encoding, bytes)) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn for_each_remote_line<F: FnMut(String) -> bool>(
+    credentials: &SshCredentials,
+    path: &str,
+    encoding: Option<&str>,
+    on_line: F,
+) -> Result<(), EasyLogError> {
+    ssh::exec_lines(credentials, &remote_command::cat(path), encoding, on_line).map(|_| ())
+}
+
+// 正则是否可以原样交给 grep -E：只使用扩展正则（ERE）中与本地正则语法含义相同的部分。
+// \d、\w等转义、(?i)等内联选项、非贪婪量词以及字符类中的转义在ERE中没有或含义不同
+fn is_ere_compatible(pattern: &str) -> bool {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut in_class = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if in_class {
+            match c {
+                '\\' => return false,
+                '[' if next != Some(':') => return false,
+                '[' => {
+                    // POSIX字符类 [:alpha:]
+                    match chars[i..].iter().collect::<String>().find(":]") {
+                        Some(end) => i += end + 1,
+                        None => return false,
+                    }
+                }
+                '&' | '-' | '~' if next == Some(c) => return false,
+                ']' => in_class = false,
+                _ => {}
+            }
+        } else {
+            match c {
+                '\\' if next.is_some_and(|n| ".[]{}()\\*+?^$|".contains(n)) => i += 1,
+                '\\' => return false,
+                '(' if next == Some('?') => return false,
+                '*' | '+' | '?' | '}' if matches!(next, Some('?' | '+')) => return false,
+                '[' => {
+                    in_class = true;
+                    // 开头的 ^ 和紧跟其后的 ] 属于字符类本身
+                    if chars.get(i + 1) == Some(&'^') {
+                        i += 1;
+                    }
+                    if chars.get(i + 1) == Some(&']') {
+                        i += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+        i += 1;
+    }
+    !in_class
+}
+
+// 远程日志的编码：调用方没有指定时，读取文件开头的一段内容识别，压缩文件解压后再识别
+fn remote_encoding(credentials: &SshCredentials, path: &str, label: Option<&str>) -> Result<&'static Encoding, EasyLogError> {
+    if !encoding::is_auto(label) {
+        return Ok(encoding::resolve_encoding(label, &[])?.encoding);
+    }
+
+    // 压缩文件不能只读取开头的字节，解压出足够的内容后关闭通道
+    let command = if compression::is_compressed_path(path) {
+        remote_command::cat(path)
+    } else {
+        remote_command::head_bytes(path, DETECT_SAMPLE_SIZE)
+    };
+    let read_failed = |e: std::io::Error| EasyLogError::RemoteCommand {
+        host: credentials.host.clone(),
+        reason: format!("读取文件开头失败: {}", e),
+    };
+    let mut channel = ssh::exec(credentials, &command)?;
+    let mut sample = Vec::new();
+    {
+        let (reader, _) = compression::decompress_stream(&mut channel).map_err(read_failed)?;
+        reader
+            .take(DETECT_SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)
+            .map_err(read_failed)?;
+    }
+    channel.close();
+    channel.wait_close().ok();
+    Ok(encoding::detect_encoding(&sample).encoding)
+}
+
+// 服务器上的rg/grep按字节匹配，只有内容与模式的字节表示一致时结果才正确：
+// UTF-8的日志可以直接匹配；单字节编码（例如windows-1252）只在模式全是ASCII字符时一致；
+// UTF-16以及GB18030等多字节编码的字节与UTF-8的模式不同，续字节还可能落在ASCII范围内造成误匹配
+fn grep_matches_encoding(encoding: &'static Encoding, pattern: &str) -> bool {
+    encoding == UTF_8 || (encoding.is_single_byte() && pattern.is_ascii())
+}
+
+// 选择服务器端搜索使用的程序：优先使用ripgrep，没有时普通文件用grep、gzip文件用zgrep。
+// rg/grep无法按日志的编码匹配、grep不支持的正则以及没有ripgrep时的其它压缩格式返回None，
+// 改为读取整个文件在本地搜索
+fn remote_grep_program(credentials: &SshCredentials, path: &str, options: &SearchOptions, encoding: &'static Encoding) -> Option<Program> {
+    if !grep_matches_encoding(encoding, &options.pattern) {
+        return None;
+    }
+    if ssh::has_program(credentials, Program::Rg) {
+        return Some(Program::Rg);
+    }
+    if options.is_regex && !is_ere_compatible(&options.pattern) {
+        return None;
+    }
+    if !compression::is_compressed_path(path) {
+        Some(Program::Grep)
+    } else if path.ends_with(".gz") {
+        Some(Program::Zgrep)
+    } else {
+        None
+    }
+}
+
+// 构建服务器端搜索命令。rg的正则语法与本地搜索相同，grep/zgrep使用扩展正则（ERE）。
+// 多取一个匹配，用来判断结果是否被截断
+fn remote_grep_command(program: Program, path: &str, options: &SearchOptions, before: usize, after: usize, max_results: usize) -> RemoteCommand {
+    let mut command = RemoteCommand::new(program).arg("-n").arg("-a");
+    if program == Program::Rg {
+        command = command.args(["--no-config", "--no-heading", "--no-filename", "--color", "never", "-z"]);
+    }
+    if !options.is_regex {
+        command = command.arg("-F");
+    } else if program != Program::Rg {
+        command = command.arg("-E");
+    }
+    if !options.case_sensitive {
+        command = command.arg("-i");
+    }
+    if options.whole_word {
+        command = command.arg("-w");
+    }
+    if before > 0 {
+        command = command.arg("-B").arg(before.to_string());
+    }
+    if after > 0 {
+        command = command.arg("-A").arg(after.to_string());
+    }
+    command
+        .arg("-m")
+        .arg(max_results.saturating_add(1).to_string())
+        .arg("-e")
+        .arg(&options.pattern)
+        .path(path)
+}
+
+// 解析grep输出的一行：匹配行为 "行号:内容"，上下文行为 "行号-内容"，不连续的上下文之间的 "--" 返回None
+fn parse_grep_line(line: &str) -> Option<(usize, bool, &str)> {
+    let digits = line.bytes().take_while(u8::is_ascii_digit).count();
+    let line_number = line[..digits].parse().ok()?;
+    match line.as_bytes().get(digits) {
+        Some(b':') => Some((line_number, true, &line[digits + 1..])),
+        Some(b'-') => Some((line_number, false, &line[digits + 1..])),
+        _ => None,
+    }
+}
+
+fn run_search(window: tauri::Window, source: LogSource, options: SearchOptions, search_id: String) -> Result<SearchSummary, EasyLogError> {
+    let matcher = build_matcher(&options.pattern, options.is_regex, options.case_sensitive, options.whole_word)?;
+
+    let emit_id = search_id.clone();
+    let emit_window = window.clone();
+    let mut searcher = Searcher {
+        matcher,
+        context_before: options.context_before.or(options.context_lines).unwrap_or(0),
+        context_after: options.context_after.or(options.context_lines).unwrap_or(0),
+        max_results: options.max_results.unwrap_or(DEFAULT_MAX_RESULTS),
+        before: VecDeque::new(),
+        waiting: VecDeque::new(),
+        ready: Vec::new(),
+        total_matches: 0,
+        truncated: false,
+        scanned_lines: 0,
+        last_emit: Instant::now(),
+        emit: move |matches, scanned_lines| {
+            let batch = SearchResultBatch {
+                search_id: emit_id.clone(),
+                matches,
+                scanned_lines,
+                done: false,
+            };
+            if let Err(e) = emit_window.emit("search-log-results", batch) {
+                error!("Failed to emit search results: {}", e);
+            }
+        },
+    };
+
+    let encoding = options.encoding.as_deref();
+    match &source {
+        LogSource::Local { path } => for_each_local_line(path, encoding, |line| searcher.push_line(line))?,
+        LogSource::Remote { credentials, path } => {
+            let credentials = credentials.resolve()?;
+            // 使用识别出的编码解码，grep的输出只有匹配行，无法据此识别编码
+            let detected = remote_encoding(&credentials, path, encoding)?;
+            let encoding = Some(detected.name());
+            match remote_grep_program(&credentials, path, &options, detected) {
+                Some(program) => {
+                    info!("Searching {} on {} with {}", path, credentials.host, program.name());
+                    let command = remote_grep_command(
+                        program,
+                        path,
+                        &options,
+                        searcher.context_before,
+                        searcher.context_after,
+                        searcher.max_results,
+                    );
+                    // 匹配与否以服务器的结果为准，本地的正则只用来标出匹配的位置
+                    ssh::exec_lines(&credentials, &command, encoding, |line| match parse_grep_line(&line) {
+                        Some((line_number, matched, text)) => {
+                            let spans = matched.then(|| searcher.find_spans(text));
+                            searcher.push_numbered(line_number, text.to_string(), spans)
+                        }
+                        None => true,
+                    })?;
+                }
+                None => for_each_remote_line(&credentials, path, encoding, |line| searcher.push_line(line))?,
+            }
+        }
+    }
+    searcher.finish();
+
+    let summary = SearchSummary {
+        search_id: search_id.clone(),
+        total_matches: searcher.total_matches,
+        scanned_lines: searcher.scanned_lines,
+        truncated: searcher.truncated,
+    };
+
+    // 通知前端搜索已完成
+    let _ = window.emit(
+        "search-log-results",
+        SearchResultBatch {
+            search_id,
+            matches: Vec::new(),
+            scanned_lines: summary.scanned_lines,
+            done: true,
+        },
+    );
+
+    Ok(summary)
+}
+
+// 搜索日志，匹配结果通过 search-log-results 事件分批发送，返回值为搜索汇总
+#[tauri::command]
+pub async fn search_log(window: tauri::Window, source: LogSource, options: SearchOptions) -> Result<SearchSummary, EasyLogError> {
+    let search_id = options.search_id.clone().unwrap_or_else(|| {
+        format!(
+            "search-{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+        )
+    });
+    info!("Searching {} for {:?} (search id: {})", source.path(), options.pattern, search_id);
+
+    tokio::task::spawn_blocking(move || run_search(window, source, options, search_id))
+        .await
+        .map_err(|e| EasyLogError::internal(format!("搜索失败: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn searcher(pattern: &str, max_results: usize) -> Searcher<impl FnMut(Vec<SearchMatch>, usize)> {
+        Searcher {
+            matcher: build_matcher(pattern, false, true, false).unwrap(),
+            context_before: 0,
+            context_after: 0,
+            max_results,
+            before: VecDeque::new(),
+            waiting: VecDeque::new(),
+            ready: Vec::new(),
+            total_matches: 0,
+            truncated: false,
+            scanned_lines: 0,
+            last_emit: Instant::now(),
+            emit: |_, _| {},
+        }
+    }
+
+    #[test]
+    fn ere_compatible_patterns() {
+        for pattern in ["error|warn", "^\\[INFO\\] .*done$", "a{2,3}", "[^]a-z]+", "[[:digit:]]+", "(foo)?bar", "x\\.y"] {
+            assert!(is_ere_compatible(pattern), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn rust_only_patterns_are_not_ere() {
+        for pattern in ["\\d+", "\\w", "\\bword\\b", "(?i)error", "(?:a|b)", "a.*?b", "a+?", "[\\d]", "[a-z&&[^x]]", "\\p{Han}", "[abc"] {
+            assert!(!is_ere_compatible(pattern), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn server_side_grep_only_for_matching_encodings() {
+        use encoding_rs::{GB18030, UTF_16LE, WINDOWS_1252};
+        assert!(grep_matches_encoding(UTF_8, "连接超时"));
+        assert!(grep_matches_encoding(WINDOWS_1252, "timeout"));
+        assert!(!grep_matches_encoding(WINDOWS_1252, "café"));
+        assert!(!grep_matches_encoding(GB18030, "timeout"));
+        assert!(!grep_matches_encoding(UTF_16LE, "timeout"));
+    }
+
+    #[test]
+    fn exactly_max_matches_is_not_truncated() {
+        let mut searcher = searcher("hit", 2);
+        for line in ["hit 1", "miss", "hit 2", "miss"] {
+            searcher.push_line(line.to_string());
+        }
+        assert_eq!(searcher.total_matches, 2);
+        assert!(!searcher.truncated);
+    }
+
+    #[test]
+    fn extra_match_marks_truncated_and_stops() {
+        let mut searcher = searcher("hit", 2);
+        let lines = ["hit 1", "hit 2", "miss", "hit 3", "hit 4"];
+        let scanned = lines.iter().take_while(|line| searcher.push_line(line.to_string())).count();
+        assert_eq!(searcher.total_matches, 2);
+        assert!(searcher.truncated);
+        assert_eq!(scanned, 3);
+    }
+}
@@ -0,0 +1,866 @@
+// SSH连接相关的公共逻辑：建立会话、身份验证、会话池复用，执行命令并按行读取输出。
+// 会话处于非阻塞模式，通道和SFTP操作在独立线程中执行，遇到EAGAIN时通过REACTOR等待会话的socket就绪，而不是定时轮询
+use crate::compression;
+use crate::encoding::StreamDecoder;
+use crate::auth_prompt::InteractivePrompter;
+use crate::error::EasyLogError;
+use crate::host_keys;
+use crate::remote_command::{Program, RemoteCommand};
+use crate::ssh_config::{self, HostConfig};
+use crate::tunnel;
+use crate::{AuthMethod, SshCredentials};
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use ssh2::{BlockDirections, Channel, ErrorCode, FileStat, Session, Sftp};
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::future::Future;
+use std::net::TcpStream;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+use tokio::io::Interest;
+use tokio::sync::futures::Notified;
+use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
+
+// libssh2在非阻塞模式下表示“需要稍后重试”的错误码
+const LIBSSH2_ERROR_EAGAIN: i32 = -37;
+// libssh2表示操作超时的错误码
+const LIBSSH2_ERROR_TIMEOUT: i32 = -9;
+// libssh2表示连接本身已失效的错误码：发送失败、服务器断开、超时、接收失败
+const LIBSSH2_TRANSPORT_ERRORS: [i32; 4] = [-7, -13, -30, -43];
+// 非阻塞操作等待会话就绪时最多等待的时间；socket上的事件可能被同一会话上其它线程的读写消耗，超时后重试
+const RETRY_WAIT: Duration = Duration::from_millis(50);
+// 会话空闲（没有正在使用的通道）超过这个时间后断开
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+// 后台线程检查会话状态、发送keepalive的间隔
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+// 连接上超过这个秒数没有数据往来时发送keepalive
+const KEEPALIVE_SECONDS: u32 = 30;
+// 关闭通道时最多等待的时间
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
+// 跳板机的最大层数，避免ProxyJump配置互相引用时无限递归
+const MAX_JUMP_DEPTH: usize = 8;
+
+// 会话池的键：(host, port, username, 认证方式和凭据的哈希)；
+// 凭据不同的请求不会复用已认证的会话，错误的密码不会因为池中已有会话而显示连接成功
+type PoolKey = (String, u16, String, u64);
+
+struct PooledSession {
+    session: Session,
+    last_used: Instant,
+    // 每个正在使用的通道持有一份，引用计数为1时表示会话空闲
+    lease: Arc<()>,
+    waker: Arc<SessionWaker>,
+    // 服务器上是否安装了某个程序（例如rg），同一会话上只检查一次
+    programs: HashMap<Program, bool>,
+}
+
+lazy_static! {
+    // 已认证的会话，相同服务器、用户和凭据的命令共用一个会话，在上面打开多个通道
+    static ref SESSION_POOL: Mutex<HashMap<PoolKey, PooledSession>> = Mutex::new(HashMap::new());
+    // 只负责IO事件和定时器的运行时，实时监控的线程在上面等待socket可读、停止请求或超时
+    static ref REACTOR: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("ssh-reactor")
+        .enable_all()
+        .build()
+        .expect("failed to start SSH reactor");
+}
+
+// 在独立线程中等待异步操作完成；不能在异步任务中调用
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    REACTOR.handle().block_on(future)
+}
+
+// 会话上可能有新数据时唤醒等待的通道：会话的socket可读，或者同一会话上的其它通道读取时
+// 把数据读进了libssh2的缓冲区（socket上的可读事件已经被它消耗掉）。跳板机隧道也用它等待套接字就绪
+pub struct SessionWaker {
+    // 会话socket的副本，注册在REACTOR上；注册失败时只能依靠其它通道的通知和超时
+    socket: Option<tokio::net::TcpStream>,
+    activity: Notify,
+}
+
+impl SessionWaker {
+    pub fn new(socket: TcpStream) -> Self {
+        let _guard = REACTOR.enter();
+        let socket = socket
+            .set_nonblocking(true)
+            .and_then(|_| tokio::net::TcpStream::from_std(socket))
+            .map_err(|e| warn!("无法监听SSH连接的可读事件，改为定时检查: {}", e))
+            .ok();
+        SessionWaker {
+            socket,
+            activity: Notify::new(),
+        }
+    }
+
+    // 清除已经处理过的可读状态，之后到达的数据会重新触发可读事件
+    pub fn clear_readable(&self) {
+        if let Some(socket) = &self.socket {
+            let _ = socket.try_io(Interest::READABLE, || Err::<(), _>(std::io::ErrorKind::WouldBlock.into()));
+        }
+    }
+
+    // 清除已经处理过的可写状态，发送缓冲区重新有空间时会再次触发可写事件
+    pub fn clear_writable(&self) {
+        if let Some(socket) = &self.socket {
+            let _ = socket.try_io(Interest::WRITABLE, || Err::<(), _>(std::io::ErrorKind::WouldBlock.into()));
+        }
+    }
+
+    pub async fn readable(&self) {
+        match &self.socket {
+            Some(socket) => {
+                let _ = socket.readable().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    pub async fn writable(&self) {
+        match &self.socket {
+            Some(socket) => {
+                let _ = socket.writable().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    // 会话上的非阻塞操作返回EAGAIN后等待它可以继续：libssh2等待接收时等socket可读，等待发送时等socket可写，
+    // 同一会话上其它通道读取到数据时也会唤醒。只能在独立线程中调用
+    fn wait_session(&self, session: &Session, activity: Pin<&mut Notified<'_>>) {
+        let directions = session.block_directions();
+        let inbound = matches!(directions, BlockDirections::Inbound | BlockDirections::Both);
+        let outbound = matches!(directions, BlockDirections::Outbound | BlockDirections::Both);
+        block_on(async {
+            tokio::select! {
+                _ = self.readable(), if inbound => {}
+                _ = self.writable(), if outbound => {}
+                _ = activity => {}
+                _ = tokio::time::sleep(RETRY_WAIT) => {}
+            }
+        });
+    }
+}
+
+static KEEPALIVE_THREAD: Once = Once::new();
+
+fn pool_key(credentials: &SshCredentials) -> PoolKey {
+    let mut hasher = DefaultHasher::new();
+    credentials.auth_method.hash(&mut hasher);
+    (
+        credentials.host.clone(),
+        credentials.port.unwrap_or(22),
+        credentials.username.clone(),
+        hasher.finish(),
+    )
+}
+
+fn is_would_block(e: &ssh2::Error) -> bool {
+    e.code() == ErrorCode::Session(LIBSSH2_ERROR_EAGAIN)
+}
+
+// 池中的会话处于非阻塞模式（多个通道交替使用同一个会话），操作返回again为true的错误时等待会话就绪后重试。
+// 每次执行前登记通知并清除就绪状态，执行期间到达的数据和其它通道的通知不会丢失
+fn retry_until<T, E>(
+    session: &Session,
+    waker: &SessionWaker,
+    mut op: impl FnMut() -> Result<T, E>,
+    again: impl Fn(&E) -> bool,
+) -> Result<T, E> {
+    loop {
+        let activity = waker.activity.notified();
+        tokio::pin!(activity);
+        activity.as_mut().enable();
+        waker.clear_readable();
+        waker.clear_writable();
+        match op() {
+            Err(e) if again(&e) => waker.wait_session(session, activity),
+            result => return result,
+        }
+    }
+}
+
+fn retry<T>(session: &Session, waker: &SessionWaker, op: impl FnMut() -> Result<T, ssh2::Error>) -> Result<T, ssh2::Error> {
+    retry_until(session, waker, op, is_would_block)
+}
+
+// 非阻塞模式下的读取和定位遇到WouldBlock时等待重试，对调用方表现为阻塞操作
+fn retry_io<T>(session: &Session, waker: &SessionWaker, op: impl FnMut() -> std::io::Result<T>) -> std::io::Result<T> {
+    retry_until(session, waker, op, |e| e.kind() == std::io::ErrorKind::WouldBlock)
+}
+
+// 没有配置IdentityFile时，与OpenSSH一样依次尝试的默认私钥
+const DEFAULT_IDENTITY_FILES: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+// 用于公钥认证的私钥文件：指定的路径，或者 ~/.ssh/config 中的IdentityFile，都没有时使用默认私钥
+fn identity_files(private_key_path: Option<&str>, config: &HostConfig) -> Vec<PathBuf> {
+    if let Some(path) = private_key_path.filter(|p| !p.is_empty()) {
+        return vec![PathBuf::from(path)];
+    }
+    if !config.identity_files.is_empty() {
+        return config.identity_files.clone();
+    }
+    dirs::home_dir()
+        .map(|home| DEFAULT_IDENTITY_FILES.iter().map(|name| home.join(".ssh").join(name)).collect())
+        .unwrap_or_default()
+}
+
+fn auth_failed(host: &str, username: &str, reason: impl std::fmt::Display) -> EasyLogError {
+    EasyLogError::AuthFailed {
+        host: host.to_string(),
+        username: username.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+// 认证请求的错误：等待服务器响应超时（例如键盘交互认证一直没有回答）时返回Timeout
+fn auth_error(host: &str, username: &str, context: impl std::fmt::Display, e: ssh2::Error) -> EasyLogError {
+    if e.code() == ErrorCode::Session(LIBSSH2_ERROR_TIMEOUT) {
+        return EasyLogError::Timeout {
+            operation: format!("SSH认证 {}@{}", username, host),
+        };
+    }
+    auth_failed(host, username, format!("{}: {}", context, e))
+}
+
+// 依次尝试私钥文件，返回最后一个错误
+fn pubkey_auth(sess: &Session, host: &str, username: &str, files: &[PathBuf], passphrase: Option<&str>) -> Result<(), EasyLogError> {
+    let mut last_error = auth_failed(host, username, "没有可用的私钥文件");
+    for path in files.iter().filter(|path| path.exists()) {
+        match sess.userauth_pubkey_file(username, None, path, passphrase) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = auth_error(host, username, path.display(), e),
+        }
+    }
+    Err(last_error)
+}
+
+// 依次尝试ssh-agent中的每个身份
+fn agent_auth(sess: &Session, host: &str, username: &str) -> Result<(), EasyLogError> {
+    let mut agent = sess.agent().map_err(|e| auth_error(host, username, "无法使用ssh-agent", e))?;
+    agent
+        .connect()
+        .map_err(|e| auth_error(host, username, "无法连接到ssh-agent", e))?;
+    agent
+        .list_identities()
+        .map_err(|e| auth_error(host, username, "读取ssh-agent身份失败", e))?;
+
+    let mut last_error = auth_failed(host, username, "ssh-agent中没有可用的身份");
+    let identities = agent
+        .identities()
+        .map_err(|e| auth_error(host, username, "读取ssh-agent身份失败", e))?;
+    for identity in identities {
+        match agent.userauth(username, &identity) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = auth_error(host, username, identity.comment(), e),
+        }
+    }
+    let _ = agent.disconnect();
+    Err(last_error)
+}
+
+fn authenticate(sess: &Session, host: &str, username: &str, auth_method: &AuthMethod, config: &HostConfig) -> Result<(), EasyLogError> {
+    match auth_method {
+        AuthMethod::Password { password } => sess
+            .userauth_password(username, password)
+            .map_err(|e| auth_error(host, username, "密码认证失败", e)),
+        AuthMethod::PublicKey { private_key_path, passphrase } => pubkey_auth(
+            sess,
+            host,
+            username,
+            &identity_files(Some(private_key_path), config),
+            Some(passphrase.as_deref().unwrap_or("")),
+        ),
+        AuthMethod::Agent => agent_auth(sess, host, username).or_else(|agent_error| {
+            // agent不可用时再尝试没有口令的私钥文件，两者都失败时一起返回原因
+            pubkey_auth(sess, host, username, &identity_files(None, config), None).map_err(|key_error| {
+                match (agent_error, key_error) {
+                    (EasyLogError::AuthFailed { reason: agent_reason, .. }, EasyLogError::AuthFailed { reason: key_reason, .. }) => {
+                        auth_failed(host, username, format!("{}; {}", agent_reason, key_reason))
+                    }
+                    (_, key_error) => key_error,
+                }
+            })
+        }),
+        AuthMethod::KeyboardInteractive { password } => {
+            let mut prompter = InteractivePrompter::new(host, password.clone());
+            sess.userauth_keyboard_interactive(username, &mut prompter)
+                .map_err(|e| auth_error(host, username, "键盘交互认证失败", e))
+        }
+    }
+}
+
+// 建立TCP连接、完成SSH握手并根据认证方式进行身份验证；配置了跳板机时经过跳板机连接
+// 同时返回会话socket的副本，用于等待会话上的新数据
+pub fn connect_session(credentials: &SshCredentials) -> Result<(Session, TcpStream), EasyLogError> {
+    connect_through(credentials, 0)
+}
+
+fn connect_through(credentials: &SshCredentials, depth: usize) -> Result<(Session, TcpStream), EasyLogError> {
+    // 主机可以是 ~/.ssh/config 中的Host别名，未指定的端口和用户名使用配置中的值
+    let config = ssh_config::lookup(&credentials.host, &credentials.username);
+    let host = config.host_name.as_deref().unwrap_or(&credentials.host);
+    let port = credentials.port.or(config.port).unwrap_or(22);
+    let username = match (credentials.username.as_str(), config.user.as_deref()) {
+        ("", Some(user)) => user,
+        ("", None) => return Err(EasyLogError::invalid("username", "未指定SSH用户名")),
+        (username, _) => username,
+    };
+
+    // 跳板机列表中的最后一个直接连接目标主机，它自己再经过前面的跳板机连接
+    let jump_hosts = if credentials.jump_hosts.is_empty() {
+        config.proxy_jump.as_deref().map(ssh_config::proxy_jump_hosts).unwrap_or_default()
+    } else {
+        credentials.jump_hosts.clone()
+    };
+    let tcp = match jump_hosts.split_last() {
+        Some((jump, previous)) => {
+            if depth >= MAX_JUMP_DEPTH {
+                return Err(EasyLogError::invalid(
+                    "jump_hosts",
+                    format!("跳板机层数过多，请检查 {} 的ProxyJump配置是否循环", credentials.host),
+                ));
+            }
+            let mut jump = jump.clone();
+            if jump.jump_hosts.is_empty() {
+                jump.jump_hosts = previous.to_vec();
+            }
+            info!("Connecting to {}:{} through jump host {}", host, port, jump.host);
+            // 跳板机的错误中已经包含跳板机的主机名
+            let (jump_session, jump_socket) = connect_through(&jump, depth + 1)?;
+            tunnel::open(jump_session, jump_socket, host, port)?
+        }
+        // 创建TCP连接
+        None => TcpStream::connect(format!("{}:{}", host, port)).map_err(|e| match e.kind() {
+            std::io::ErrorKind::TimedOut => EasyLogError::Timeout {
+                operation: format!("连接 {}:{}", host, port),
+            },
+            _ => connection_failed(host, port, e),
+        })?,
+    };
+    let socket = tcp.try_clone().map_err(|e| connection_failed(host, port, e))?;
+
+    // 创建SSH会话
+    let mut sess = Session::new().map_err(|e| EasyLogError::internal(format!("创建SSH会话失败: {}", e)))?;
+
+    sess.set_tcp_stream(tcp);
+    sess.handshake()
+        .map_err(|e| connection_failed(host, port, format!("SSH握手失败: {}", e)))?;
+
+    // 发送认证信息之前先确认服务器的身份
+    host_keys::verify(&sess, host, port)?;
+
+    // 根据认证方式进行身份验证
+    authenticate(&sess, host, username, &credentials.auth_method, &config)?;
+
+    Ok((sess, socket))
+}
+
+fn connection_failed(host: &str, port: u16, reason: impl std::fmt::Display) -> EasyLogError {
+    EasyLogError::ConnectionFailed {
+        host: host.to_string(),
+        port,
+        reason: reason.to_string(),
+    }
+}
+
+// 后台线程：定期发送keepalive，断开已失效或长时间空闲的会话
+fn start_keepalive_thread() {
+    KEEPALIVE_THREAD.call_once(|| {
+        std::thread::spawn(|| loop {
+            std::thread::sleep(KEEPALIVE_INTERVAL);
+
+            let mut pool = SESSION_POOL.lock().unwrap();
+            pool.retain(|key, pooled| {
+                let idle = Arc::strong_count(&pooled.lease) == 1;
+                if idle && pooled.last_used.elapsed() >= IDLE_TIMEOUT {
+                    info!("Closing idle SSH session to {}@{}:{}", key.2, key.0, key.1);
+                    let _ = pooled.session.disconnect(None, "idle", None);
+                    return false;
+                }
+                match pooled.session.keepalive_send() {
+                    Err(e) if !is_would_block(&e) => {
+                        warn!("SSH session to {}:{} is no longer alive: {}", key.0, key.1, e);
+                        // 唤醒等待数据的通道，让它们发现会话已失效
+                        pooled.waker.activity.notify_waiters();
+                        false
+                    }
+                    _ => true,
+                }
+            });
+        });
+    });
+}
+
+// 从池中取出会话，没有可用会话时新建连接并放入池中
+fn pooled_session(credentials: &SshCredentials) -> Result<(Session, Arc<()>, Arc<SessionWaker>), EasyLogError> {
+    let key = pool_key(credentials);
+    {
+        let mut pool = SESSION_POOL.lock().unwrap();
+        if let Some(pooled) = pool.get_mut(&key) {
+            pooled.last_used = Instant::now();
+            return Ok((pooled.session.clone(), pooled.lease.clone(), pooled.waker.clone()));
+        }
+    }
+
+    // 连接和认证比较耗时，不持有池的锁，避免阻塞其它服务器的请求
+    let (session, socket) = connect_session(credentials)?;
+    session.set_keepalive(false, KEEPALIVE_SECONDS);
+    session.set_blocking(false);
+    start_keepalive_thread();
+
+    let mut pool = SESSION_POOL.lock().unwrap();
+    match pool.entry(key) {
+        Entry::Occupied(entry) => {
+            // 并发请求已经放入了同一服务器的会话，使用已有的会话并断开多余的连接，
+            // 否则服务器上的sshd连接要等到TCP超时才会释放
+            let pooled = entry.into_mut();
+            pooled.last_used = Instant::now();
+            let existing = (pooled.session.clone(), pooled.lease.clone(), pooled.waker.clone());
+            drop(pool);
+            session.set_blocking(true);
+            let _ = session.disconnect(None, "duplicate", None);
+            Ok(existing)
+        }
+        Entry::Vacant(entry) => {
+            let key = entry.key();
+            info!("Opened pooled SSH session to {}@{}:{}", key.2, key.0, key.1);
+            let pooled = entry.insert(PooledSession {
+                session,
+                last_used: Instant::now(),
+                lease: Arc::new(()),
+                waker: Arc::new(SessionWaker::new(socket)),
+                programs: HashMap::new(),
+            });
+            Ok((pooled.session.clone(), pooled.lease.clone(), pooled.waker.clone()))
+        }
+    }
+}
+
+// 从池中移除已失效的会话，下次使用时重新连接；lease用于确认池中还是同一个会话，
+// 其它线程已经重新连接时不会误断开新的会话
+fn evict(credentials: &SshCredentials, lease: &Arc<()>) {
+    let mut pool = SESSION_POOL.lock().unwrap();
+    let key = pool_key(credentials);
+    if !pool.get(&key).is_some_and(|pooled| Arc::ptr_eq(&pooled.lease, lease)) {
+        return;
+    }
+    if let Some(pooled) = pool.remove(&key) {
+        let _ = pooled.session.disconnect(None, "reconnect", None);
+        pooled.waker.activity.notify_waiters();
+    }
+}
+
+// 打开通道或SFTP失败时判断是否是连接本身失效；服务器拒绝打开更多通道（例如超过MaxSessions）
+// 时连接仍然可用，不能断开会话，否则同一会话上正在运行的其它日志流和搜索也会中断
+fn transport_dead(session: &Session, e: &ssh2::Error) -> bool {
+    if matches!(e.code(), ErrorCode::Session(code) if LIBSSH2_TRANSPORT_ERRORS.contains(&code)) {
+        return true;
+    }
+    if !session.authenticated() {
+        return true;
+    }
+    matches!(session.keepalive_send(), Err(ref e) if !is_would_block(e))
+}
+
+// 用新的连接测试认证信息是否有效，测试完成后断开，不影响池中的会话
+pub fn test_connection(credentials: &SshCredentials) -> Result<(), EasyLogError> {
+    let (session, _) = connect_session(credentials)?;
+    let _ = session.disconnect(None, "connection test", None);
+    Ok(())
+}
+
+// 读取等待的结果
+pub enum WaitRead {
+    Data(usize), // 读取到的字节数，0表示通道已经结束
+    Idle,        // 暂时没有数据（被唤醒或超时），调用方检查状态后再次读取
+    Cancelled,
+}
+
+// 池中会话上的一个通道：读取时遇到EAGAIN会等待重试，对调用方表现为阻塞读取
+pub struct PooledChannel {
+    channel: Channel,
+    session: Session,
+    lease: Arc<()>,
+    waker: Arc<SessionWaker>,
+    host: String, // 用于错误信息
+}
+
+impl PooledChannel {
+    // 读取数据，没有数据时在REACTOR上等待会话的socket可读、同一会话上其它通道的读取、取消或者超时，
+    // 等待期间不占用异步运行时的工作线程。只能在独立线程中调用，用于实时监控
+    pub fn read_wait(&mut self, buffer: &mut [u8], timeout: Duration, cancel: &CancellationToken) -> std::io::Result<WaitRead> {
+        if cancel.is_cancelled() {
+            return Ok(WaitRead::Cancelled);
+        }
+        let waker = self.waker.clone();
+        let channel = &mut self.channel;
+        block_on(async {
+            // 先登记通知再读取，读取之后其它通道发出的通知不会丢失
+            let activity = waker.activity.notified();
+            tokio::pin!(activity);
+            activity.as_mut().enable();
+            waker.clear_readable();
+
+            match channel.read(buffer) {
+                Ok(n) => {
+                    if n > 0 {
+                        waker.activity.notify_waiters();
+                    }
+                    return Ok(WaitRead::Data(n));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+
+            tokio::select! {
+                _ = cancel.cancelled() => Ok(WaitRead::Cancelled),
+                _ = waker.readable() => Ok(WaitRead::Idle),
+                _ = activity => Ok(WaitRead::Idle),
+                _ = tokio::time::sleep(timeout) => Ok(WaitRead::Idle),
+            }
+        })
+    }
+
+    pub fn eof(&self) -> bool {
+        self.channel.eof()
+    }
+
+    // 通道所在的会话是否仍在会话池中；keepalive检测到连接失效时会把会话移出会话池
+    pub fn session_alive(&self) -> bool {
+        SESSION_POOL
+            .lock()
+            .unwrap()
+            .values()
+            .any(|pooled| Arc::ptr_eq(&pooled.lease, &self.lease))
+    }
+
+    // 读取已经收到的标准错误输出，不等待新的输出；用于实时监控时检查 tail -F 的提示
+    pub fn read_stderr_now(&mut self) -> String {
+        let mut stderr = self.channel.stderr();
+        let mut output = Vec::new();
+        let mut buffer = [0u8; 4096];
+        while let Ok(n) = stderr.read(&mut buffer) {
+            if n == 0 {
+                break;
+            }
+            output.extend_from_slice(&buffer[..n]);
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    // 读取标准错误输出
+    pub fn read_stderr(&mut self) -> String {
+        let mut stderr = self.channel.stderr();
+        let mut output = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            match retry_io(&self.session, &self.waker, || stderr.read(&mut buffer)) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => output.extend_from_slice(&buffer[..n]),
+            }
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    }
+
+    // 等待远程命令结束，返回退出码
+    pub fn wait_close(&mut self) -> Result<i32, EasyLogError> {
+        retry(&self.session, &self.waker, || self.channel.wait_close()).map_err(|e| self.channel_error("关闭SSH通道失败", e))?;
+        self.channel
+            .exit_status()
+            .map_err(|e| self.channel_error("获取命令退出码失败", e))
+    }
+
+    fn channel_error(&self, context: &str, e: ssh2::Error) -> EasyLogError {
+        if e.code() == ErrorCode::Session(LIBSSH2_ERROR_TIMEOUT) {
+            return EasyLogError::Timeout {
+                operation: format!("{} ({})", context, self.host),
+            };
+        }
+        EasyLogError::RemoteCommand {
+            host: self.host.clone(),
+            reason: format!("{}: {}", context, e),
+        }
+    }
+
+    // 提前关闭通道，让远程命令（例如 tail -f）退出
+    pub fn close(&mut self) {
+        let deadline = Instant::now() + CLOSE_TIMEOUT;
+        let _ = retry_until(
+            &self.session,
+            &self.waker,
+            || self.channel.close(),
+            |e| is_would_block(e) && Instant::now() < deadline,
+        );
+    }
+}
+
+impl Read for PooledChannel {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let n = retry_io(&self.session, &self.waker, || self.channel.read(buffer))?;
+        if n > 0 {
+            // 读取时可能顺带收到了其它通道的数据，唤醒等待中的实时监控
+            self.waker.activity.notify_waiters();
+        }
+        Ok(n)
+    }
+}
+
+impl Drop for PooledChannel {
+    // 非阻塞模式下释放通道时libssh2不会等待关闭完成，先在这里确保通道已关闭
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+// 池中会话上的SFTP子系统：会话处于非阻塞模式，每个操作遇到EAGAIN时等待重试
+pub struct PooledSftp {
+    sftp: Sftp,
+    session: Session,
+    lease: Arc<()>,
+    waker: Arc<SessionWaker>,
+}
+
+impl PooledSftp {
+    // 列出目录内容，不包含 . 和 ..；返回的路径已经拼接上目录
+    pub fn readdir(&self, path: &Path) -> Result<Vec<(PathBuf, FileStat)>, EasyLogError> {
+        retry(&self.session, &self.waker, || self.sftp.readdir(path)).map_err(|e| EasyLogError::sftp(path, e))
+    }
+
+    // 获取文件信息，符号链接返回其指向的文件的信息
+    pub fn stat(&self, path: &Path) -> Result<FileStat, EasyLogError> {
+        retry(&self.session, &self.waker, || self.sftp.stat(path)).map_err(|e| EasyLogError::sftp(path, e))
+    }
+
+    // 转换为绝对路径，"." 表示登录用户的主目录
+    pub fn realpath(&self, path: &Path) -> Result<PathBuf, EasyLogError> {
+        retry(&self.session, &self.waker, || self.sftp.realpath(path)).map_err(|e| EasyLogError::sftp(path, e))
+    }
+
+    // 以只读方式打开文件
+    pub fn open(&self, path: &Path) -> Result<PooledSftpFile, EasyLogError> {
+        let file = retry(&self.session, &self.waker, || self.sftp.open(path)).map_err(|e| EasyLogError::sftp(path, e))?;
+        Ok(PooledSftpFile {
+            file,
+            session: self.session.clone(),
+            _lease: self.lease.clone(),
+            waker: self.waker.clone(),
+        })
+    }
+}
+
+// SFTP打开的远程文件，可以像本地文件一样定位和读取，只传输实际读取的部分
+pub struct PooledSftpFile {
+    file: ssh2::File,
+    session: Session,
+    _lease: Arc<()>,
+    waker: Arc<SessionWaker>,
+}
+
+impl Read for PooledSftpFile {
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        retry_io(&self.session, &self.waker, || self.file.read(buffer))
+    }
+}
+
+impl Seek for PooledSftpFile {
+    fn seek(&mut self, position: SeekFrom) -> std::io::Result<u64> {
+        retry_io(&self.session, &self.waker, || self.file.seek(position))
+    }
+}
+
+// 在池中的会话上打开SFTP子系统；会话已失效时重新连接一次
+pub fn sftp(credentials: &SshCredentials) -> Result<PooledSftp, EasyLogError> {
+    let (session, lease, waker) = pooled_session(credentials)?;
+    match retry(&session, &waker, || session.sftp()) {
+        Ok(sftp) => {
+            return Ok(PooledSftp {
+                sftp,
+                session,
+                lease,
+                waker,
+            })
+        }
+        Err(e) if !transport_dead(&session, &e) => {
+            return Err(channel_failed(credentials, format!("无法打开SFTP: {}", e)));
+        }
+        Err(_) => {}
+    }
+
+    warn!("Pooled SSH session to {} failed, reconnecting", credentials.host);
+    evict(credentials, &lease);
+    drop(lease);
+    let (session, lease, waker) = pooled_session(credentials)?;
+    let sftp = retry(&session, &waker, || session.sftp())
+        .map_err(|e| session_failed(credentials, format!("无法打开SFTP: {}", e)))?;
+    Ok(PooledSftp {
+        sftp,
+        session,
+        lease,
+        waker,
+    })
+}
+
+// 重新连接后会话仍然不可用
+fn session_failed(credentials: &SshCredentials, reason: String) -> EasyLogError {
+    connection_failed(&credentials.host, credentials.port.unwrap_or(22), reason)
+}
+
+// 连接正常但服务器拒绝打开新的通道
+fn channel_failed(credentials: &SshCredentials, reason: String) -> EasyLogError {
+    EasyLogError::RemoteCommand {
+        host: credentials.host.clone(),
+        reason,
+    }
+}
+
+fn open_channel(session: &Session, waker: &SessionWaker, command: &str) -> Result<Channel, ssh2::Error> {
+    let mut channel = retry(session, waker, || session.channel_session())?;
+    retry(session, waker, || channel.exec(command))?;
+    Ok(channel)
+}
+
+// 在池中的会话上执行命令；会话已失效（例如网络中断后）时重新连接一次。
+// 命令只能通过RemoteCommand构造，参数都已经加好引号
+pub fn exec(credentials: &SshCredentials, command: &RemoteCommand) -> Result<PooledChannel, EasyLogError> {
+    let command = command.to_string();
+    debug!("Executing on {}: {}", credentials.host, command);
+    let (session, lease, waker) = pooled_session(credentials)?;
+    match open_channel(&session, &waker, &command) {
+        Ok(channel) => {
+            return Ok(PooledChannel {
+                channel,
+                session,
+                lease,
+                waker,
+                host: credentials.host.clone(),
+            })
+        }
+        Err(e) if !transport_dead(&session, &e) => {
+            return Err(channel_failed(credentials, format!("执行命令失败: {}", e)));
+        }
+        Err(_) => {}
+    }
+
+    warn!("Pooled SSH session to {} failed, reconnecting", credentials.host);
+    evict(credentials, &lease);
+    drop(lease);
+    let (session, lease, waker) = pooled_session(credentials)?;
+    let channel = open_channel(&session, &waker, &command)
+        .map_err(|e| session_failed(credentials, format!("执行命令失败: {}", e)))?;
+    Ok(PooledChannel {
+        channel,
+        session,
+        lease,
+        waker,
+        host: credentials.host.clone(),
+    })
+}
+
+// 在远程服务器上执行命令，把输出解压、解码后逐行交给on_line处理；on_line返回false时提前结束
+pub fn exec_lines<F>(credentials: &SshCredentials, command: &RemoteCommand, encoding: Option<&str>, mut on_line: F) -> Result<i32, EasyLogError>
+where
+    F: FnMut(String) -> bool,
+{
+    let mut channel = exec(credentials, command)?;
+
+    let mut decoder = StreamDecoder::new(encoding)?;
+    let mut pending = String::new();
+    let mut buffer = vec![0; 8192];
+    let mut stopped = false;
+
+    {
+        let (mut reader, _) = compression::decompress_stream(&mut channel).map_err(|e| read_failed(credentials, e))?;
+
+        loop {
+            let n = reader.read(&mut buffer).map_err(|e| read_failed(credentials, e))?;
+            pending.push_str(&decoder.feed(&buffer[..n], n == 0));
+
+            while let Some(idx) = pending.find('\n') {
+                let line: String = pending.drain(..=idx).collect();
+                if !on_line(line.trim_end_matches('\n').trim_end_matches('\r').to_string()) {
+                    stopped = true;
+                    break;
+                }
+            }
+
+            if stopped || n == 0 {
+                break;
+            }
+        }
+    }
+
+    // 最后一行没有换行符
+    if !stopped && !pending.is_empty() {
+        on_line(pending.trim_end_matches('\r').to_string());
+    }
+
+    // 提前结束时关闭通道，让远程命令退出
+    if stopped {
+        channel.close();
+        channel.wait_close().ok();
+        return Ok(0);
+    }
+    let status = channel.wait_close().unwrap_or(0);
+    let stderr = channel.read_stderr();
+    if status != 0 && !stderr.trim().is_empty() {
+        return Err(EasyLogError::remote_command(&credentials.host, &stderr));
+    }
+    Ok(status)
+}
+
+fn read_failed(credentials: &SshCredentials, e: std::io::Error) -> EasyLogError {
+    EasyLogError::RemoteCommand {
+        host: credentials.host.clone(),
+        reason: format!("读取命令输出失败: {}", e),
+    }
+}
+
+// 服务器上是否安装了program：执行 program --version 检查，结果缓存在池中的会话上，
+// 重新连接后会重新检查。无法执行检查命令（例如通道被拒绝）时返回false，但不缓存
+pub fn has_program(credentials: &SshCredentials, program: Program) -> bool {
+    let key = pool_key(credentials);
+    let cached = SESSION_POOL
+        .lock()
+        .unwrap()
+        .get(&key)
+        .and_then(|pooled| pooled.programs.get(&program).copied());
+    if let Some(found) = cached {
+        return found;
+    }
+
+    let result = exec(credentials, &RemoteCommand::new(program).arg("--version")).and_then(|mut channel| {
+        std::io::copy(&mut channel, &mut std::io::sink()).map_err(|e| read_failed(credentials, e))?;
+        channel.wait_close()
+    });
+    let found = match result {
+        Ok(status) => status == 0,
+        Err(e) => {
+            warn!("Failed to check for {} on {}: {}", program.name(), credentials.host, e);
+            return false;
+        }
+    };
+    if let Some(pooled) = SESSION_POOL.lock().unwrap().get_mut(&key) {
+        pooled.programs.insert(program, found);
+    }
+    found
+}
+
+// 执行命令并返回完整的标准输出，用于获取文件大小等简短结果
+pub fn exec_output(credentials: &SshCredentials, command: &RemoteCommand) -> Result<String, EasyLogError> {
+    let mut channel = exec(credentials, command)?;
+
+    let mut output = String::new();
+    channel.read_to_string(&mut output).map_err(|e| read_failed(credentials, e))?;
+
+    if channel.wait_close().unwrap_or(0) != 0 {
+        return Err(EasyLogError::remote_command(&credentials.host, &channel.read_stderr()));
+    }
+    Ok(output)
+}
@@ -0,0 +1,386 @@
+// 读取 ~/.ssh/config：按Host别名补全实际的主机名、端口、用户名、私钥和跳板机设置。
+// 与OpenSSH一样，同一个设置以最先出现的值为准，IdentityFile可以有多个；Match块暂不支持，其中的设置会被忽略
+use crate::{AuthMethod, SshCredentials};
+use log::warn;
+use std::path::{Path, PathBuf};
+
+// Include嵌套的最大深度，防止循环引用
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, Default, Clone)]
+pub struct HostConfig {
+    pub host_name: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_files: Vec<PathBuf>,
+    pub proxy_jump: Option<String>, // 逗号分隔的跳板机列表，"none"表示不使用
+}
+
+fn ssh_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh"))
+}
+
+// 通配符匹配，支持 * 和 ?。匹配失败时只回退到最近的一个 *，时间复杂度为 O(模式长度 × 文本长度)
+fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // 最近的 * 在模式中的位置，以及它之后的内容从文本的哪个位置开始匹配
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // 让 * 多匹配一个字符后重新尝试
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+// Host行的匹配规则：任意一个模式匹配并且没有被 !模式 排除
+fn host_matches(patterns: &[String], host: &str) -> bool {
+    let host = host.to_lowercase();
+    let mut matched = false;
+    for pattern in patterns {
+        let pattern = pattern.to_lowercase();
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated.as_bytes(), host.as_bytes()) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern.as_bytes(), host.as_bytes()),
+        }
+    }
+    matched
+}
+
+// 拆分一行中的关键字和参数，关键字和参数之间可以用空格或等号分隔，参数可以用双引号包含空格
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let split = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let keyword = line[..split].to_lowercase();
+    let rest = line[split..].trim_start().strip_prefix('=').unwrap_or(&line[split..]);
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut in_arg = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Some((keyword, args))
+}
+
+// 展开路径开头的 ~
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ if path == "~" => dirs::home_dir().unwrap_or_default(),
+        _ => PathBuf::from(path),
+    }
+}
+
+// Include的路径：相对路径相对于 ~/.ssh，文件名中可以使用通配符
+fn include_paths(pattern: &str) -> Vec<PathBuf> {
+    let mut path = expand_home(pattern);
+    if path.is_relative() {
+        match ssh_dir() {
+            Some(dir) => path = dir.join(path),
+            None => return Vec::new(),
+        }
+    }
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| wildcard_match(name.as_bytes(), entry.file_name().to_string_lossy().as_bytes()))
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+struct Parser<'a> {
+    alias: &'a str,
+    config: HostConfig,
+    identity_files: Vec<String>, // 展开 %r 等变量之前的原始路径
+}
+
+impl Parser<'_> {
+    fn parse_file(&mut self, path: &Path, active: &mut bool, depth: usize) {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return;
+        };
+        for (keyword, args) in content.lines().filter_map(split_line) {
+            match keyword.as_str() {
+                "host" => *active = host_matches(&args, self.alias),
+                "match" => *active = false,
+                "include" if *active => {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        warn!("Include nested too deeply in {}", path.display());
+                        continue;
+                    }
+                    for include in args.iter().flat_map(|arg| include_paths(arg)) {
+                        self.parse_file(&include, active, depth + 1);
+                    }
+                }
+                _ if *active => self.apply(&keyword, args),
+                _ => {}
+            }
+        }
+    }
+
+    fn apply(&mut self, keyword: &str, mut args: Vec<String>) {
+        if args.is_empty() {
+            return;
+        }
+        let config = &mut self.config;
+        match keyword {
+            "hostname" if config.host_name.is_none() => config.host_name = Some(args.remove(0)),
+            "port" if config.port.is_none() => match args[0].parse() {
+                Ok(port) => config.port = Some(port),
+                Err(_) => warn!("Invalid Port in ssh config: {}", args[0]),
+            },
+            "user" if config.user.is_none() => config.user = Some(args.remove(0)),
+            "proxyjump" if config.proxy_jump.is_none() => config.proxy_jump = Some(args.remove(0)),
+            "identityfile" => self.identity_files.push(args.remove(0)),
+            _ => {}
+        }
+    }
+}
+
+// 展开路径中的 %h %r %u %d %% 变量
+fn expand_tokens(value: &str, host: &str, user: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => result.push_str(host),
+            Some('r') => result.push_str(user),
+            Some('u') => result.push_str(&std::env::var("USER").or_else(|_| std::env::var("USERNAME")).unwrap_or_default()),
+            Some('d') => result.push_str(&dirs::home_dir().unwrap_or_default().to_string_lossy()),
+            Some('%') => result.push('%'),
+            Some(other) => {
+                result.push('%');
+                result.push(other);
+            }
+            None => result.push('%'),
+        }
+    }
+    result
+}
+
+// 查找主机（或Host别名）对应的配置，没有配置文件时返回空配置
+pub fn lookup(alias: &str, username: &str) -> HostConfig {
+    match ssh_dir() {
+        Some(dir) => lookup_in(&dir.join("config"), alias, username),
+        None => HostConfig::default(),
+    }
+}
+
+fn lookup_in(path: &Path, alias: &str, username: &str) -> HostConfig {
+    let mut parser = Parser {
+        alias,
+        config: HostConfig::default(),
+        identity_files: Vec::new(),
+    };
+    parser.parse_file(path, &mut true, 0);
+
+    let mut config = parser.config;
+    if let Some(host_name) = config.host_name.as_mut() {
+        *host_name = host_name.replace("%h", alias);
+    }
+    let host = config.host_name.clone().unwrap_or_else(|| alias.to_string());
+    let user = if username.is_empty() {
+        config.user.clone().unwrap_or_default()
+    } else {
+        username.to_string()
+    };
+    config.identity_files = parser
+        .identity_files
+        .iter()
+        .map(|file| expand_home(&expand_tokens(file, &host, &user)))
+        .collect();
+    if config.proxy_jump.as_deref().is_some_and(|jump| jump.eq_ignore_ascii_case("none")) {
+        config.proxy_jump = None;
+    }
+    config
+}
+
+// 解析ProxyJump的跳板机列表：[user@]host[:port]，多个跳板机用逗号分隔，也支持 ssh://user@host:port 的写法。
+// 跳板机使用ssh-agent或 ~/.ssh/config 中的IdentityFile认证
+pub fn proxy_jump_hosts(spec: &str) -> Vec<SshCredentials> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|jump| !jump.is_empty())
+        .map(|jump| {
+            let jump = jump.strip_prefix("ssh://").unwrap_or(jump);
+            let (username, address) = match jump.rsplit_once('@') {
+                Some((user, address)) => (user.to_string(), address),
+                None => (String::new(), jump),
+            };
+            // IPv6地址需要写成 [addr]:port
+            let (host, port) = match address.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+                Some((host, rest)) => (host, rest.strip_prefix(':').and_then(|p| p.parse().ok())),
+                None => match address.split_once(':') {
+                    Some((host, port)) if !port.contains(':') => (host, port.parse().ok()),
+                    _ => (address, None),
+                },
+            };
+            SshCredentials {
+                host: host.to_string(),
+                port,
+                username,
+                auth_method: AuthMethod::Agent,
+                jump_hosts: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup_config(content: &str, alias: &str, username: &str) -> HostConfig {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config");
+        std::fs::write(&path, content).unwrap();
+        lookup_in(&path, alias, username)
+    }
+
+    #[test]
+    fn split_line_separators_and_quotes() {
+        let expected = Some(("hostname".to_string(), vec!["example.com".to_string()]));
+        assert_eq!(split_line("HostName example.com"), expected);
+        assert_eq!(split_line("HostName=example.com"), expected);
+        assert_eq!(split_line("HostName = example.com"), expected);
+        assert_eq!(split_line("  HostName\texample.com  "), expected);
+        assert_eq!(
+            split_line(r#"IdentityFile "~/my keys/id_ed25519" other"#),
+            Some(("identityfile".to_string(), vec!["~/my keys/id_ed25519".to_string(), "other".to_string()]))
+        );
+        assert_eq!(split_line("# comment"), None);
+        assert_eq!(split_line("   "), None);
+    }
+
+    #[test]
+    fn wildcard_patterns() {
+        assert!(wildcard_match(b"*.example.com", b"web1.example.com"));
+        assert!(wildcard_match(b"web?", b"web1"));
+        assert!(!wildcard_match(b"web?", b"web10"));
+        assert!(wildcard_match(b"*", b""));
+        assert!(wildcard_match(b"a*b*c", b"aXbYbZc"));
+        assert!(!wildcard_match(b"a*b*c", b"aXbYbZ"));
+        // 多个 * 的模式不能出现指数级的回溯
+        let text = vec![b'a'; 10_000];
+        assert!(!wildcard_match(b"*a*a*a*a*a*a*b", &text));
+    }
+
+    #[test]
+    fn negated_host_patterns() {
+        let patterns = vec!["*.example.com".to_string(), "!bastion.example.com".to_string()];
+        assert!(host_matches(&patterns, "web.example.com"));
+        assert!(host_matches(&patterns, "WEB.Example.com"));
+        assert!(!host_matches(&patterns, "bastion.example.com"));
+        assert!(!host_matches(&["!foo".to_string()], "bar"));
+    }
+
+    #[test]
+    fn first_match_wins_across_host_blocks() {
+        let config = lookup_config(
+            "Host web\n  HostName web.internal\n  Port 2200\n  IdentityFile ~/.ssh/web_%r\n\
+             Host *\n  HostName ignored\n  Port 22\n  User deploy\n  IdentityFile /keys/%h\n",
+            "web",
+            "",
+        );
+        assert_eq!(config.host_name.as_deref(), Some("web.internal"));
+        assert_eq!(config.port, Some(2200));
+        assert_eq!(config.user.as_deref(), Some("deploy"));
+        // IdentityFile可以有多个，%r和%h按最终的用户名和主机名展开
+        assert_eq!(config.identity_files.len(), 2);
+        assert!(config.identity_files[0].ends_with(".ssh/web_deploy"));
+        assert_eq!(config.identity_files[1], PathBuf::from("/keys/web.internal"));
+    }
+
+    #[test]
+    fn match_blocks_are_ignored() {
+        let config = lookup_config("Match host web\n  User skipped\nHost web\n  User kept\n", "web", "");
+        assert_eq!(config.user.as_deref(), Some("kept"));
+    }
+
+    #[test]
+    fn proxy_jump_none_disables_jump() {
+        let config = lookup_config("Host internal\n  ProxyJump none\nHost *\n  ProxyJump bastion\n", "internal", "");
+        assert_eq!(config.proxy_jump, None);
+
+        let config = lookup_config("Host internal\n  ProxyJump none\nHost *\n  ProxyJump bastion\n", "other", "");
+        assert_eq!(config.proxy_jump.as_deref(), Some("bastion"));
+    }
+
+    #[test]
+    fn proxy_jump_list() {
+        let hosts = proxy_jump_hosts("alice@bastion.example.com:2222, jump2 ,ssh://bob@10.0.0.1:22,[::1]:2200,carol@[fe80::1]");
+        let parsed: Vec<_> = hosts
+            .iter()
+            .map(|h| (h.username.as_str(), h.host.as_str(), h.port))
+            .collect();
+        assert_eq!(
+            parsed,
+            [
+                ("alice", "bastion.example.com", Some(2222)),
+                ("", "jump2", None),
+                ("bob", "10.0.0.1", Some(22)),
+                ("", "::1", Some(2200)),
+                ("carol", "fe80::1", None),
+            ]
+        );
+        assert!(hosts.iter().all(|h| matches!(h.auth_method, AuthMethod::Agent)));
+        assert!(proxy_jump_hosts(" , ").is_empty());
+    }
+}
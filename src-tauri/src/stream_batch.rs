@@ -0,0 +1,284 @@
+// 实时日志的批量发送：读取线程把行放入有界的环形缓冲区，发送线程每攒够一批或者每隔一段时间
+// 把缓冲区中的行作为一个事件发送到前端，避免高频日志逐行发送事件占满IPC通道导致界面卡死。
+// 前端处理不过来、缓冲区已满时按设置丢弃最旧的行（并报告丢弃的行数）或者暂停读取
+use crate::LogStreamData;
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+const DEFAULT_BATCH_LINES: usize = 200;
+const DEFAULT_BATCH_INTERVAL: Duration = Duration::from_millis(100);
+// 发送间隔的下限，避免间隔为0时发送线程空转
+const MIN_BATCH_INTERVAL: Duration = Duration::from_millis(10);
+const DEFAULT_BUFFER_LINES: usize = 10_000;
+
+// 缓冲区已满时的处理方式
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    #[default]
+    DropOldest, // 丢弃最旧的行，继续读取
+    Pause,      // 暂停读取，等前端处理完再继续（远程日志由SSH的流量控制让服务器端等待）
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct BatchOptions {
+    batch_lines: Option<usize>,       // 可选参数，每批最多的行数，默认200
+    batch_interval_ms: Option<u64>,   // 可选参数，发送间隔（毫秒），默认100
+    buffer_lines: Option<usize>,      // 可选参数，缓冲区最多保存的行数，默认10000
+    overflow: Option<OverflowPolicy>, // 可选参数，缓冲区已满时的处理方式，默认丢弃最旧的行
+}
+
+impl BatchOptions {
+    fn interval(&self) -> Duration {
+        self.batch_interval_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_BATCH_INTERVAL)
+            .max(MIN_BATCH_INTERVAL)
+    }
+}
+
+// ssh-log-batch 事件的数据
+#[derive(Debug, Serialize, Clone)]
+pub struct LogStreamBatch {
+    stream_id: Option<String>,
+    lines: Vec<LogStreamData>,
+    dropped: u64,       // 上一批之后因为缓冲区已满而丢弃的行数
+    total_dropped: u64, // 这个流一共丢弃的行数
+}
+
+struct BufferState {
+    lines: VecDeque<LogStreamData>,
+    dropped: u64,
+    closed: bool,
+}
+
+struct Shared {
+    state: Mutex<BufferState>,
+    ready: Condvar, // 攒够一批或者流结束时通知发送线程
+    space: Condvar, // 发送线程取走数据后通知暂停中的读取线程
+}
+
+// 读取线程持有的一端，释放时等待缓冲区中剩下的行发送完
+pub struct BatchSender {
+    shared: Arc<Shared>,
+    batch_lines: usize,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    emitter: Option<JoinHandle<()>>,
+}
+
+impl BatchSender {
+    // 启动发送线程，每一批通过 event 事件发送
+    pub fn spawn(window: tauri::Window, event: &'static str, stream_id: Option<String>, options: &BatchOptions) -> Self {
+        Self::with_emitter(options, move |lines, dropped, total_dropped| {
+            let batch = LogStreamBatch {
+                stream_id: stream_id.clone(),
+                lines,
+                dropped,
+                total_dropped,
+            };
+            if let Err(e) = window.emit(event, batch) {
+                error!("Failed to emit {}: {}", event, e);
+            }
+        })
+    }
+
+    // 启动发送线程，每一批交给 emit 处理
+    fn with_emitter(options: &BatchOptions, emit: impl FnMut(Vec<LogStreamData>, u64, u64) + Send + 'static) -> Self {
+        let batch_lines = options.batch_lines.unwrap_or(DEFAULT_BATCH_LINES).max(1);
+        let interval = options.interval();
+        let shared = Arc::new(Shared {
+            state: Mutex::new(BufferState {
+                lines: VecDeque::new(),
+                dropped: 0,
+                closed: false,
+            }),
+            ready: Condvar::new(),
+            space: Condvar::new(),
+        });
+
+        let emitter_shared = shared.clone();
+        let emitter = std::thread::spawn(move || run_emitter(&emitter_shared, batch_lines, interval, emit));
+
+        BatchSender {
+            shared,
+            batch_lines,
+            capacity: options.buffer_lines.unwrap_or(DEFAULT_BUFFER_LINES).max(batch_lines),
+            overflow: options.overflow.unwrap_or_default(),
+            emitter: Some(emitter),
+        }
+    }
+
+    pub fn push(&self, data: LogStreamData) {
+        let mut state = self.shared.state.lock().unwrap();
+        while state.lines.len() >= self.capacity {
+            match self.overflow {
+                OverflowPolicy::DropOldest => {
+                    state.lines.pop_front();
+                    state.dropped += 1;
+                }
+                OverflowPolicy::Pause => state = self.shared.space.wait(state).unwrap(),
+            }
+        }
+        state.lines.push_back(data);
+        if state.lines.len() >= self.batch_lines {
+            self.shared.ready.notify_one();
+        }
+    }
+}
+
+impl Drop for BatchSender {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.ready.notify_one();
+        if let Some(emitter) = self.emitter.take() {
+            let _ = emitter.join();
+        }
+    }
+}
+
+// 发送线程：等到攒够一批、到达发送间隔或者流结束时取出数据发送，发送事件时不持有锁
+fn run_emitter(shared: &Shared, batch_lines: usize, interval: Duration, mut emit: impl FnMut(Vec<LogStreamData>, u64, u64)) {
+    let mut total_dropped = 0;
+    let mut state = shared.state.lock().unwrap();
+    loop {
+        let deadline = Instant::now() + interval;
+        while state.lines.len() < batch_lines && !state.closed {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            state = shared.ready.wait_timeout(state, deadline - now).unwrap().0;
+        }
+
+        if state.lines.is_empty() && state.dropped == 0 {
+            if state.closed {
+                break;
+            }
+            continue;
+        }
+        let count = state.lines.len().min(batch_lines);
+        let lines: Vec<LogStreamData> = state.lines.drain(..count).collect();
+        let dropped = std::mem::take(&mut state.dropped);
+        shared.space.notify_all();
+        drop(state);
+
+        if dropped > 0 {
+            total_dropped += dropped;
+            warn!("实时日志缓冲区已满，丢弃了 {} 行（共 {} 行）", dropped, total_dropped);
+        }
+        emit(lines, dropped, total_dropped);
+        state = shared.state.lock().unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
+    type Batch = (Vec<String>, u64, u64);
+
+    fn line(content: &str) -> LogStreamData {
+        LogStreamData::new_line(content.to_string(), "remote", None)
+    }
+
+    fn options(batch_lines: usize, buffer_lines: usize, overflow: OverflowPolicy) -> BatchOptions {
+        BatchOptions {
+            batch_lines: Some(batch_lines),
+            batch_interval_ms: Some(60_000),
+            buffer_lines: Some(buffer_lines),
+            overflow: Some(overflow),
+        }
+    }
+
+    // 每发送一批都等 gate 放行，测试可以在发送线程被前端“卡住”时往缓冲区写入
+    fn gated_sender(options: &BatchOptions) -> (BatchSender, mpsc::Receiver<Batch>, mpsc::Sender<()>) {
+        let (batches, received) = mpsc::channel();
+        let (gate, gate_rx) = mpsc::channel::<()>();
+        let sender = BatchSender::with_emitter(options, move |lines, dropped, total_dropped| {
+            let lines = lines.into_iter().map(|l| l.content).collect();
+            let _ = batches.send((lines, dropped, total_dropped));
+            let _ = gate_rx.recv();
+        });
+        (sender, received, gate)
+    }
+
+    fn push_all(sender: &BatchSender, contents: &[&str]) {
+        for content in contents {
+            sender.push(line(content));
+        }
+    }
+
+    #[test]
+    fn drop_oldest_reports_dropped_lines() {
+        let (sender, batches, gate) = gated_sender(&options(3, 3, OverflowPolicy::DropOldest));
+        push_all(&sender, &["1", "2", "3"]);
+        assert_eq!(batches.recv().unwrap(), (vec!["1".into(), "2".into(), "3".into()], 0, 0));
+
+        // 发送线程卡住时缓冲区只保留最新的3行
+        push_all(&sender, &["4", "5", "6", "7", "8"]);
+        gate.send(()).unwrap();
+        assert_eq!(batches.recv().unwrap(), (vec!["6".into(), "7".into(), "8".into()], 2, 2));
+
+        push_all(&sender, &["9", "10", "11", "12"]);
+        gate.send(()).unwrap();
+        assert_eq!(batches.recv().unwrap(), (vec!["10".into(), "11".into(), "12".into()], 1, 3));
+
+        drop(gate);
+        drop(sender);
+        assert!(batches.try_iter().next().is_none());
+    }
+
+    #[test]
+    fn pause_blocks_the_reader_until_there_is_space() {
+        let (sender, batches, gate) = gated_sender(&options(2, 2, OverflowPolicy::Pause));
+        push_all(&sender, &["1", "2"]);
+        assert_eq!(batches.recv().unwrap(), (vec!["1".into(), "2".into()], 0, 0));
+
+        let pushed = AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for content in ["3", "4", "5"] {
+                    sender.push(line(content));
+                    pushed.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+            std::thread::sleep(Duration::from_millis(200));
+            assert_eq!(pushed.load(Ordering::SeqCst), 2);
+            gate.send(()).unwrap();
+        });
+        assert_eq!(pushed.load(Ordering::SeqCst), 3);
+        assert_eq!(batches.recv().unwrap(), (vec!["3".into(), "4".into()], 0, 0));
+
+        // 流结束时发送剩下的行，暂停模式下不会丢弃任何行
+        drop(gate);
+        drop(sender);
+        assert_eq!(batches.recv().unwrap(), (vec!["5".into()], 0, 0));
+        assert!(batches.try_iter().next().is_none());
+    }
+
+    #[test]
+    fn interval_is_clamped() {
+        let zero = BatchOptions {
+            batch_interval_ms: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(zero.interval(), MIN_BATCH_INTERVAL);
+        assert_eq!(BatchOptions::default().interval(), DEFAULT_BATCH_INTERVAL);
+
+        // 不满一批的行到达发送间隔后发送
+        let (sender, batches, gate) = gated_sender(&zero);
+        sender.push(line("1"));
+        let batch = batches.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(batch, (vec!["1".into()], 0, 0));
+        drop(gate);
+        drop(sender);
+    }
+}
@@ -0,0 +1,554 @@
+// 按时间范围过滤日志：日志按时间顺序写入时，先在字节偏移上二分查找起始位置，
+// 再从该位置顺序读取到结束时间为止，不需要从文件开头扫描；
+// 没有时间戳的行（例如异常堆栈）归属于它前面最近的带时间戳的行
+use crate::encoding::{self, DETECT_SAMPLE_SIZE};
+use crate::error::EasyLogError;
+use crate::file_index::LineTerminator;
+use crate::parser::{leading_timestamp, parse_timestamp, RecordParser};
+use crate::search::LogSource;
+use crate::remote_command::{self, Program};
+use crate::{compression, ssh, SshCredentials};
+use encoding_rs::Encoding;
+use lazy_static::lazy_static;
+use log::info;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+// 二分查找时每次探测最多读取的字节数，超过这个长度仍没有找到带时间戳的行就当作没有找到
+const PROBE_LIMIT: u64 = 1024 * 1024;
+// 默认最多返回的行数
+const DEFAULT_MAX_LINES: usize = 100_000;
+
+#[derive(Debug, Deserialize)]
+pub struct TimeRangeOptions {
+    start_time: Option<String>, // 可选参数，开始时间（包含），为空表示不限制
+    end_time: Option<String>,   // 可选参数，结束时间（包含），为空表示不限制
+    max_lines: Option<usize>,   // 可选参数，最多返回的行数
+    encoding: Option<String>,   // 可选参数，指定日志编码
+    parse: Option<String>,      // 可选参数，识别时间戳使用的日志格式，默认自动识别
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TimeRangeContent {
+    content: String,
+    line_count: usize,
+    start_byte: u64, // 第一行在文件中的字节偏移
+    end_byte: u64,   // 最后一行结束处的字节偏移
+    encoding: String,
+    truncated: bool, // 是否因为达到max_lines而提前结束
+}
+
+const SECOND_MS: i64 = 1000;
+const DAY_MS: i64 = 24 * 60 * 60 * SECOND_MS;
+
+lazy_static! {
+    // 秒后面的小数部分
+    static ref FRACTION: Regex = Regex::new(r":\d{2}[.,](\d+)").unwrap();
+}
+
+// 解析后的时间参数：millis为时间点，precision为输入精确到的毫秒数（只有日期时为一天）
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bound {
+    millis: i64,
+    precision: i64,
+}
+
+impl Bound {
+    // 结束时间包含输入精度内的所有时间，例如 03:04:05 包含 03:04:05.999
+    fn last_millis(&self) -> i64 {
+        self.millis + self.precision - 1
+    }
+}
+
+// 时间参数支持日志中常见的时间格式（包括 20240102、20240102030405 这样的紧凑格式），
+// 也支持Unix时间戳：13位及以上按毫秒处理，10到12位按秒处理
+fn parse_bound(value: Option<&str>, field: &str) -> Result<Option<Bound>, EasyLogError> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+    let invalid = || EasyLogError::invalid(field, format!("无法识别的时间: {}", value));
+
+    if value.bytes().all(|b| b.is_ascii_digit()) && !matches!(value.len(), 8 | 14) {
+        let number: i64 = value.parse().map_err(|_| invalid())?;
+        let bound = match number {
+            1_000_000_000_000.. => Bound {
+                millis: number,
+                precision: 1,
+            },
+            1_000_000_000.. => Bound {
+                millis: number * SECOND_MS,
+                precision: SECOND_MS,
+            },
+            _ => return Err(invalid()),
+        };
+        return Ok(Some(bound));
+    }
+
+    let millis = parse_timestamp(value).ok_or_else(invalid)?;
+    // 只有日期：2024-01-02 或 20240102
+    let precision = if !value.contains(':') && value.len() <= 10 {
+        DAY_MS
+    } else if let Some(fraction) = FRACTION.captures(value).and_then(|c| c.get(1)) {
+        10i64.pow(3u32.saturating_sub(fraction.len() as u32))
+    } else {
+        SECOND_MS
+    };
+    Ok(Some(Bound { millis, precision }))
+}
+
+fn read_failed(e: std::io::Error) -> EasyLogError {
+    EasyLogError::internal(format!("读取日志内容失败: {}", e))
+}
+
+// 可以从任意偏移开始读取的数据源
+trait ByteSource {
+    // 数据总长度，无法获取时为None（此时不做二分查找）
+    fn size(&self) -> Option<u64>;
+    // 从offset开始读取，最多读取limit个字节
+    fn open_at(&mut self, offset: u64, limit: Option<u64>) -> Result<Box<dyn Read + '_>, EasyLogError>;
+}
+
+struct LocalSource {
+    file: File,
+    size: u64,
+}
+
+impl LocalSource {
+    fn open(path: &str) -> Result<Self, EasyLogError> {
+        let path = std::path::Path::new(path);
+        if !path.exists() {
+            return Err(EasyLogError::not_found(path));
+        }
+        // 压缩文件先解压到缓存目录，解压后的文件同样可以随机访问
+        let path = compression::materialize(path).map_err(|e| EasyLogError::io(path, e))?;
+        let file = File::open(&path).map_err(|e| EasyLogError::io(&path, e))?;
+        let size = file.metadata().map_err(|e| EasyLogError::io(&path, e))?.len();
+        Ok(LocalSource { file, size })
+    }
+}
+
+impl ByteSource for LocalSource {
+    fn size(&self) -> Option<u64> {
+        Some(self.size)
+    }
+
+    fn open_at(&mut self, offset: u64, limit: Option<u64>) -> Result<Box<dyn Read + '_>, EasyLogError> {
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .map_err(read_failed)?;
+        Ok(Box::new((&mut self.file).take(limit.unwrap_or(u64::MAX))))
+    }
+}
+
+// 远程文件通过 tail -c 从指定偏移读取；压缩文件无法随机访问，只能解压后从头读取
+struct RemoteSource {
+    credentials: SshCredentials,
+    path: String,
+    compressed: bool,
+    size: Option<u64>,
+}
+
+impl RemoteSource {
+    fn open(credentials: SshCredentials, path: &str) -> Result<Self, EasyLogError> {
+        let compressed = compression::is_compressed_path(path);
+        let size = if compressed {
+            None
+        } else {
+            let output = ssh::exec_output(&credentials, &remote_command::file_size(path))?;
+            Some(remote_command::parse_size(&output)?)
+        };
+        Ok(RemoteSource {
+            credentials,
+            path: path.to_string(),
+            compressed,
+            size,
+        })
+    }
+}
+
+impl ByteSource for RemoteSource {
+    fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    fn open_at(&mut self, offset: u64, limit: Option<u64>) -> Result<Box<dyn Read + '_>, EasyLogError> {
+        let command = match (self.compressed, limit) {
+            (true, _) => remote_command::cat(&self.path),
+            (false, None) => remote_command::tail_from(&self.path, offset, false),
+            (false, Some(limit)) => remote_command::tail_from(&self.path, offset, false)
+                .pipe(Program::Head)
+                .arg("-c")
+                .arg(limit.to_string()),
+        };
+
+        // 每次探测都在会话池中的同一个会话上打开新的通道
+        let channel = ssh::exec(&self.credentials, &command)?;
+
+        if !self.compressed {
+            return Ok(Box::new(channel));
+        }
+        let (mut reader, _) = compression::decompress_stream(channel).map_err(read_failed)?;
+        std::io::copy(&mut (&mut reader).take(offset), &mut std::io::sink()).map_err(read_failed)?;
+        Ok(Box::new(reader.take(limit.unwrap_or(u64::MAX))))
+    }
+}
+
+fn line_timestamp(parser: &mut RecordParser, line: &str) -> Option<i64> {
+    parser
+        .parse(0, line)
+        .timestamp_ms
+        .or_else(|| leading_timestamp(line).and_then(parse_timestamp))
+}
+
+// 一行完整内容的位置和时间戳
+struct TimedLine {
+    next_offset: u64, // 下一行的起始偏移
+    timestamp: i64,
+}
+
+struct RangeReader<S: ByteSource> {
+    source: S,
+    encoding: &'static Encoding,
+    terminator: LineTerminator,
+    bom_len: u64,
+    parser: RecordParser,
+}
+
+impl<S: ByteSource> RangeReader<S> {
+    fn new(mut source: S, encoding: Option<&str>, parse: Option<&str>) -> Result<Self, EasyLogError> {
+        let mut sample = Vec::new();
+        source
+            .open_at(0, Some(DETECT_SAMPLE_SIZE as u64))?
+            .read_to_end(&mut sample)
+            .map_err(read_failed)?;
+        let detected = encoding::resolve_encoding(encoding, &sample)?;
+
+        Ok(RangeReader {
+            source,
+            encoding: detected.encoding,
+            terminator: LineTerminator::for_encoding(detected.encoding),
+            bom_len: detected.bom_len as u64,
+            parser: RecordParser::new(parse.unwrap_or("auto"))?,
+        })
+    }
+
+    // 找到offset之后第一条带时间戳的完整行；offset不在行首时先跳过不完整的第一行
+    fn probe(&mut self, offset: u64, at_line_start: bool) -> Result<Option<TimedLine>, EasyLogError> {
+        let size = self.source.size();
+        let terminator = self.terminator;
+        let encoding = self.encoding;
+        let mut reader = BufReader::new(self.source.open_at(offset, Some(PROBE_LIMIT))?);
+        let mut position = offset;
+        let mut line = Vec::new();
+        let mut skip = !at_line_start;
+
+        loop {
+            line.clear();
+            let n = terminator
+                .read_line(&mut reader, &mut line)
+                .map_err(read_failed)?;
+            if n == 0 {
+                break;
+            }
+            position += n as u64;
+            // 被读取上限截断的行不能作为结果
+            let complete = terminator.trim(&line).len() < line.len() || Some(position) == size;
+            if !complete {
+                break;
+            }
+            if skip {
+                skip = false;
+                continue;
+            }
+            let text = encoding::decode_lossy(encoding, terminator.trim(&line));
+            if let Some(timestamp) = line_timestamp(&mut self.parser, &text) {
+                return Ok(Some(TimedLine {
+                    next_offset: position,
+                    timestamp,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    // 二分查找起始偏移：返回的位置一定在行首，并且它之前所有带时间戳的行都早于start
+    fn find_start(&mut self, start: i64) -> Result<u64, EasyLogError> {
+        let Some(size) = self.source.size() else {
+            return Ok(self.bom_len);
+        };
+        let unit = self.terminator.unit_len() as u64;
+        let (mut low, mut high) = (self.bom_len, size);
+
+        while low < high {
+            let mid = low + (high - low) / 2 / unit * unit;
+            // 从mid的前一个编码单元开始跳过不完整的行，这样mid恰好在行首时，这一行同样会被检查
+            let at = mid.saturating_sub(unit).max(low);
+            match self.probe(at, at == low)? {
+                Some(line) if line.timestamp < start => low = line.next_offset,
+                _ => high = mid,
+            }
+        }
+        Ok(low)
+    }
+
+    // 从offset开始顺序读取，收集时间在范围内的行，遇到晚于end的行时结束
+    fn collect(&mut self, offset: u64, start: Option<i64>, end: Option<i64>, max_lines: usize) -> Result<TimeRangeContent, EasyLogError> {
+        let terminator = self.terminator;
+        let encoding = self.encoding;
+        let mut result = TimeRangeContent {
+            content: String::new(),
+            line_count: 0,
+            start_byte: offset,
+            end_byte: offset,
+            encoding: encoding.name().to_string(),
+            truncated: false,
+        };
+
+        let mut reader = BufReader::new(self.source.open_at(offset, None)?);
+        let mut position = offset;
+        let mut line = Vec::new();
+        // 当前所在记录的时间戳，没有时间戳的行沿用它
+        let mut current: Option<i64> = None;
+
+        loop {
+            line.clear();
+            let n = terminator
+                .read_line(&mut reader, &mut line)
+                .map_err(read_failed)?;
+            if n == 0 {
+                break;
+            }
+            let line_start = position;
+            position += n as u64;
+
+            let text = encoding::decode_lossy(encoding, terminator.trim(&line));
+            if let Some(timestamp) = line_timestamp(&mut self.parser, &text) {
+                if end.is_some_and(|end| timestamp > end) {
+                    break;
+                }
+                current = Some(timestamp);
+            }
+
+            // 还没遇到带时间戳的行时，只有不限制开始时间才保留
+            let in_range = match current {
+                Some(timestamp) => timestamp >= start.unwrap_or(i64::MIN),
+                None => start.is_none(),
+            };
+            if !in_range {
+                continue;
+            }
+
+            if result.line_count >= max_lines {
+                result.truncated = true;
+                break;
+            }
+            if result.line_count == 0 {
+                result.start_byte = line_start;
+            }
+            result.content.push_str(&text);
+            result.content.push('\n');
+            result.line_count += 1;
+            result.end_byte = position;
+        }
+
+        Ok(result)
+    }
+}
+
+fn read_range<S: ByteSource>(source: S, options: &TimeRangeOptions) -> Result<TimeRangeContent, EasyLogError> {
+    let start = parse_bound(options.start_time.as_deref(), "start_time")?.map(|bound| bound.millis);
+    let end = parse_bound(options.end_time.as_deref(), "end_time")?.map(|bound| bound.last_millis());
+    if let (Some(start), Some(end)) = (start, end) {
+        if start > end {
+            return Err(EasyLogError::invalid("end_time", "开始时间不能晚于结束时间"));
+        }
+    }
+
+    let mut reader = RangeReader::new(source, options.encoding.as_deref(), options.parse.as_deref())?;
+    let offset = match start {
+        Some(start) => reader.find_start(start)?,
+        None => reader.bom_len,
+    };
+    reader.collect(offset, start, end, options.max_lines.unwrap_or(DEFAULT_MAX_LINES))
+}
+
+// 读取时间范围内的日志行，本地和远程文件都先二分查找起始位置
+#[tauri::command]
+pub async fn read_time_range(source: LogSource, options: TimeRangeOptions) -> Result<TimeRangeContent, EasyLogError> {
+    info!(
+        "Reading {} in time range {:?} - {:?}",
+        source.path(),
+        options.start_time,
+        options.end_time
+    );
+
+    tokio::task::spawn_blocking(move || match &source {
+        LogSource::Local { path } => read_range(LocalSource::open(path)?, &options),
+        LogSource::Remote { credentials, path } => {
+            read_range(RemoteSource::open(credentials.resolve()?, path)?, &options)
+        }
+    })
+    .await
+    .map_err(|e| EasyLogError::internal(format!("按时间范围读取失败: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    struct MemorySource(Vec<u8>);
+
+    impl ByteSource for MemorySource {
+        fn size(&self) -> Option<u64> {
+            Some(self.0.len() as u64)
+        }
+
+        fn open_at(&mut self, offset: u64, limit: Option<u64>) -> Result<Box<dyn Read + '_>, EasyLogError> {
+            let mut cursor = Cursor::new(&self.0[..]);
+            cursor.set_position(offset);
+            Ok(Box::new(cursor.take(limit.unwrap_or(u64::MAX))))
+        }
+    }
+
+    // 2024-01-02T03:04:00Z 之后第n秒
+    fn millis(second: i64) -> i64 {
+        1_704_164_640_000 + second * 1000
+    }
+
+    fn line(second: i64, message: &str) -> String {
+        format!("2024-01-02T03:04:{:02}Z INFO app - {}", second, message)
+    }
+
+    fn reader(lines: &[String], newline: &str) -> RangeReader<MemorySource> {
+        let mut text = lines.join(newline);
+        text.push_str(newline);
+        RangeReader::new(MemorySource(text.into_bytes()), None, None).unwrap()
+    }
+
+    fn collect_range(lines: &[String], newline: &str, start: i64, end: i64) -> Vec<String> {
+        let mut reader = reader(lines, newline);
+        let offset = reader.find_start(start).unwrap();
+        let result = reader.collect(offset, Some(start), Some(end), 1000).unwrap();
+        result.content.lines().map(str::to_string).collect()
+    }
+
+    fn sample_lines() -> Vec<String> {
+        let mut lines = Vec::new();
+        for second in 0..40 {
+            lines.push(line(second, &format!("event {}", second)));
+            if second % 3 == 0 {
+                // 没有时间戳的续行归属于前面的行
+                lines.push(format!("    at frame {}", second));
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn find_start_lands_on_first_line_in_range() {
+        let lines = sample_lines();
+        let found = collect_range(&lines, "\n", millis(10), millis(12));
+        assert_eq!(found.first().unwrap(), &line(10, "event 10"));
+        assert_eq!(found.last().unwrap(), "    at frame 12");
+        assert_eq!(found.len(), 4);
+    }
+
+    #[test]
+    fn start_on_untimestamped_line_keeps_its_record() {
+        let lines = sample_lines();
+        // 从第9秒开始，第9秒的续行也应包含在内
+        let found = collect_range(&lines, "\n", millis(9), millis(9));
+        assert_eq!(found, [line(9, "event 9"), "    at frame 9".to_string()]);
+    }
+
+    #[test]
+    fn range_before_first_and_after_last_line() {
+        let lines = sample_lines();
+        let found = collect_range(&lines, "\n", millis(-100), millis(1));
+        assert_eq!(found[0], line(0, "event 0"));
+        assert_eq!(found.len(), 3);
+
+        let found = collect_range(&lines, "\n", millis(39), millis(100));
+        assert_eq!(found, [line(39, "event 39"), "    at frame 39".to_string()]);
+
+        assert!(collect_range(&lines, "\n", millis(100), millis(200)).is_empty());
+    }
+
+    #[test]
+    fn leading_lines_without_timestamps() {
+        let mut lines = vec!["header without time".to_string(), "second header".to_string()];
+        lines.extend(sample_lines());
+        let found = collect_range(&lines, "\n", millis(0), millis(0));
+        assert_eq!(found, [line(0, "event 0"), "    at frame 0".to_string()]);
+
+        // 不限制开始时间时保留开头没有时间戳的行
+        let mut reader = reader(&lines, "\n");
+        let result = reader.collect(0, None, Some(millis(0)), 1000).unwrap();
+        assert_eq!(result.line_count, 4);
+    }
+
+    #[test]
+    fn crlf_lines() {
+        let lines = sample_lines();
+        let found = collect_range(&lines, "\r\n", millis(20), millis(21));
+        assert_eq!(found, [line(20, "event 20"), line(21, "event 21"), "    at frame 21".to_string()]);
+    }
+
+    #[test]
+    fn find_start_returns_each_line_offset() {
+        // 行长度各不相同，二分查找的探测位置会落在行首、行中和换行符上
+        let lines: Vec<String> = (0..50).map(|s| line(s, &"x".repeat((s * 7 % 11) as usize))).collect();
+        for newline in ["\n", "\r\n"] {
+            let mut offsets = vec![0u64];
+            for text in &lines {
+                offsets.push(offsets.last().unwrap() + (text.len() + newline.len()) as u64);
+            }
+            let mut reader = reader(&lines, newline);
+            for second in 0..=50 {
+                assert_eq!(reader.find_start(millis(second)).unwrap(), offsets[second as usize]);
+            }
+        }
+    }
+
+    #[test]
+    fn parse_bound_formats() {
+        let bound = |value: &str| parse_bound(Some(value), "t").unwrap().unwrap();
+
+        assert_eq!(bound("1704164645123"), Bound { millis: 1_704_164_645_123, precision: 1 });
+        assert_eq!(bound("1704164645"), Bound { millis: 1_704_164_645_000, precision: 1000 });
+        assert_eq!(bound("2024-01-02T03:04:05Z"), Bound { millis: 1_704_164_645_000, precision: 1000 });
+        assert_eq!(bound("2024-01-02T03:04:05.5Z").precision, 100);
+        assert_eq!(bound("2024-01-02T03:04:05,123Z").precision, 1);
+        assert_eq!(bound("20240102").millis, parse_timestamp("2024-01-02").unwrap());
+        assert_eq!(bound("20240102").precision, DAY_MS);
+        assert_eq!(bound("2024-01-02").precision, DAY_MS);
+        assert_eq!(bound("20240102030405").precision, 1000);
+
+        assert!(parse_bound(Some("  "), "t").unwrap().is_none());
+        assert!(parse_bound(Some("12345"), "t").is_err());
+        assert!(parse_bound(Some("yesterday"), "t").is_err());
+    }
+
+    #[test]
+    fn end_bound_includes_typed_precision() {
+        let lines = [
+            "2024-01-02T03:04:05.000Z INFO app - a",
+            "2024-01-02T03:04:05.999Z INFO app - b",
+            "2024-01-02T03:04:06.000Z INFO app - c",
+        ];
+        let options = TimeRangeOptions {
+            start_time: Some("2024-01-02T03:04:05Z".to_string()),
+            end_time: Some("2024-01-02T03:04:05Z".to_string()),
+            max_lines: None,
+            encoding: None,
+            parse: None,
+        };
+        let text = lines.join("\n") + "\n";
+        let result = read_range(MemorySource(text.into_bytes()), &options).unwrap();
+        assert_eq!(result.line_count, 2);
+        assert!(result.content.ends_with("- b\n"));
+    }
+}
@@ -0,0 +1,167 @@
+// 跳板机隧道：在跳板机的会话上打开到目标主机的direct-tcpip通道。
+// libssh2的会话只能运行在真实的套接字上，所以在本地建立一对回环连接，
+// 一端交给目标主机的会话，另一端由后台线程与通道之间互相转发数据。
+// 两个方向都没有进展时在REACTOR上等待回环套接字或跳板机会话的socket就绪，而不是定时轮询
+use crate::error::EasyLogError;
+use crate::ssh::{self, SessionWaker};
+use log::{info, warn};
+use ssh2::{BlockDirections, Channel, Session};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::time::Duration;
+
+// 没有收到就绪事件时最多等待这么久再检查一次，套接字无法注册到REACTOR时依靠它继续转发
+const IDLE_WAKE: Duration = Duration::from_millis(250);
+// 隧道结束时关闭通道和跳板机会话最多等待的毫秒数
+const CLOSE_TIMEOUT_MS: u32 = 2000;
+const BUFFER_SIZE: usize = 32 * 1024;
+
+// 建立一对互相连接的本地套接字
+fn loopback_pair() -> std::io::Result<(TcpStream, TcpStream)> {
+    let listener = TcpListener::bind(("127.0.0.1", 0))?;
+    let local = TcpStream::connect(listener.local_addr()?)?;
+    let (remote, peer) = listener.accept()?;
+    // 确认接受的是自己发起的连接，而不是其它进程抢先连上来的
+    if peer != local.local_addr()? {
+        return Err(std::io::Error::new(ErrorKind::PermissionDenied, "unexpected loopback peer"));
+    }
+    Ok((local, remote))
+}
+
+// 通过已认证的跳板机会话连接到目标主机，返回可以交给下一个会话使用的本地套接字；
+// jump_socket是跳板机会话socket的副本，用于等待通道上的新数据
+pub fn open(jump: Session, jump_socket: TcpStream, host: &str, port: u16) -> Result<TcpStream, EasyLogError> {
+    let channel = jump
+        .channel_direct_tcpip(host, port, None)
+        .map_err(|e| EasyLogError::ConnectionFailed {
+            host: host.to_string(),
+            port,
+            reason: format!("无法通过跳板机连接: {}", e),
+        })?;
+    let (local, remote) = loopback_pair().map_err(|e| EasyLogError::internal(format!("创建本地隧道失败: {}", e)))?;
+    remote
+        .set_nonblocking(true)
+        .map_err(|e| EasyLogError::internal(format!("创建本地隧道失败: {}", e)))?;
+    let _ = remote.set_nodelay(true);
+    let _ = local.set_nodelay(true);
+    let remote_waker = remote
+        .try_clone()
+        .map(SessionWaker::new)
+        .map_err(|e| EasyLogError::internal(format!("创建本地隧道失败: {}", e)))?;
+
+    jump.set_blocking(false);
+    let target = format!("{}:{}", host, port);
+    std::thread::spawn(move || {
+        let wakers = TunnelWakers {
+            jump: SessionWaker::new(jump_socket),
+            socket: remote_waker,
+        };
+        pump(&jump, channel, remote, &wakers, &target);
+        info!("Tunnel to {} closed", target);
+    });
+    Ok(local)
+}
+
+// 写入尽可能多的数据，返回是否有进展；对端关闭或出错时返回Err
+fn flush(pending: &mut Vec<u8>, writer: &mut impl Write) -> std::io::Result<bool> {
+    if pending.is_empty() {
+        return Ok(false);
+    }
+    match writer.write(pending) {
+        Ok(0) => Err(ErrorKind::WriteZero.into()),
+        Ok(n) => {
+            pending.drain(..n);
+            Ok(true)
+        }
+        Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+// 上次读取的数据都已转发时再读取一块追加到pending，返回读取的字节数（0表示对端已关闭），暂时没有数据时返回None
+fn fill(pending: &mut Vec<u8>, reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<Option<usize>> {
+    if !pending.is_empty() {
+        return Ok(None);
+    }
+    match reader.read(buffer) {
+        Ok(n) => {
+            pending.extend_from_slice(&buffer[..n]);
+            Ok(Some(n))
+        }
+        Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// 隧道两端的就绪通知：跳板机会话的socket和转发给目标会话的回环套接字
+struct TunnelWakers {
+    jump: SessionWaker,
+    socket: SessionWaker,
+}
+
+impl TunnelWakers {
+    // 在读写之前清除就绪状态，读写之后到达的事件会重新唤醒等待
+    fn clear(&self) {
+        self.jump.clear_readable();
+        self.jump.clear_writable();
+        self.socket.clear_readable();
+        self.socket.clear_writable();
+    }
+
+    // 等待可能让转发继续的事件：跳板机会话收到数据（通道数据或窗口调整），libssh2需要发送时会话socket可写，
+    // 目标会话有数据要发送时回环套接字可读，还有数据没有转发给目标会话时回环套接字可写
+    fn wait(&self, jump: &Session, to_channel: &[u8], to_socket: &[u8]) {
+        let outbound = matches!(jump.block_directions(), BlockDirections::Outbound | BlockDirections::Both);
+        ssh::block_on(async {
+            tokio::select! {
+                _ = self.jump.readable() => {}
+                _ = self.jump.writable(), if outbound => {}
+                _ = self.socket.readable(), if to_channel.is_empty() => {}
+                _ = self.socket.writable(), if !to_socket.is_empty() => {}
+                _ = tokio::time::sleep(IDLE_WAKE) => {}
+            }
+        });
+    }
+}
+
+// 在本地套接字和通道之间转发数据，任意一端关闭后结束，并断开跳板机会话
+fn pump(jump: &Session, mut channel: Channel, mut socket: TcpStream, wakers: &TunnelWakers, target: &str) {
+    let mut to_channel = Vec::new();
+    let mut to_socket = Vec::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+
+    let result: std::io::Result<()> = (|| loop {
+        let mut progressed = false;
+        wakers.clear();
+
+        // 目标会话 -> 跳板机通道
+        match fill(&mut to_channel, &mut socket, &mut buffer)? {
+            Some(0) => return Ok(()), // 目标主机的会话已经关闭
+            Some(_) => progressed = true,
+            None => {}
+        }
+        progressed |= flush(&mut to_channel, &mut channel)?;
+
+        // 跳板机通道 -> 目标会话
+        match fill(&mut to_socket, &mut channel, &mut buffer)? {
+            Some(0) if channel.eof() => return Ok(()),
+            Some(0) | None => {}
+            Some(_) => progressed = true,
+        }
+        progressed |= flush(&mut to_socket, &mut socket)?;
+
+        if !progressed {
+            wakers.wait(jump, &to_channel, &to_socket);
+        }
+    })();
+
+    if let Err(e) = result {
+        warn!("Tunnel to {} failed: {}", target, e);
+    }
+    let _ = socket.shutdown(Shutdown::Both);
+
+    jump.set_blocking(true);
+    jump.set_timeout(CLOSE_TIMEOUT_MS);
+    let _ = channel.close();
+    let _ = jump.disconnect(None, "tunnel closed", None);
+}
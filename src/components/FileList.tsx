@@ -10,6 +10,7 @@ import {
     IconFileZip
 } from '@tabler/icons-react';
 import {open as openDialog} from '@tauri-apps/plugin-dialog';
+import {formatError} from '../utils/errors';
 import {useFileHandler} from '../hooks/useFileHandler';
 import {invoke} from '@tauri-apps/api/core';
 import {useLogContentStore} from '../stores/logContentStore';
@@ -118,7 +119,7 @@ export default function FileList() {
             }
         } catch (error) {
             console.error('Error opening file:', error);
-            await logToBackend('error', t('fileList.errors.readError', { error }));
+            await logToBackend('error', t('fileList.errors.readError', { error: formatError(error) }));
             // 这里可以添加错误提示UI
         }
     };
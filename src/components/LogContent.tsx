@@ -11,6 +11,7 @@ import { useLogSettingsStore } from '../stores/logSettingsStore';
 import { useLogFormatStore } from '../stores/logFormatStore';
 import { LogEntry } from '../types/log';
 import { highlightText } from '../utils/textHighlight';
+import { formatError } from '../utils/errors';
 import SearchNavigation from './SearchNavigation';
 import { useViewportSize } from '@mantine/hooks';
 import TextSelectionPopover from './TextSelectionPopover';
@@ -194,7 +195,7 @@ export default function LogContent() {
       console.error('处理文件失败:', error);
       notifications.show({
         title: t('logContent.errors.fileLoadFailed'),
-        message: error.message || String(error),
+        message: formatError(error),
         color: 'red',
       });
     }
@@ -22,6 +22,7 @@ import { useLogContentStore } from '../stores/logContentStore';
 import { PasswordInput } from '@mantine/core';
 import { RemoteLog, useRemoteLogHandler } from '../hooks/useRemoteLogHandler';
 import { useRemoteLogStore } from '../stores/remoteLogStore';
+import { formatError } from '../utils/errors';
 
 export default function RemoteLogsPanel() {
   const { isDark } = useThemeStore();
@@ -131,13 +132,13 @@ export default function RemoteLogsPanel() {
         name: newLog.name,
         type: newLog.type as RemoteLog['type'],
         host: newLog.host,
-        port: newLog.port || 22,
+        port: newLog.port,  // 留空时使用 ~/.ssh/config 中的端口，默认22
         status: 'disconnected',
         // SSH连接特有字段
         ...(newLog.type === 'ssh' ? {
           username: newLog.username || '',
           authType: newLog.authType || 'password',  // 默认使用password模式
-          password: newLog.authType === 'password' ? newLog.password : undefined,
+          password: newLog.authType === 'password' || newLog.authType === 'keyboard_interactive' ? newLog.password : undefined,
           privateKeyPath: newLog.authType === 'key' ? newLog.privateKeyPath : undefined,
           passphrase: newLog.authType === 'key' ? newLog.passphrase : undefined,
           logFilePath: newLog.logFilePath || '/var/log/syslog'
@@ -183,10 +184,10 @@ export default function RemoteLogsPanel() {
       setNotification({ type: 'success', message: t('remoteLogs.notification.connected', { name: log.name }) });
     } catch (error) {
       console.error('SSH connection error:', error);
-      setNotification({ type: 'error', message: String(error) });
+      setNotification({ type: 'error', message: formatError(error) });
       
       // 更新日志状态为错误
-      updateLogStatus(log.id, 'error', String(error));
+      updateLogStatus(log.id, 'error', formatError(error));
     }
   };
   
@@ -211,7 +212,7 @@ export default function RemoteLogsPanel() {
       setNotification({ type: 'info', message: t('remoteLogs.notification.disconnected', { name: log.name }) });
     } catch (error) {
       console.error('SSH disconnection error:', error);
-      setNotification({ type: 'error', message: String(error) });
+      setNotification({ type: 'error', message: formatError(error) });
     }
   };
   
@@ -565,20 +566,22 @@ export default function RemoteLogsPanel() {
                         data={[
                           { value: 'password', label: t('remoteLogs.form.passwordAuth') },
                           { value: 'key', label: t('remoteLogs.form.keyAuth') },
+                          { value: 'agent', label: t('remoteLogs.form.agentAuth') },
+                          { value: 'keyboard_interactive', label: t('remoteLogs.form.keyboardInteractiveAuth') },
                         ]}
                         value={newLog.authType || 'password'}
-                        onChange={(value) => setNewLog({ ...newLog, authType: value as 'password' | 'key' })}
+                        onChange={(value) => setNewLog({ ...newLog, authType: value as RemoteLog['authType'] })}
                         required
                       />
-                      {newLog.authType === 'password' ? (
+                      {newLog.authType === 'password' || newLog.authType === 'keyboard_interactive' ? (
                         <PasswordInput
                           label={t('remoteLogs.form.password')}
                           placeholder={t('remoteLogs.form.inputPassword')}
                           value={newLog.password || ''}
                           onChange={(e) => setNewLog({ ...newLog, password: e.target.value })}
-                          required
+                          required={newLog.authType === 'password'}
                         />
-                      ) : (
+                      ) : newLog.authType === 'key' && (
                         <>
                           <TextInput
                             label={t('remoteLogs.form.privateKeyPath')}
@@ -713,12 +716,14 @@ export default function RemoteLogsPanel() {
                   data={[
                     { value: 'password', label: t('remoteLogs.form.passwordAuth') },
                     { value: 'key', label: t('remoteLogs.form.keyAuth') },
+                    { value: 'agent', label: t('remoteLogs.form.agentAuth') },
+                    { value: 'keyboard_interactive', label: t('remoteLogs.form.keyboardInteractiveAuth') },
                   ]}
                   value={editingLog.authType || 'password'}
-                  onChange={(value) => setEditingLog({ ...editingLog, authType: value as 'password' | 'key' })}
+                  onChange={(value) => setEditingLog({ ...editingLog, authType: value as RemoteLog['authType'] })}
                   required
                 />
-                {editingLog.authType === 'password' ? (
+                {editingLog.authType === 'password' || editingLog.authType === 'keyboard_interactive' ? (
                   <PasswordInput
                     label={t('remoteLogs.form.password')}
                     type="password"
@@ -726,7 +731,7 @@ export default function RemoteLogsPanel() {
                     value={editingLog.password || ''}
                     onChange={(e) => setEditingLog({ ...editingLog, password: e.target.value })}
                   />
-                ) : (
+                ) : editingLog.authType === 'key' && (
                   <>
                     <TextInput
                       label={t('remoteLogs.form.privateKeyPath')}
@@ -2,6 +2,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { useState } from 'react';
 import { notifications } from '@mantine/notifications';
 import { isTauri } from '../utils/environment';
+import { formatError } from '../utils/errors';
 
 interface FileContent {
   content: string;
@@ -45,7 +46,7 @@ export function useFileHandler() {
       console.error('Detailed file read error:', error);
       notifications.show({
         title: '读取文件失败',
-        message: error instanceof Error ? error.message : '未知错误',
+        message: formatError(error),
         color: 'red',
       });
     } finally {
@@ -5,12 +5,13 @@ import { useLogContentStore } from '../stores/logContentStore';
 import { useTranslation } from 'react-i18next';
 import { invoke } from '@tauri-apps/api/core';
 import type { UnlistenFn } from '@tauri-apps/api/event';
+import { formatError } from '../utils/errors';
 
 // 全局变量，用于跟踪事件监听器是否已设置
 let isListenerSetup = false;
 
 export interface AuthMethod {
-  auth_type: 'password' | 'key';
+  auth_type: 'password' | 'key' | 'agent' | 'keyboard_interactive';
   password?: string;
   private_key_path?: string;
   passphrase?: string;
@@ -20,10 +21,12 @@ export interface SshCredentials {
   host: string;
   port?: number;
   username: string;
-  auth_type: 'password' | 'key';
+  auth_type: 'password' | 'key' | 'agent' | 'keyboard_interactive';
   password?: string;
   private_key_path?: string;
   passphrase?: string;
+  // 依次经过的跳板机，为空时使用 ~/.ssh/config 中的ProxyJump
+  jump_hosts?: SshCredentials[];
 }
 
 export interface LogStreamOptions {
@@ -55,7 +58,7 @@ export interface RemoteLog {
   message?: string;
   // SSH特有属性
   username?: string;
-  authType?: 'password' | 'key';
+  authType?: 'password' | 'key' | 'agent' | 'keyboard_interactive';
   password?: string;
   privateKeyPath?: string;
   passphrase?: string;
@@ -135,25 +138,25 @@ export function useRemoteLogHandler() {
         
         // 监听SSH日志连接成功
         const logConnectedListener = await listen('ssh-log-connected', (event: any) => {
-          const logPath = event.payload;
-          console.log('SSH日志连接成功:', logPath);
+          const { stream_id: streamId, log_path: logPath } = event.payload;
+          console.log('SSH日志连接成功:', streamId, logPath);
           
-          // 如果有活跃连接，更新其状态
-          if (activeConnectionIdRef.current) {
-            updateConnectionStatus(activeConnectionIdRef.current, 'connected');
-            updateMonitoringStatus(activeConnectionIdRef.current, true, logPath);
+          // 流id就是连接id，更新对应连接的状态
+          if (connectionsRef.current.some(conn => conn.id === streamId)) {
+            updateConnectionStatus(streamId, 'connected');
+            updateMonitoringStatus(streamId, true, logPath);
           }
         });
         unlisteners.push(logConnectedListener);
         
         // 监听SSH日志断开连接
         const logDisconnectedListener = await listen('ssh-log-disconnected', (event: any) => {
-          const logPath = event.payload;
-          console.log('SSH日志连接断开:', logPath);
+          const { stream_id: streamId, log_path: logPath } = event.payload;
+          console.log('SSH日志连接断开:', streamId, logPath);
           
           // 查找匹配的连接并更新其状态
           connectionsRef.current.forEach(conn => {
-            if (conn.logPath === logPath) {
+            if (conn.id === streamId) {
               updateConnectionStatus(conn.id, 'disconnected');
               updateMonitoringStatus(conn.id, false);
             }
@@ -161,27 +164,63 @@ export function useRemoteLogHandler() {
         });
         unlisteners.push(logDisconnectedListener);
         
-        // 监听SSH日志数据
-        const logDataListener = await listen('ssh-log-data', (event: any) => {
-          const { content: logContent, source } = event.payload;
-          console.log('收到SSH日志数据:', { source, contentLength: logContent?.length });
+        // 监听SSH日志自动重连
+        const logReconnectingListener = await listen('ssh-log-reconnecting', (event: any) => {
+          const { stream_id: streamId, attempt, delay_ms: delayMs, message } = event.payload;
+          console.warn('SSH日志连接断开，准备重连:', { streamId, attempt, delayMs, message });
           
-          if (!source || !logContent) {
-            console.warn('收到无效的日志数据:', event.payload);
+          const connection = connectionsRef.current.find(conn => conn.id === streamId);
+          if (!connection) {
             return;
           }
+          updateConnectionStatus(streamId, 'error', message);
           
-          // 查找与日志路径匹配的连接
-          const connection = connectionsRef.current.find(conn => conn.logPath === source);
+          // 只在第一次重连时提示，避免网络长时间不可用时反复弹出
+          if (attempt === 1) {
+            notifications.show({
+              title: t('remoteLogs.notification.reconnecting'),
+              message: t('remoteLogs.notification.reconnectingDetail', { name: connection.name }),
+              color: 'yellow',
+              autoClose: 3000 // 3秒后自动关闭
+            });
+          }
+        });
+        unlisteners.push(logReconnectingListener);
+        
+        // 监听SSH日志数据，后端每隔一段时间批量发送一次
+        const logDataListener = await listen('ssh-log-batch', (event: any) => {
+          const { stream_id: streamId, lines, dropped, total_dropped: totalDropped } = event.payload;
+          
+          // 查找与流id匹配的连接
+          const connection = connectionsRef.current.find(conn => conn.id === streamId);
           if (!connection) {
-            console.warn('找不到匹配的连接:', source);
+            console.warn('找不到匹配的连接:', streamId);
+            return;
+          }
+          
+          // 前端处理不过来时后端会丢弃最旧的行，第一次出现时提示用户
+          if (dropped > 0) {
+            console.warn(`日志流 ${streamId} 丢弃了 ${dropped} 行（共 ${totalDropped} 行）`);
+            if (dropped === totalDropped) {
+              notifications.show({
+                title: t('remoteLogs.notification.linesDropped'),
+                message: t('remoteLogs.notification.linesDroppedDetail', { name: connection.name }),
+                color: 'yellow',
+                autoClose: 5000
+              });
+            }
+          }
+          
+          const validLines = (lines || []).filter((line: any) => line.source && line.content);
+          if (validLines.length === 0) {
             return;
           }
+          const source = validLines[validLines.length - 1].source;
           
           // 更新内部日志状态
           setLogContent(prev => {
             const prevContent = prev[connection.id] || [];
-            const newContent = [...prevContent, logContent];
+            const newContent = [...prevContent, ...validLines.map((line: any) => line.content)];
             
             // 如果是当前活跃的连接，则更新LogContent组件
             if (connection.id === activeConnectionIdRef.current) {
@@ -201,7 +240,8 @@ export function useRemoteLogHandler() {
         
         // 监听SSH日志错误
         const logErrorListener = await listen('ssh-log-error', (event: any) => {
-          const errorMessage = event.payload;
+          const { stream_id: streamId, message, error } = event.payload;
+          const errorMessage = error ? formatError(error) : message;
           console.error('SSH日志错误:', errorMessage);
           
           notifications.show({
@@ -211,14 +251,65 @@ export function useRemoteLogHandler() {
             autoClose: 3000 // 3秒后自动关闭
           });
           
-          // 如果有活跃连接，更新其状态为错误
-          if (activeConnectionIdRef.current) {
-            updateConnectionStatus(activeConnectionIdRef.current, 'error', errorMessage);
-            updateMonitoringStatus(activeConnectionIdRef.current, false);
+          // 更新出错的连接状态为错误
+          if (connectionsRef.current.some(conn => conn.id === streamId)) {
+            updateConnectionStatus(streamId, 'error', errorMessage);
+            updateMonitoringStatus(streamId, false);
           }
         });
         unlisteners.push(logErrorListener);
         
+        // 监听未知的服务器主机密钥，用户确认指纹后保存，再重新连接
+        const hostKeyListener = await listen('ssh-host-key-unknown', async (event: any) => {
+          const { host, port, key_type: keyType, fingerprint } = event.payload;
+          console.log('未知的主机密钥:', { host, port, keyType, fingerprint });
+          
+          const trusted = window.confirm(
+            t('remoteLogs.hostKey.confirm', { host, port, keyType, fingerprint })
+          );
+          if (!trusted) {
+            return;
+          }
+          
+          try {
+            await invoke('trust_host_key', { host, port, fingerprint });
+            notifications.show({
+              title: t('remoteLogs.notification.info'),
+              message: t('remoteLogs.hostKey.trusted', { host }),
+              color: 'blue',
+              autoClose: 3000 // 3秒后自动关闭
+            });
+          } catch (error) {
+            notifications.show({
+              title: t('remoteLogs.notification.error'),
+              message: formatError(error),
+              color: 'red',
+              autoClose: 3000 // 3秒后自动关闭
+            });
+          }
+        });
+        unlisteners.push(hostKeyListener);
+        
+        // 监听键盘交互认证的提示（例如动态验证码），用户输入后返回给后端继续认证
+        const authPromptListener = await listen('ssh-auth-prompt', async (event: any) => {
+          const { request_id: requestId, host, instructions, prompts } = event.payload;
+          console.log('SSH认证提示:', { requestId, host, instructions });
+          
+          const responses: string[] = [];
+          for (const prompt of prompts as { text: string; echo: boolean }[]) {
+            const message = [host, instructions, prompt.text].filter(Boolean).join('\n');
+            const response = window.prompt(message);
+            if (response === null) {
+              // 用户取消
+              await invoke('answer_ssh_auth_prompt', { requestId, responses: null });
+              return;
+            }
+            responses.push(response);
+          }
+          await invoke('answer_ssh_auth_prompt', { requestId, responses });
+        });
+        unlisteners.push(authPromptListener);
+        
         console.log('远程日志事件监听器设置完成');
       } catch (error) {
         console.error('设置事件监听器失败:', error);
@@ -296,7 +387,8 @@ export function useRemoteLogHandler() {
         try {
           const result = await invoke('monitor_remote_log', {
             credentials: connection.credentials,
-            logPath: logPath
+            logPath: logPath,
+            streamId: connectionId
           });
           
           // 详细记录返回结果
@@ -330,11 +422,11 @@ export function useRemoteLogHandler() {
           console.error('monitor_remote_log调用失败:', error);
           notifications.show({
             title: t('remoteLogs.notification.error'),
-            message: String(error),
+            message: formatError(error),
             color: 'red',
             autoClose: 3000 // 3秒后自动关闭
           });
-          updateConnectionStatus(connectionId, 'error', String(error));
+          updateConnectionStatus(connectionId, 'error', formatError(error));
           throw error;
         }
       } else {
@@ -355,7 +447,7 @@ export function useRemoteLogHandler() {
         // 否则使用read_remote_log命令一次性获取日志内容
         const result = await invoke<{ content: string, total_lines: number }>('read_remote_log', {
           credentials: connection.credentials,
-          logPath: logPath
+          options: { log_file_path: logPath, follow: false }
         });
         
         console.log('获取到的远程日志:', { 
@@ -373,7 +465,7 @@ export function useRemoteLogHandler() {
       console.error('获取远程日志失败:', error);
       notifications.show({
         title: t('remoteLogs.notification.error'),
-        message: String(error),
+        message: formatError(error),
         color: 'red',
         autoClose: 3000 // 3秒后自动关闭
       });
@@ -399,15 +491,15 @@ export function useRemoteLogHandler() {
       // 准备SSH凭证
       const sshCredentials: SshCredentials = {
         host: log.host,
-        port: log.port || 22,
+        port: log.port,
         username: log.username || '',
         auth_type: log.authType || 'password',
-        ...(log.authType === 'password' ? 
-          { password: log.password } : 
+        ...(log.authType === 'key' ? 
           { 
             private_key_path: log.privateKeyPath,
             passphrase: log.passphrase
-          }
+          } : 
+          { password: log.password }
         )
       };
       
@@ -460,7 +552,8 @@ export function useRemoteLogHandler() {
       // 调用Rust函数开始监控日志
       await invoke('monitor_remote_log', {
         credentials: sshCredentials,
-        logPath
+        logPath,
+        streamId: log.id
       });
       
       // 连接成功
@@ -476,12 +569,12 @@ export function useRemoteLogHandler() {
       console.error('连接到远程日志失败:', error);
       
       // 更新连接状态为错误
-      updateConnectionStatus(log.id, 'error', String(error));
+      updateConnectionStatus(log.id, 'error', formatError(error));
       updateMonitoringStatus(log.id, false);
       
       notifications.show({
         title: t('remoteLogs.notification.error'),
-        message: String(error),
+        message: formatError(error),
         color: 'red',
         autoClose: 3000 // 3秒后自动关闭
       });
@@ -517,10 +610,9 @@ export function useRemoteLogHandler() {
         return;
       }
       
-      // 停止日志流 - 修复参数，传递 host 和 port
-      await invoke('stop_log_stream', { 
-        host: connection.credentials.host,
-        port: connection.credentials.port
+      // 只停止这个连接的日志流，同一服务器上的其它流不受影响
+      await invoke('stop_remote_log_monitor', { 
+        streamId: id
       });
       
       // 清空该连接的日志内容
@@ -549,7 +641,7 @@ export function useRemoteLogHandler() {
       
       notifications.show({
         title: t('remoteLogs.notification.error'),
-        message: String(error),
+        message: formatError(error),
         color: 'red',
         autoClose: 3000 // 3秒后自动关闭
       });
@@ -562,15 +654,15 @@ export function useRemoteLogHandler() {
   const convertToSshCredentials = (log: RemoteLog): SshCredentials => {
     return {
       host: log.host,
-      port: log.port || 22,
+      port: log.port,
       username: log.username || '',
       auth_type: log.authType || 'password',
-      ...(log.authType === 'password' ? 
-        { password: log.password } : 
+      ...(log.authType === 'key' ? 
         { 
           private_key_path: log.privateKeyPath,
           passphrase: log.passphrase
-        }
+        } : 
+        { password: log.password }
       )
     };
   };
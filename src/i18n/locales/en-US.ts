@@ -53,6 +53,8 @@ export default {
       password: 'Password',
       authType: 'Authentication Type',
       passwordAuth: 'Password Authentication',
+      agentAuth: 'SSH Agent',
+      keyboardInteractiveAuth: 'Keyboard-Interactive (OTP)',
       keyAuth: 'Key Authentication',
       privateKey: 'Private Key',
       privateKeyPath: 'Private Key Path',
@@ -89,8 +91,16 @@ export default {
       disconnected: 'Disconnected',
       disconnectedDetail: 'Connection to {{name}} has been closed',
       connectionError: 'Connection error',
+      reconnecting: 'Reconnecting',
+      reconnectingDetail: 'Connection to {{name}} was lost. Reconnecting automatically...',
+      linesDropped: 'Too many log lines',
+      linesDroppedDetail: 'Logs from {{name}} are arriving too fast; some older lines were dropped',
       authError: 'Authentication error',
     },
+    hostKey: {
+      confirm: 'The authenticity of host {{host}}:{{port}} can\'t be established.\n\n{{keyType}} key fingerprint:\n{{fingerprint}}\n\nAre you sure this is the right server and you want to continue connecting?',
+      trusted: 'Host key for {{host}} saved. Please connect again.',
+    },
     content: {
       title: 'Log Content',
       empty: 'No remote connections yet. Add one to get started.',
@@ -285,6 +295,23 @@ export default {
       invalidPattern: 'Invalid regex pattern'
     }
   },
+  errors: {
+    NOT_FOUND: 'File not found: {{path}}',
+    PERMISSION_DENIED: 'Permission denied: {{path}}',
+    IO: 'Failed to read {{path}}: {{reason}}',
+    CONNECTION_FAILED: 'Could not connect to {{host}}:{{port}}: {{reason}}',
+    AUTH_FAILED: 'SSH authentication failed for {{username}}@{{host}}: {{reason}}',
+    HOST_KEY_UNKNOWN: 'The host key of {{host}}:{{port}} is unknown ({{key_type}} {{fingerprint}}). Confirm the fingerprint and connect again',
+    HOST_KEY_MISMATCH: 'The host key of {{host}}:{{port}} does not match the one saved in {{known_hosts}}. Current fingerprint: {{key_type}} {{fingerprint}}. If the server key really changed, remove the saved key first',
+    TIMEOUT: 'Operation timed out: {{operation}}',
+    ENCODING: 'Unsupported encoding: {{encoding}}',
+    INVALID_INPUT: 'Invalid {{field}}: {{reason}}',
+    REMOTE_COMMAND: 'Command failed on {{host}}: {{reason}}',
+    STREAM_NOT_FOUND: 'No active log stream: {{stream_id}}',
+    PROFILE_NOT_FOUND: 'SSH profile not found: {{id}}',
+    PROFILES_LOCKED: 'SSH profiles are locked. Enter the master password first',
+    WRONG_PASSWORD: 'Incorrect master password',
+  },
   bottomMenu: {
     format: 'Format',
     size: 'Size',
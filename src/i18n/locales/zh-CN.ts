@@ -53,6 +53,8 @@ export default {
       password: '密码',
       authType: '认证类型',
       passwordAuth: '密码认证',
+      agentAuth: 'SSH Agent',
+      keyboardInteractiveAuth: '键盘交互（验证码）',
       keyAuth: '密钥认证',
       privateKey: '私钥',
       privateKeyPath: '私钥路径',
@@ -89,8 +91,16 @@ export default {
       disconnected: '已断开',
       disconnectedDetail: '与 {{name}} 的连接已关闭',
       connectionError: '连接错误',
+      reconnecting: '正在重连',
+      reconnectingDetail: '与 {{name}} 的连接已断开，正在自动重连...',
+      linesDropped: '日志过多',
+      linesDroppedDetail: '{{name}} 的日志产生过快，部分较早的行已被丢弃',
       authError: '认证错误',
     },
+    hostKey: {
+      confirm: '无法确认服务器 {{host}}:{{port}} 的身份。\n\n{{keyType}} 密钥指纹：\n{{fingerprint}}\n\n确认这是正确的服务器并继续连接吗？',
+      trusted: '已保存 {{host}} 的主机密钥，请重新连接',
+    },
     content: {
       title: '日志内容',
       empty: '还没有远程连接。添加一个开始使用。',
@@ -285,6 +295,23 @@ export default {
       invalidPattern: '无效的正则表达式'
     }
   },
+  errors: {
+    NOT_FOUND: '文件不存在: {{path}}',
+    PERMISSION_DENIED: '没有访问权限: {{path}}',
+    IO: '读取 {{path}} 失败: {{reason}}',
+    CONNECTION_FAILED: '无法连接到服务器 {{host}}:{{port}}: {{reason}}',
+    AUTH_FAILED: 'SSH认证失败 ({{username}}@{{host}}): {{reason}}',
+    HOST_KEY_UNKNOWN: '服务器 {{host}}:{{port}} 的主机密钥未知（{{key_type}} {{fingerprint}}），请确认指纹后重新连接',
+    HOST_KEY_MISMATCH: '服务器 {{host}}:{{port}} 的主机密钥与 {{known_hosts}} 中保存的不一致，可能存在中间人攻击！当前密钥指纹: {{key_type}} {{fingerprint}}。如果服务器确实更换了密钥，请先删除已保存的旧密钥',
+    TIMEOUT: '操作超时: {{operation}}',
+    ENCODING: '不支持的编码: {{encoding}}',
+    INVALID_INPUT: '参数 {{field}} 无效: {{reason}}',
+    REMOTE_COMMAND: '在 {{host}} 上执行命令失败: {{reason}}',
+    STREAM_NOT_FOUND: '未找到日志流: {{stream_id}}',
+    PROFILE_NOT_FOUND: 'SSH配置不存在: {{id}}',
+    PROFILES_LOCKED: 'SSH配置已锁定，请先输入主密码解锁',
+    WRONG_PASSWORD: '主密码不正确',
+  },
   bottomMenu: {
     format: '格式',
     size: '大小',
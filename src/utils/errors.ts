@@ -0,0 +1,27 @@
+import i18n from '../i18n';
+
+// 后端命令统一返回的错误：code是稳定的错误码，details是填入本地化提示的数据，
+// message是后端生成的说明，没有对应的翻译时直接显示
+export interface EasyLogError {
+  code: string;
+  message: string;
+  details: Record<string, unknown>;
+}
+
+export const isEasyLogError = (error: unknown): error is EasyLogError => {
+  return typeof error === 'object' && error !== null
+    && typeof (error as EasyLogError).code === 'string'
+    && typeof (error as EasyLogError).message === 'string';
+};
+
+// 把invoke抛出的错误转成可以显示给用户的文字
+export const formatError = (error: unknown): string => {
+  if (isEasyLogError(error)) {
+    const key = `errors.${error.code}`;
+    return i18n.exists(key) ? i18n.t(key, { ...error.details }) : error.message;
+  }
+  if (error instanceof Error) {
+    return error.message;
+  }
+  return String(error);
+};